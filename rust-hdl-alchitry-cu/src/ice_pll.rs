@@ -16,7 +16,6 @@
 //  OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 //
 
-use rust_hdl_core::ast::VerilogLiteral;
 use rust_hdl_core::prelude::*;
 
 #[derive(Clone, Default, Debug)]
@@ -137,20 +136,19 @@ pub struct ICE40PLLBlock<FIN: Domain, FOUT: Domain> {
     pub clock_in: Signal<In, Clock, FIN>,
     pub clock_out: Signal<Out, Clock, FOUT>,
     pub locked: Signal<Out, Bit, Async>,
-    core: ICEPLL40Core,
-    _settings: ICE40PLLSettings,
+    core: ICEPLL40Core<FIN, FOUT>,
 }
 
 impl<FIN: Domain, FOUT: Domain> ICE40PLLBlock<FIN, FOUT> {
     pub fn new() -> Self {
         let freq_in_mhz = (FIN::FREQ as f64) / (1_000_000.0);
         let freq_out_mhz = (FOUT::FREQ as f64) / (1_000_000.0);
+        let settings = analyze(true, freq_in_mhz, freq_out_mhz).unwrap();
         Self {
             clock_in: Signal::default(),
             clock_out: Signal::new_with_default(Clock(false)),
             locked: Signal::new_with_default(false),
-            core: ICEPLL40Core::new(),
-            _settings: analyze(true, freq_in_mhz, freq_out_mhz).unwrap(),
+            core: ICEPLL40Core::new(&settings),
         }
     }
 }
@@ -162,89 +160,64 @@ impl<FIN: Domain, FOUT: Domain> Default for ICE40PLLBlock<FIN, FOUT> {
 }
 
 impl<FIN: Domain, FOUT: Domain> Logic for ICE40PLLBlock<FIN, FOUT> {
-    fn update(&mut self) {}
-
-    fn connect(&mut self) {
-        self.clock_out.connect();
-        self.locked.connect();
-    }
-
-    fn hdl(&self) -> Verilog {
-        Verilog::Custom(format!(
-            "\
-SB_PLL40_CORE #(
-                .FEEDBACK_PATH(\"{feedback}\"),
-                .DIVR({DIVR}),
-                .DIVF({DIVF}),
-                .DIVQ({DIVQ}),
-                .FILTER_RANGE({FILTER_RANGE})
-               ) uut (
-                .LOCK(locked),
-                .RESETB(1'b1),
-                .BYPASS(1'b0),
-                .REFERENCECLK(clock_in),
-                .PLLOUTCORE(clock_out));
-",
-            feedback = if self._settings.simple {
-                "SIMPLE"
-            } else {
-                "NON_SIMPLE"
-            },
-            DIVR = VerilogLiteral::from(self._settings.divr as u32),
-            DIVF = VerilogLiteral::from(self._settings.divf as u32),
-            DIVQ = VerilogLiteral::from(self._settings.divq as u32),
-            FILTER_RANGE = VerilogLiteral::from(self._settings.filter_range())
-        ))
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.core.REFERENCECLK.next = self.clock_in.val();
+        self.core.RESETB.next = true.into();
+        self.core.BYPASS.next = false.into();
+        self.clock_out.next = self.core.PLLOUTCORE.val();
+        self.locked.next = self.core.LOCK.val();
     }
 }
 
+// The ports of SB_PLL40_CORE that the PLL block uses, named as in the iCE40
+// primitive.  Only the lock output is simulated.
+#[allow(non_snake_case)]
 #[derive(LogicBlock)]
-pub struct ICEPLL40Core {}
+pub struct ICEPLL40Core<FIN: Domain, FOUT: Domain> {
+    pub REFERENCECLK: Signal<In, Clock, FIN>,
+    pub PLLOUTCORE: Signal<Out, Clock, FOUT>,
+    pub LOCK: Signal<Out, Bit, Async>,
+    pub BYPASS: Signal<In, Bit, Async>,
+    pub RESETB: Signal<In, Bit, Async>,
+    pub DIVR: Constant<Bits<4>>,
+    pub DIVF: Constant<Bits<7>>,
+    pub DIVQ: Constant<Bits<3>>,
+    pub FILTER_RANGE: Constant<Bits<3>>,
+    _simple: bool,
+}
 
-impl ICEPLL40Core {
-    pub fn new() -> ICEPLL40Core {
-        Self {}
+impl<FIN: Domain, FOUT: Domain> ICEPLL40Core<FIN, FOUT> {
+    fn new(settings: &ICE40PLLSettings) -> Self {
+        Self {
+            REFERENCECLK: Signal::default(),
+            PLLOUTCORE: Signal::new_with_default(Clock(false)),
+            LOCK: Signal::new_with_default(false),
+            BYPASS: Signal::default(),
+            RESETB: Signal::default(),
+            DIVR: Constant::new((settings.divr as u32).into()),
+            DIVF: Constant::new((settings.divf as u32).into()),
+            DIVQ: Constant::new((settings.divq as u32).into()),
+            FILTER_RANGE: Constant::new((settings.filter_range() as u32).into()),
+            _simple: settings.simple,
+        }
     }
 }
 
-impl Logic for ICEPLL40Core {
-    fn update(&mut self) {}
+impl<FIN: Domain, FOUT: Domain> Logic for ICEPLL40Core<FIN, FOUT> {
+    fn update(&mut self) {
+        self.LOCK.next = true.into();
+    }
+
+    fn connect(&mut self) {
+        self.PLLOUTCORE.connect();
+        self.LOCK.connect();
+    }
 
     fn hdl(&self) -> Verilog {
-        Verilog::Blackbox(
-            r#"
-(* blackbox *)
-module SB_PLL40_CORE (
-    input   REFERENCECLK,
-    output  PLLOUTCORE,
-    output  PLLOUTGLOBAL,
-    input   EXTFEEDBACK,
-    input   [7:0] DYNAMICDELAY,
-    output  LOCK,
-    input   BYPASS,
-    input   RESETB,
-    input   LATCHINPUTVALUE,
-    output  SDO,
-    input   SDI,
-    input   SCLK
-);
-parameter FEEDBACK_PATH = "SIMPLE";
-parameter DELAY_ADJUSTMENT_MODE_FEEDBACK = "FIXED";
-parameter DELAY_ADJUSTMENT_MODE_RELATIVE = "FIXED";
-parameter SHIFTREG_DIV_MODE = 1'b0;
-parameter FDA_FEEDBACK = 4'b0000;
-parameter FDA_RELATIVE = 4'b0000;
-parameter PLLOUT_SELECT = "GENCLK";
-parameter DIVR = 4'b0000;
-parameter DIVF = 7'b0000000;
-parameter DIVQ = 3'b000;
-parameter FILTER_RANGE = 3'b000;
-parameter ENABLE_ICEGATE = 1'b0;
-parameter TEST_MODE = 1'b0;
-parameter EXTERNAL_DIVIDE_FACTOR = 1;
-endmodule
-            "#
-            .into(),
-        )
+        let feedback = if self._simple { "SIMPLE" } else { "NON_SIMPLE" };
+        BlackBox::new("SB_PLL40_CORE", self)
+            .parameter("FEEDBACK_PATH", &format!("\"{}\"", feedback))
+            .hdl()
     }
 }
@@ -0,0 +1,36 @@
+use crate::pins::{header_input, header_output};
+use rust_hdl_core::prelude::*;
+
+// The Io shield sits on banks A, B and C.  Pins are given from bit 0 up.
+
+// Three rows of 8, the top row in bits 0 to 7.  Each LED is lit when high.
+pub fn leds() -> Signal<Out, Bits<24>, Async> {
+    header_output(&[
+        "B21", "B20", "B18", "B17", "B15", "B14", "B12", "B11", "B9", "B8", "B6", "B5", "B3", "B2",
+        "A24", "A23", "A21", "A20", "A18", "A17", "A15", "A14", "A12", "A11",
+    ])
+}
+
+// Up, center, down, left and right, each high while pressed
+pub fn buttons() -> Signal<In, Bits<5>, Async> {
+    header_input(&["A2", "A3", "A5", "A6", "A8"])
+}
+
+// Three banks of 8, laid out like the LEDs.  Each switch reads high when on.
+pub fn dip_switches() -> Signal<In, Bits<24>, Async> {
+    header_input(&[
+        "B23", "B24", "B27", "B28", "B30", "B31", "B33", "B34", "B36", "B37", "B39", "B40", "B42",
+        "B43", "B45", "B46", "C2", "C3", "C5", "C6", "C8", "C9", "C11", "C12",
+    ])
+}
+
+// Segments a through g, then the decimal point.  They are shared by the four
+// digits, and each is lit when low.
+pub fn segments() -> Signal<Out, Bits<8>, Async> {
+    header_output(&["A27", "A28", "A30", "A31", "A33", "A34", "A36", "A37"])
+}
+
+// Bit 0 is the rightmost digit, which shows the segments while its select is low
+pub fn digit_selects() -> Signal<Out, Bits<4>, Async> {
+    header_output(&["A39", "A40", "A42", "A43"])
+}
@@ -1,4 +1,5 @@
 pub mod ice_pll;
+pub mod io_shield;
 pub mod pcf_gen;
 pub mod pins;
 pub mod synth;
@@ -1,26 +1,61 @@
+use rust_hdl_boards::board::{signal_at, Board, Toolchain};
 use rust_hdl_core::prelude::*;
 
 make_domain!(Mhz100, 100_000_000);
 
+pub struct AlchitryCu;
+
+impl Board for AlchitryCu {
+    const NAME: &'static str = "Alchitry Cu";
+    const TOOLCHAIN: Toolchain = Toolchain::Ice40;
+    const DEVICE: &'static str = "hx8k";
+    const PACKAGE: &'static str = "cb132";
+    fn clock_pins() -> Vec<(&'static str, u64)> {
+        vec![("P7", Mhz100::FREQ)]
+    }
+    fn led_pins() -> Vec<&'static str> {
+        vec!["J11", "K11", "K12", "K14", "L12", "L14", "M12", "N14"]
+    }
+    // The reset button, which reads low when pressed
+    fn button_pins() -> Vec<&'static str> {
+        vec!["P8"]
+    }
+}
+
 pub fn clock() -> Signal<In, Clock, Mhz100> {
-    let mut x = Signal::default();
-    x.add_location(0, "P7");
+    let mut x = signal_at(&["P7"]);
     x.connect();
     x
 }
 
 pub fn leds() -> Signal<Out, Bits<8>, Async> {
-    let mut x = Signal::default();
-    for (ndx, uname) in ["J11", "K11", "K12", "K14", "L12", "L14", "M12", "N14"]
-        .iter()
-        .enumerate()
-    {
-        x.add_location(ndx, uname);
-    }
+    signal_at(&AlchitryCu::led_pins())
+}
+
+// Reads low while pressed
+pub fn reset_button() -> Signal<In, Bit, Async> {
+    let mut x = signal_at(&AlchitryCu::button_pins());
+    x.connect();
+    x
+}
+
+fn header_pads(pins: &[&str]) -> Vec<&'static str> {
+    pins.iter().map(|x| map_alchitry_pin_to_cu_pad(x)).collect()
+}
+
+// Header pins are named as on the Alchitry connectors, e.g. A2, one per bit
+pub fn header_input<T: Synth>(pins: &[&str]) -> Signal<In, T, Async> {
+    let mut x = signal_at(&header_pads(pins));
+    x.connect();
     x
 }
 
-pub fn map_alchitry_pin_to_cu_pad(pin: &str) -> &str {
+pub fn header_output<T: Synth>(pins: &[&str]) -> Signal<Out, T, Async> {
+    signal_at(&header_pads(pins))
+}
+
+// Banks C and D only bring out the pins listed
+pub fn map_alchitry_pin_to_cu_pad(pin: &str) -> &'static str {
     match pin {
         "A2" => "M1",
 
@@ -85,8 +120,102 @@ pub fn map_alchitry_pin_to_cu_pad(pin: &str) -> &str {
         "A48" => "N1",
 
         "A49" => "P1",
+
+        "B2" => "A6",
+
+        "B3" => "A7",
+
+        "B5" => "C6",
+
+        "B6" => "C7",
+
+        "B8" => "A9",
+
+        "B9" => "A10",
+
+        "B11" => "C9",
+
+        "B12" => "C10",
+
+        "B14" => "A11",
+
+        "B15" => "A12",
+
+        "B17" => "C11",
+
+        "B18" => "C12",
+
+        "B20" => "A14",
+
+        "B21" => "C14",
+
+        "B23" => "D12",
+
+        "B24" => "D14",
+
+        "B27" => "E12",
+
+        "B28" => "E14",
+
+        "B30" => "F12",
+
+        "B31" => "F14",
+
+        "B33" => "G12",
+
+        "B34" => "G14",
+
+        "B36" => "H12",
+
+        "B37" => "H14",
+
+        "B39" => "D10",
+
+        "B40" => "D11",
+
+        "B42" => "D6",
+
+        "B43" => "D7",
+
+        "B45" => "D9",
+
+        "B46" => "E11",
+
+        "B48" => "F11",
+
+        "B49" => "G11",
+
+        "C2" => "P2",
+
+        "C3" => "M3",
+
+        "C5" => "P3",
+
+        "C6" => "M4",
+
+        "C8" => "P4",
+
+        "C9" => "M5",
+
+        "C11" => "P5",
+
+        "C12" => "M6",
+
+        "D2" => "P9",
+
+        "D3" => "M9",
+
+        "D5" => "P10",
+
+        "D6" => "M10",
+
+        "D8" => "P11",
+
+        "D9" => "M11",
+
+        "D11" => "P12",
         _ => {
-            panic!("Unknown pin");
+            panic!("Unknown pin {}", pin);
         }
     }
 }
@@ -0,0 +1,319 @@
+use crate::board::{Board, Toolchain};
+use crate::lpf_gen::generate_lpf;
+use crate::pcf_gen::{generate_pcf, PinError};
+use crate::pin_db::{chipdb_path, PinDatabase};
+use rust_hdl_core::named_path::NamedPath;
+use rust_hdl_core::prelude::{generate_verilog, lint, Atom, Block, Diagnostic, Probe, Severity};
+use rust_hdl_synth::{SynthError, TimingReport, UtilizationReport};
+use std::fs::{create_dir_all, read_to_string, remove_dir_all, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BuildLog {
+    pub stdout: String,
+    pub stderr: String,
+}
+
+#[derive(Debug)]
+pub enum BuildError {
+    DesignErrors(Vec<Diagnostic>),
+    PinErrors(Vec<PinError>),
+    MissingTool(String),
+    SynthesisFailed(BuildLog),
+    PlaceAndRouteFailed(BuildLog),
+    TimingFailed(TimingReport),
+    PackFailed(BuildLog),
+    BadReport(String),
+    IOError(std::io::Error),
+}
+
+impl From<std::io::Error> for BuildError {
+    fn from(x: std::io::Error) -> Self {
+        BuildError::IOError(x)
+    }
+}
+
+impl From<SynthError> for BuildError {
+    fn from(x: SynthError) -> Self {
+        match x {
+            SynthError::BadReport(x) => BuildError::BadReport(x),
+            SynthError::IOError(x) => BuildError::IOError(x),
+            x => BuildError::BadReport(format!("{:?}", x)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BitstreamArtifacts {
+    pub dir: PathBuf,
+    pub bitstream: PathBuf,
+    // Of the flattened design, so it only has the top module
+    pub utilization: UtilizationReport,
+    pub timing: TimingReport,
+    pub synth_log: BuildLog,
+    pub pnr_log: BuildLog,
+    pub pack_log: BuildLog,
+    // Set when the inputs were unchanged, and the previous build was kept
+    pub cached: bool,
+}
+
+fn save_log(log: &BuildLog, dir: &Path, basename: &str) -> Result<(), std::io::Error> {
+    let mut out_file = File::create(dir.join(format!("{}.out", basename)))?;
+    write!(out_file, "{}", log.stdout)?;
+    let mut err_file = File::create(dir.join(format!("{}.err", basename)))?;
+    write!(err_file, "{}", log.stderr)?;
+    Ok(())
+}
+
+fn load_log(dir: &Path, basename: &str) -> Result<BuildLog, std::io::Error> {
+    Ok(BuildLog {
+        stdout: read_to_string(dir.join(format!("{}.out", basename)))?,
+        stderr: read_to_string(dir.join(format!("{}.err", basename)))?,
+    })
+}
+
+// Runs one step of the flow and saves its log.  A failed step's log goes into the
+// error that fail makes.
+fn run_tool(
+    dir: &Path,
+    tool: &str,
+    args: &[String],
+    basename: &str,
+    fail: fn(BuildLog) -> BuildError,
+) -> Result<BuildLog, BuildError> {
+    let output = match Command::new(tool).current_dir(dir).args(args).output() {
+        Err(x) if x.kind() == ErrorKind::NotFound => {
+            return Err(BuildError::MissingTool(tool.into()))
+        }
+        x => x?,
+    };
+    let log = BuildLog {
+        stdout: String::from_utf8_lossy(&output.stdout).into(),
+        stderr: String::from_utf8_lossy(&output.stderr).into(),
+    };
+    save_log(&log, dir, basename)?;
+    if output.status.success() {
+        Ok(log)
+    } else {
+        Err(fail(log))
+    }
+}
+
+#[derive(Default)]
+struct ClockFinder {
+    path: NamedPath,
+    namespace: NamedPath,
+    clocks: Vec<(String, u64)>,
+}
+
+impl Probe for ClockFinder {
+    fn visit_start_scope(&mut self, name: &str, _node: &dyn Block) {
+        self.path.push(name);
+        self.namespace.reset();
+    }
+    fn visit_start_namespace(&mut self, name: &str, _node: &dyn Block) {
+        self.namespace.push(name);
+    }
+    fn visit_atom(&mut self, name: &str, signal: &dyn Atom) {
+        if self.path.len() == 1 {
+            if let Some(freq) = signal.clock_frequency() {
+                let namespace = self.namespace.flat("_");
+                let name = if namespace.is_empty() {
+                    name.to_owned()
+                } else {
+                    format!("{}_{}", namespace, name)
+                };
+                self.clocks.push((name, freq));
+            }
+        }
+    }
+    fn visit_end_namespace(&mut self, _name: &str, _node: &dyn Block) {
+        self.namespace.pop();
+    }
+    fn visit_end_scope(&mut self, _name: &str, _node: &dyn Block) {
+        self.path.pop();
+    }
+}
+
+// The clock inputs of the top level, with the frequencies of their domains.  Clocks
+// derived inside the design (e.g. by a PLL) are constrained by nextpnr itself.
+fn top_level_clocks<U: Block>(uut: &U) -> Vec<(String, u64)> {
+    let mut finder = ClockFinder::default();
+    uut.accept("top", &mut finder);
+    finder.clocks
+}
+
+fn mhz(freq: u64) -> f64 {
+    freq as f64 / 1.0e6
+}
+
+// Everything a build is made from, as one text that is kept with the build.  Each
+// part is headed by its name and length, so no two sets of inputs give the same text.
+fn build_inputs(parts: &[(&str, &str)]) -> String {
+    parts
+        .iter()
+        .map(|(name, text)| format!("-- {} ({} bytes)\n{}\n", name, text.len(), text))
+        .collect()
+}
+
+// The previous build in the directory, if it was made from the same inputs
+fn cached_build(dir: &Path, toolchain: Toolchain, inputs: &str) -> Option<BitstreamArtifacts> {
+    if read_to_string(dir.join("inputs.txt")).ok()? != inputs {
+        return None;
+    }
+    Some(BitstreamArtifacts {
+        dir: dir.into(),
+        bitstream: dir.join(toolchain.bitstream_file()),
+        utilization: UtilizationReport::from_stat_json(
+            &read_to_string(dir.join("stat.json")).ok()?,
+        )
+        .ok()?,
+        timing: TimingReport::from_nextpnr_json(&read_to_string(dir.join("report.json")).ok()?)
+            .ok()?,
+        synth_log: load_log(dir, "yosys_synth").ok()?,
+        pnr_log: load_log(dir, "nextpnr").ok()?,
+        pack_log: load_log(dir, "pack").ok()?,
+        cached: true,
+    })
+    .filter(|x| x.bitstream.exists())
+}
+
+pub fn generate_bitstream<B: Board, U: Block>(
+    mut uut: U,
+    prefix: &str,
+) -> Result<BitstreamArtifacts, BuildError> {
+    uut.connect_all();
+    let diagnostics = lint(&uut);
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+    let errors = diagnostics
+        .into_iter()
+        .filter(|x| x.severity == Severity::Error)
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        return Err(BuildError::DesignErrors(errors));
+    }
+    let toolchain = B::TOOLCHAIN;
+    let verilog_text = generate_verilog(&uut);
+    let constraints_text = match toolchain {
+        Toolchain::Ice40 => {
+            // An installed chipdb takes precedence over the pins built into the crate
+            let pins = match chipdb_path(B::DEVICE) {
+                Some(chipdb) => PinDatabase::from_chipdb(&read_to_string(chipdb)?, B::PACKAGE)
+                    .map_err(BuildError::BadReport)?,
+                None => PinDatabase::builtin(B::PACKAGE).ok_or_else(|| {
+                    BuildError::MissingTool(format!("icebox chipdb for {}", B::DEVICE))
+                })?,
+            };
+            generate_pcf(&uut, &pins).map_err(BuildError::PinErrors)?
+        }
+        Toolchain::Ecp5 => generate_lpf(&uut),
+    };
+    let clocks = top_level_clocks(&uut);
+    let clocks_text = clocks
+        .iter()
+        .map(|(name, freq)| format!("ctx.addClock(\"{}\", {})\n", name, mhz(*freq)))
+        .collect::<String>();
+    let synth_args = vec![
+        "-p".to_string(),
+        format!(
+            "{}; tee -q -o stat.json stat -json",
+            toolchain.synth_command()
+        ),
+        "top.v".into(),
+    ];
+    // Unconstrained clocks get the fastest domain's frequency, or the board's fastest
+    // oscillator's.  Timing failures are reported below, with every clock's Fmax,
+    // rather than by nextpnr.
+    let freq = clocks
+        .iter()
+        .map(|x| x.1)
+        .max()
+        .or_else(|| B::clock_pins().iter().map(|x| x.1).max())
+        .unwrap_or(12_000_000);
+    let mut pnr_args = toolchain.pnr_args(B::DEVICE, B::PACKAGE);
+    pnr_args.extend(
+        [
+            "--pre-pack",
+            "clocks.py",
+            "--report",
+            "report.json",
+            "--timing-allow-fail",
+            "--freq",
+            &format!("{}", mhz(freq)),
+        ]
+        .iter()
+        .map(|x| x.to_string()),
+    );
+    let pack_args = vec![
+        toolchain.routed_file().to_string(),
+        toolchain.bitstream_file().into(),
+    ];
+    let dir = PathBuf::from(prefix);
+    let inputs = build_inputs(&[
+        ("device", B::DEVICE),
+        ("package", B::PACKAGE),
+        ("top.v", &verilog_text),
+        (toolchain.constraint_file(), &constraints_text),
+        ("clocks.py", &clocks_text),
+        ("yosys", &synth_args.join(" ")),
+        (toolchain.pnr_tool(), &pnr_args.join(" ")),
+        (toolchain.pack_tool(), &pack_args.join(" ")),
+    ]);
+    if let Some(artifacts) = cached_build(&dir, toolchain, &inputs) {
+        println!("Inputs are unchanged, keeping the bitstream in {}", prefix);
+        return Ok(artifacts);
+    }
+    let _ = remove_dir_all(&dir);
+    create_dir_all(&dir)?;
+    write!(File::create(dir.join("top.v"))?, "{}", verilog_text)?;
+    write!(
+        File::create(dir.join(toolchain.constraint_file()))?,
+        "{}",
+        constraints_text
+    )?;
+    write!(File::create(dir.join("clocks.py"))?, "{}", clocks_text)?;
+    let synth_log = run_tool(
+        &dir,
+        "yosys",
+        &synth_args,
+        "yosys_synth",
+        BuildError::SynthesisFailed,
+    )?;
+    let utilization = UtilizationReport::from_stat_json(&read_to_string(dir.join("stat.json"))?)?;
+    println!("{}", utilization);
+    let pnr_log = run_tool(
+        &dir,
+        toolchain.pnr_tool(),
+        &pnr_args,
+        "nextpnr",
+        BuildError::PlaceAndRouteFailed,
+    )?;
+    let timing = TimingReport::from_nextpnr_json(&read_to_string(dir.join("report.json"))?)?;
+    println!("{}", timing);
+    if !timing.failing().is_empty() {
+        return Err(BuildError::TimingFailed(timing));
+    }
+    let pack_log = run_tool(
+        &dir,
+        toolchain.pack_tool(),
+        &pack_args,
+        "pack",
+        BuildError::PackFailed,
+    )?;
+    // Only a complete build is reused
+    write!(File::create(dir.join("inputs.txt"))?, "{}", inputs)?;
+    Ok(BitstreamArtifacts {
+        bitstream: dir.join(toolchain.bitstream_file()),
+        dir,
+        utilization,
+        timing,
+        synth_log,
+        pnr_log,
+        pack_log,
+        cached: false,
+    })
+}
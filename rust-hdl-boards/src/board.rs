@@ -0,0 +1,95 @@
+use rust_hdl_core::direction::Direction;
+use rust_hdl_core::prelude::*;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Toolchain {
+    // yosys, nextpnr-ice40 and icepack, with the pins in a PCF file
+    Ice40,
+    // yosys, nextpnr-ecp5 and ecppack, with the pins in an LPF file
+    Ecp5,
+}
+
+impl Toolchain {
+    pub fn synth_command(&self) -> &'static str {
+        match self {
+            Toolchain::Ice40 => "synth_ice40 -top top -json top.json",
+            Toolchain::Ecp5 => "synth_ecp5 -top top -json top.json",
+        }
+    }
+
+    pub fn pnr_tool(&self) -> &'static str {
+        match self {
+            Toolchain::Ice40 => "nextpnr-ice40",
+            Toolchain::Ecp5 => "nextpnr-ecp5",
+        }
+    }
+
+    pub fn constraint_file(&self) -> &'static str {
+        match self {
+            Toolchain::Ice40 => "top.pcf",
+            Toolchain::Ecp5 => "top.lpf",
+        }
+    }
+
+    // The placed and routed design, before it is packed into a bitstream
+    pub fn routed_file(&self) -> &'static str {
+        match self {
+            Toolchain::Ice40 => "top.asc",
+            Toolchain::Ecp5 => "top.config",
+        }
+    }
+
+    pub fn pnr_args(&self, device: &str, package: &str) -> Vec<String> {
+        let (constraints, routed) = match self {
+            Toolchain::Ice40 => ("--pcf", "--asc"),
+            Toolchain::Ecp5 => ("--lpf", "--textcfg"),
+        };
+        vec![
+            format!("--{}", device),
+            "--package".into(),
+            package.into(),
+            "--json".into(),
+            "top.json".into(),
+            constraints.into(),
+            self.constraint_file().into(),
+            routed.into(),
+            self.routed_file().into(),
+        ]
+    }
+
+    pub fn pack_tool(&self) -> &'static str {
+        match self {
+            Toolchain::Ice40 => "icepack",
+            Toolchain::Ecp5 => "ecppack",
+        }
+    }
+
+    pub fn bitstream_file(&self) -> &'static str {
+        match self {
+            Toolchain::Ice40 => "top.bin",
+            Toolchain::Ecp5 => "top.bit",
+        }
+    }
+}
+
+pub trait Board {
+    const NAME: &'static str;
+    const TOOLCHAIN: Toolchain;
+    // As nextpnr spells them, e.g. hx8k and cb132, or 85k and CABGA381
+    const DEVICE: &'static str;
+    const PACKAGE: &'static str;
+    // The on-board oscillators, with their frequencies in Hz
+    fn clock_pins() -> Vec<(&'static str, u64)>;
+    fn led_pins() -> Vec<&'static str>;
+    fn button_pins() -> Vec<&'static str>;
+}
+
+// A top level signal on the given pins, one per bit
+pub fn signal_at<D: Direction, T: Synth, F: Domain>(pins: &[&str]) -> Signal<D, T, F> {
+    assert_eq!(pins.len(), T::BITS);
+    let mut x = Signal::default();
+    for (ndx, pin) in pins.iter().enumerate() {
+        x.add_location(ndx, pin);
+    }
+    x
+}
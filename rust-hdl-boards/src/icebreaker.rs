@@ -0,0 +1,40 @@
+use crate::board::{signal_at, Board, Toolchain};
+use rust_hdl_core::prelude::*;
+
+make_domain!(Mhz12, 12_000_000);
+
+pub struct IceBreaker;
+
+impl Board for IceBreaker {
+    const NAME: &'static str = "iCEBreaker";
+    const TOOLCHAIN: Toolchain = Toolchain::Ice40;
+    const DEVICE: &'static str = "up5k";
+    const PACKAGE: &'static str = "sg48";
+    fn clock_pins() -> Vec<(&'static str, u64)> {
+        vec![("35", Mhz12::FREQ)]
+    }
+    // Red, then green.  Both are lit by a low output.
+    fn led_pins() -> Vec<&'static str> {
+        vec!["11", "37"]
+    }
+    // Reads low when pressed
+    fn button_pins() -> Vec<&'static str> {
+        vec!["10"]
+    }
+}
+
+pub fn clock() -> Signal<In, Clock, Mhz12> {
+    let mut x = signal_at(&["35"]);
+    x.connect();
+    x
+}
+
+pub fn leds_n() -> Signal<Out, Bits<2>, Async> {
+    signal_at(&IceBreaker::led_pins())
+}
+
+pub fn button_n() -> Signal<In, Bit, Async> {
+    let mut x = signal_at(&IceBreaker::button_pins());
+    x.connect();
+    x
+}
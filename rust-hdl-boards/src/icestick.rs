@@ -0,0 +1,33 @@
+use crate::board::{signal_at, Board, Toolchain};
+use rust_hdl_core::prelude::*;
+
+make_domain!(Mhz12, 12_000_000);
+
+pub struct IceStick;
+
+impl Board for IceStick {
+    const NAME: &'static str = "iCEstick";
+    const TOOLCHAIN: Toolchain = Toolchain::Ice40;
+    const DEVICE: &'static str = "hx1k";
+    const PACKAGE: &'static str = "tq144";
+    fn clock_pins() -> Vec<(&'static str, u64)> {
+        vec![("21", Mhz12::FREQ)]
+    }
+    // The four red LEDs around the edge, then the green one in the middle
+    fn led_pins() -> Vec<&'static str> {
+        vec!["99", "98", "97", "96", "95"]
+    }
+    fn button_pins() -> Vec<&'static str> {
+        vec![]
+    }
+}
+
+pub fn clock() -> Signal<In, Clock, Mhz12> {
+    let mut x = signal_at(&["21"]);
+    x.connect();
+    x
+}
+
+pub fn leds() -> Signal<Out, Bits<5>, Async> {
+    signal_at(&IceStick::led_pins())
+}
@@ -0,0 +1,10 @@
+pub mod bitstream;
+pub mod board;
+pub mod icebreaker;
+pub mod icestick;
+pub mod lpf_gen;
+pub mod pcf_gen;
+pub mod pin_db;
+pub mod pin_tables;
+pub mod tinyfpga_bx;
+pub mod ulx3s;
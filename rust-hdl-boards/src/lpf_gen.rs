@@ -0,0 +1,72 @@
+use rust_hdl_core::named_path::NamedPath;
+use rust_hdl_core::prelude::*;
+
+#[derive(Default)]
+struct LPFGenerator {
+    path: NamedPath,
+    namespace: NamedPath,
+    lpf: Vec<String>,
+}
+
+fn io_type(kind: &SignalType) -> String {
+    match kind {
+        SignalType::LowVoltageCMOS_1v8 => "LVCMOS18".into(),
+        SignalType::LowVoltageCMOS_3v3 => "LVCMOS33".into(),
+        SignalType::StubSeriesTerminatedLogic_II => "SSTL18_II".into(),
+        SignalType::DifferentialStubSeriesTerminatedLogic_II => "SSTL18D_II".into(),
+        SignalType::Custom(s) => s.clone(),
+        _ => panic!("Signal type {:?} is unsupported on the ECP5!", kind),
+    }
+}
+
+impl Probe for LPFGenerator {
+    fn visit_start_scope(&mut self, name: &str, _node: &dyn Block) {
+        self.path.push(name);
+        self.namespace.reset();
+    }
+    fn visit_start_namespace(&mut self, name: &str, _node: &dyn Block) {
+        self.namespace.push(name);
+    }
+    fn visit_atom(&mut self, name: &str, signal: &dyn Atom) {
+        if self.path.len() == 1 {
+            let namespace = self.namespace.flat("_");
+            let name = if namespace.is_empty() {
+                name.to_owned()
+            } else {
+                format!("{}_{}", namespace, name)
+            };
+            for pin in &signal.constraints() {
+                let port = if signal.bits() == 1 {
+                    name.clone()
+                } else {
+                    format!("{}[{}]", name, pin.index)
+                };
+                match &pin.constraint {
+                    Constraint::Location(l) => self
+                        .lpf
+                        .push(format!("LOCATE COMP \"{}\" SITE \"{}\";", port, l)),
+                    Constraint::Kind(k) => {
+                        self.lpf
+                            .push(format!("IOBUF PORT \"{}\" IO_TYPE={};", port, io_type(k)))
+                    }
+                    Constraint::Custom(s) => self.lpf.push(s.clone()),
+                    _ => {
+                        panic!("Pin constraint type {:?} is unsupported!", pin.constraint)
+                    }
+                }
+            }
+        }
+    }
+    fn visit_end_namespace(&mut self, _name: &str, _node: &dyn Block) {
+        self.namespace.pop();
+    }
+    fn visit_end_scope(&mut self, _name: &str, _node: &dyn Block) {
+        self.path.pop();
+    }
+}
+
+pub fn generate_lpf<U: Block>(uut: &U) -> String {
+    let mut lpf = LPFGenerator::default();
+    uut.accept("top", &mut lpf);
+    lpf.lpf.join("\n") + "\n"
+}
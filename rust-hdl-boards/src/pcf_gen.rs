@@ -0,0 +1,111 @@
+use crate::pin_db::PinDatabase;
+use rust_hdl_core::named_path::NamedPath;
+use rust_hdl_core::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PinError {
+    Illegal { port: String, pin: String },
+    Duplicate { pin: String, ports: Vec<String> },
+}
+
+impl Display for PinError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PinError::Illegal { port, pin } => {
+                write!(
+                    f,
+                    "{} is assigned to {}, which is not an I/O pin",
+                    port, pin
+                )
+            }
+            PinError::Duplicate { pin, ports } => {
+                write!(f, "{} is assigned to {}", pin, ports.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct PCFGenerator {
+    path: NamedPath,
+    namespace: NamedPath,
+    pcf: Vec<String>,
+    locations: Vec<(String, String)>,
+}
+
+impl Probe for PCFGenerator {
+    fn visit_start_scope(&mut self, name: &str, _node: &dyn Block) {
+        let _top_level = self.path.to_string();
+        self.path.push(name);
+        self.namespace.reset();
+    }
+    fn visit_start_namespace(&mut self, name: &str, _node: &dyn Block) {
+        self.namespace.push(name);
+    }
+    fn visit_atom(&mut self, name: &str, signal: &dyn Atom) {
+        if self.path.len() == 1 {
+            let namespace = self.namespace.flat("_");
+            let name = if namespace.is_empty() {
+                name.to_owned()
+            } else {
+                format!("{}_{}", namespace, name)
+            };
+            for pin in &signal.constraints() {
+                match &pin.constraint {
+                    Constraint::Location(l) => {
+                        let port = if signal.bits() == 1 {
+                            name.clone()
+                        } else {
+                            format!("{}[{}]", name, pin.index)
+                        };
+                        self.pcf.push(format!("set_io {} {}", port, l));
+                        self.locations.push((port, l.clone()));
+                    }
+                    Constraint::Custom(s) => self.pcf.push(s.clone()),
+                    _ => {
+                        panic!("Pin constraint type {:?} is unsupported!", pin.constraint)
+                    }
+                }
+            }
+        }
+    }
+    fn visit_end_namespace(&mut self, _name: &str, _node: &dyn Block) {
+        self.namespace.pop();
+    }
+    fn visit_end_scope(&mut self, _name: &str, _node: &dyn Block) {
+        self.path.pop();
+    }
+}
+
+// Every location must be an I/O pin of the package, and no pin can be used twice.
+// A table that may leave pins out can only say which pins are I/Os.
+pub fn generate_pcf<U: Block>(uut: &U, pins: &PinDatabase) -> Result<String, Vec<PinError>> {
+    let mut pcf = PCFGenerator::default();
+    uut.accept("top", &mut pcf);
+    let mut errors = vec![];
+    let mut ports_by_pin: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (port, pin) in &pcf.locations {
+        if pins.complete && pins.pin(pin).is_none() {
+            errors.push(PinError::Illegal {
+                port: port.clone(),
+                pin: pin.clone(),
+            });
+        }
+        ports_by_pin.entry(pin).or_default().push(port.clone());
+    }
+    for (pin, ports) in ports_by_pin {
+        if ports.len() > 1 {
+            errors.push(PinError::Duplicate {
+                pin: pin.into(),
+                ports,
+            });
+        }
+    }
+    if errors.is_empty() {
+        Ok(pcf.pcf.join("\n") + "\n")
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,208 @@
+use crate::pin_tables::{CB132, CM81, SG48, TQ144};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackagePin {
+    pub name: String,
+    // 0 is the top edge of the die, then clockwise
+    pub bank: usize,
+    // The global network the pin can drive directly
+    pub global_buffer: Option<usize>,
+    // The pin is shared with a PLL output, and is lost to I/O when that PLL is used
+    pub pll: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PinDatabase {
+    pub package: String,
+    // Every I/O of the package is listed, so a pin that is missing is not an I/O
+    pub complete: bool,
+    pins: BTreeMap<String, PackagePin>,
+}
+
+// A pin of a built-in table, as (pin, bank, global buffer, PLL output)
+pub type PinEntry = (&'static str, usize, Option<usize>, bool);
+
+pub struct PinTable {
+    pub package: &'static str,
+    // The table was generated from an icebox chipdb, rather than written by hand
+    pub complete: bool,
+    pub pins: &'static [PinEntry],
+}
+
+type Site = (usize, usize, usize);
+
+fn site(fields: &[&str]) -> Option<Site> {
+    match fields {
+        [x, y, z, ..] => Some((x.parse().ok()?, y.parse().ok()?, z.parse().ok()?)),
+        _ => None,
+    }
+}
+
+impl PinDatabase {
+    // Reads the pins of one package from an icebox chipdb, e.g. chipdb-8k.txt
+    pub fn from_chipdb(chipdb: &str, package: &str) -> Result<PinDatabase, String> {
+        let mut section = vec![];
+        let mut io_tiles = vec![];
+        let mut package_pins = vec![];
+        let mut global_buffers = BTreeMap::new();
+        let mut pll_sites = vec![];
+        for line in chipdb.lines() {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            if fields.is_empty() || fields[0].starts_with('#') {
+                continue;
+            }
+            if fields[0].starts_with('.') {
+                section = fields.iter().map(|x| x.to_string()).collect();
+                if fields[0] == ".io_tile" {
+                    let x = fields.get(1).and_then(|x| x.parse::<usize>().ok());
+                    let y = fields.get(2).and_then(|x| x.parse::<usize>().ok());
+                    if let (Some(x), Some(y)) = (x, y) {
+                        io_tiles.push((x, y));
+                    }
+                }
+                continue;
+            }
+            let bad_line = || format!("Bad line in chipdb: {}", line);
+            match section.first().map(|x| x.as_str()) {
+                Some(".pins")
+                    if section
+                        .get(1)
+                        .is_some_and(|x| x.eq_ignore_ascii_case(package)) =>
+                {
+                    package_pins.push((
+                        fields[0].to_string(),
+                        site(&fields[1..]).ok_or_else(bad_line)?,
+                    ));
+                }
+                Some(".gbufpin") => {
+                    let glb = fields.get(3).and_then(|x| x.parse().ok());
+                    global_buffers.insert(
+                        site(&fields).ok_or_else(bad_line)?,
+                        glb.ok_or_else(bad_line)?,
+                    );
+                }
+                Some(".extra_cell")
+                    if section.last().map(|x| x.as_str()) == Some("PLL")
+                        && (fields[0] == "PLLOUT_A" || fields[0] == "PLLOUT_B") =>
+                {
+                    pll_sites.push(site(&fields[1..]).ok_or_else(bad_line)?);
+                }
+                _ => {}
+            }
+        }
+        if package_pins.is_empty() {
+            return Err(format!("Package {} is not in the chipdb", package));
+        }
+        let max_x = io_tiles.iter().map(|x| x.0).max().unwrap_or(0);
+        let max_y = io_tiles.iter().map(|x| x.1).max().unwrap_or(0);
+        let pins = package_pins
+            .into_iter()
+            .map(|(name, (x, y, z))| {
+                let bank = if y == max_y {
+                    0
+                } else if x == max_x {
+                    1
+                } else if y == 0 {
+                    2
+                } else {
+                    3
+                };
+                let pin = PackagePin {
+                    name: name.clone(),
+                    bank,
+                    global_buffer: global_buffers.get(&(x, y, z)).cloned(),
+                    pll: pll_sites.contains(&(x, y, z)),
+                };
+                (name, pin)
+            })
+            .collect();
+        Ok(PinDatabase {
+            package: package.into(),
+            complete: true,
+            pins,
+        })
+    }
+
+    // The pins built into the crate, for when icebox is not installed
+    pub fn builtin(package: &str) -> Option<PinDatabase> {
+        let table = [TQ144, SG48, CM81, CB132]
+            .iter()
+            .find(|x| x.package.eq_ignore_ascii_case(package))?;
+        let pins = table
+            .pins
+            .iter()
+            .map(|(name, bank, global_buffer, pll)| {
+                let pin = PackagePin {
+                    name: name.to_string(),
+                    bank: *bank,
+                    global_buffer: *global_buffer,
+                    pll: *pll,
+                };
+                (name.to_string(), pin)
+            })
+            .collect();
+        Some(PinDatabase {
+            package: package.into(),
+            complete: table.complete,
+            pins,
+        })
+    }
+
+    // The pins as a built-in table, as pin_tables.rs has them
+    pub fn table_source(&self, name: &str, comment: &str) -> String {
+        let mut pins = self.pins.values().collect::<Vec<_>>();
+        // Ball names sort by row, then by column as a number
+        pins.sort_by_key(|x| {
+            let column = x.name.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+            (
+                x.name[..x.name.len() - column.len()].to_string(),
+                column.parse::<usize>().unwrap_or(0),
+            )
+        });
+        let mut code = format!(
+            "// {}\npub const {}: PinTable = PinTable {{\n    package: \"{}\",\n    complete: true,\n    pins: &[\n",
+            comment,
+            name,
+            self.package.to_ascii_lowercase()
+        );
+        for pin in pins {
+            let _ = writeln!(
+                code,
+                "        (\"{}\", {}, {:?}, {}),",
+                pin.name, pin.bank, pin.global_buffer, pin.pll
+            );
+        }
+        code + "    ],\n};\n"
+    }
+
+    pub fn pin(&self, name: &str) -> Option<&PackagePin> {
+        self.pins.get(name)
+    }
+
+    pub fn pins(&self) -> impl Iterator<Item = &PackagePin> {
+        self.pins.values()
+    }
+}
+
+// Where icestorm installed the chipdb for the device (e.g. hx8k), if it did.  The
+// ICEBOX environment variable overrides the usual places.
+pub fn chipdb_path(device: &str) -> Option<PathBuf> {
+    // The 4k parts are 8k dies
+    let size = match device.trim_start_matches(|x: char| x.is_ascii_alphabetic()) {
+        "4k" => "8k",
+        x => x,
+    };
+    let name = format!("chipdb-{}.txt", size);
+    std::env::var("ICEBOX")
+        .ok()
+        .into_iter()
+        .chain(vec![
+            "/usr/local/share/icebox".to_string(),
+            "/usr/share/icebox".to_string(),
+        ])
+        .map(|dir| PathBuf::from(dir).join(&name))
+        .find(|path| path.exists())
+}
@@ -0,0 +1,331 @@
+// The I/O pins of the packages that the boards here use, so that pin assignments
+// can be checked without icebox.  With icebox installed, regenerate_pin_tables in
+// rust-hdl-test writes this file from its chipdbs.  Until then the tables are
+// written by hand, may leave pins out, and have no global buffer or PLL data where
+// it was not known.
+use crate::pin_db::PinTable;
+
+// iCE40HX1K, as on the iCEstick
+pub const TQ144: PinTable = PinTable {
+    package: "tq144",
+    complete: false,
+    pins: &[
+        ("1", 3, None, false),
+        ("2", 3, None, false),
+        ("3", 3, None, false),
+        ("4", 3, None, false),
+        ("7", 3, None, false),
+        ("8", 3, None, false),
+        ("9", 3, None, false),
+        ("10", 3, None, false),
+        ("11", 3, None, false),
+        ("12", 3, None, false),
+        ("19", 3, None, false),
+        ("20", 3, Some(7), false),
+        ("21", 3, Some(6), false),
+        ("22", 3, None, false),
+        ("23", 3, None, false),
+        ("24", 3, None, false),
+        ("25", 3, None, false),
+        ("26", 3, None, false),
+        ("28", 3, None, false),
+        ("29", 3, None, false),
+        ("31", 3, None, false),
+        ("32", 3, None, false),
+        ("33", 3, None, false),
+        ("34", 3, None, false),
+        ("37", 2, None, false),
+        ("38", 2, None, false),
+        ("39", 2, None, false),
+        ("41", 2, None, false),
+        ("42", 2, None, false),
+        ("43", 2, None, false),
+        ("44", 2, None, false),
+        ("45", 2, None, false),
+        ("47", 2, None, false),
+        ("48", 2, None, false),
+        ("49", 2, Some(5), false),
+        ("50", 2, Some(4), false),
+        ("52", 2, None, false),
+        ("56", 2, None, false),
+        ("58", 2, None, false),
+        ("60", 2, None, false),
+        ("61", 2, None, false),
+        ("62", 2, None, false),
+        ("63", 2, None, false),
+        ("64", 2, None, false),
+        ("67", 2, None, false),
+        ("68", 2, None, false),
+        ("70", 2, None, false),
+        ("71", 2, None, false),
+        ("73", 1, None, false),
+        ("74", 1, None, false),
+        ("75", 1, None, false),
+        ("76", 1, None, false),
+        ("78", 1, None, false),
+        ("79", 1, None, false),
+        ("80", 1, None, false),
+        ("81", 1, None, false),
+        ("87", 1, None, false),
+        ("88", 1, None, false),
+        ("90", 1, None, false),
+        ("91", 1, None, false),
+        ("93", 1, Some(3), false),
+        ("94", 1, Some(2), false),
+        ("95", 1, None, false),
+        ("96", 1, None, false),
+        ("97", 1, None, false),
+        ("98", 1, None, false),
+        ("99", 1, None, false),
+        ("101", 1, None, false),
+        ("102", 1, None, false),
+        ("104", 1, None, false),
+        ("105", 1, None, false),
+        ("106", 1, None, false),
+        ("107", 1, None, false),
+        ("112", 0, None, false),
+        ("113", 0, None, false),
+        ("114", 0, None, false),
+        ("115", 0, None, false),
+        ("116", 0, None, false),
+        ("117", 0, None, false),
+        ("118", 0, None, false),
+        ("119", 0, None, false),
+        ("120", 0, None, false),
+        ("121", 0, None, false),
+        ("122", 0, None, false),
+        ("128", 0, Some(1), false),
+        ("129", 0, Some(0), false),
+        ("134", 0, None, false),
+        ("135", 0, None, false),
+        ("136", 0, None, false),
+        ("137", 0, None, false),
+        ("138", 0, None, false),
+        ("139", 0, None, false),
+        ("141", 0, None, false),
+        ("142", 0, None, false),
+        ("143", 0, None, false),
+        ("144", 0, None, false),
+    ],
+};
+
+// iCE40UP5K, as on the iCEBreaker
+pub const SG48: PinTable = PinTable {
+    package: "sg48",
+    complete: false,
+    pins: &[
+        ("2", 3, None, false),
+        ("3", 3, None, false),
+        ("4", 3, None, false),
+        ("6", 3, None, false),
+        ("9", 3, None, false),
+        ("10", 3, None, false),
+        ("11", 3, None, false),
+        ("12", 3, None, false),
+        ("13", 2, None, false),
+        ("14", 2, None, false),
+        ("15", 2, None, false),
+        ("16", 2, None, false),
+        ("17", 2, None, false),
+        ("18", 2, None, false),
+        ("19", 2, None, false),
+        ("20", 2, Some(3), false),
+        ("21", 2, None, false),
+        ("23", 2, None, false),
+        ("25", 1, None, false),
+        ("26", 1, None, false),
+        ("27", 1, None, false),
+        ("28", 1, None, false),
+        ("31", 1, None, false),
+        ("32", 1, None, false),
+        ("34", 1, None, false),
+        ("35", 1, Some(0), false),
+        ("36", 1, None, false),
+        ("37", 0, Some(1), false),
+        ("38", 0, None, false),
+        ("39", 0, None, false),
+        ("40", 0, None, false),
+        ("41", 0, None, false),
+        ("42", 0, None, false),
+        ("43", 0, None, false),
+        ("44", 0, Some(6), false),
+        ("45", 0, None, false),
+        ("46", 0, None, false),
+        ("47", 0, None, false),
+        ("48", 0, None, false),
+    ],
+};
+
+// iCE40LP8K, as on the TinyFPGA BX
+pub const CM81: PinTable = PinTable {
+    package: "cm81",
+    complete: false,
+    pins: &[
+        ("A1", 0, None, false),
+        ("A2", 0, None, false),
+        ("A3", 0, None, false),
+        ("A4", 0, None, false),
+        ("A6", 0, None, false),
+        ("A7", 0, None, false),
+        ("A8", 0, None, false),
+        ("A9", 0, None, false),
+        ("B1", 3, None, false),
+        ("B2", 0, None, false),
+        ("B3", 0, None, false),
+        ("B4", 0, None, false),
+        ("B5", 0, None, false),
+        ("B6", 0, None, false),
+        ("B7", 0, None, false),
+        ("B8", 0, None, false),
+        ("B9", 1, None, false),
+        ("C1", 3, None, false),
+        ("C2", 3, None, false),
+        ("C3", 0, None, false),
+        ("C4", 0, None, false),
+        ("C5", 0, None, false),
+        ("C6", 0, None, false),
+        ("C7", 0, None, false),
+        ("C8", 1, None, false),
+        ("C9", 1, None, false),
+        ("D1", 3, None, false),
+        ("D2", 3, None, false),
+        ("D3", 3, None, false),
+        ("D4", 0, None, false),
+        ("D5", 0, None, false),
+        ("D6", 0, None, false),
+        ("D7", 1, None, false),
+        ("D8", 1, None, false),
+        ("D9", 1, None, false),
+        ("E1", 3, None, false),
+        ("E2", 3, None, false),
+        ("E3", 3, None, false),
+        ("E4", 3, None, false),
+        ("E5", 0, None, false),
+        ("E7", 1, None, false),
+        ("E8", 1, None, false),
+        ("F1", 3, None, false),
+        ("F2", 3, None, false),
+        ("F3", 3, None, false),
+        ("F4", 2, None, false),
+        ("F5", 2, None, false),
+        ("F7", 1, None, false),
+        ("F8", 1, None, false),
+        ("F9", 1, None, false),
+        ("G1", 3, None, false),
+        ("G2", 3, None, false),
+        ("G3", 2, None, false),
+        ("G4", 2, None, false),
+        ("G5", 2, None, false),
+        ("G6", 2, None, false),
+        ("G7", 1, None, false),
+        ("H1", 3, None, false),
+        ("H2", 2, None, false),
+        ("H4", 2, None, false),
+        ("H5", 2, None, false),
+        ("H7", 2, None, false),
+        ("H9", 1, None, false),
+        ("J1", 2, None, false),
+        ("J2", 2, None, false),
+        ("J3", 2, None, false),
+        ("J4", 2, None, false),
+        ("J8", 2, None, false),
+        ("J9", 1, None, false),
+    ],
+};
+
+// iCE40HX8K, as on the Alchitry Cu.  Only the balls that the Cu brings out are listed.
+pub const CB132: PinTable = PinTable {
+    package: "cb132",
+    complete: false,
+    pins: &[
+        ("A1", 0, None, false),
+        ("A2", 0, None, false),
+        ("A3", 0, None, false),
+        ("A4", 0, None, false),
+        ("A5", 0, None, false),
+        ("A6", 0, None, false),
+        ("A7", 0, None, false),
+        ("A9", 0, None, false),
+        ("A10", 0, None, false),
+        ("A11", 0, None, false),
+        ("A12", 0, None, false),
+        ("A14", 0, None, false),
+        ("B1", 3, None, false),
+        ("C1", 3, None, false),
+        ("C3", 0, None, false),
+        ("C4", 0, None, false),
+        ("C5", 0, None, false),
+        ("C6", 0, None, false),
+        ("C7", 0, None, false),
+        ("C9", 0, None, false),
+        ("C10", 0, None, false),
+        ("C11", 0, None, false),
+        ("C12", 0, None, false),
+        ("C14", 1, None, false),
+        ("D1", 3, None, false),
+        ("D3", 3, None, false),
+        ("D4", 0, None, false),
+        ("D5", 0, None, false),
+        ("D6", 0, None, false),
+        ("D7", 0, None, false),
+        ("D9", 0, None, false),
+        ("D10", 0, None, false),
+        ("D11", 0, None, false),
+        ("D12", 1, None, false),
+        ("D14", 1, None, false),
+        ("E1", 3, None, false),
+        ("E4", 3, None, false),
+        ("E11", 1, None, false),
+        ("E12", 1, None, false),
+        ("E14", 1, None, false),
+        ("F3", 3, None, false),
+        ("F4", 3, None, false),
+        ("F11", 1, None, false),
+        ("F12", 1, None, false),
+        ("F14", 1, None, false),
+        ("G1", 3, None, false),
+        ("G3", 3, None, false),
+        ("G4", 3, None, false),
+        ("G11", 1, None, false),
+        ("G12", 1, None, false),
+        ("G14", 1, None, false),
+        ("H1", 3, None, false),
+        ("H3", 3, None, false),
+        ("H4", 3, None, false),
+        ("H12", 1, None, false),
+        ("H14", 1, None, false),
+        ("J1", 3, None, false),
+        ("J3", 3, None, false),
+        ("J11", 1, None, false),
+        ("K3", 3, None, false),
+        ("K4", 3, None, false),
+        ("K11", 1, None, false),
+        ("K12", 1, None, false),
+        ("K14", 1, None, false),
+        ("L1", 3, None, false),
+        ("L12", 1, None, false),
+        ("L14", 1, None, false),
+        ("M1", 3, None, false),
+        ("M3", 2, None, false),
+        ("M4", 2, None, false),
+        ("M5", 2, None, false),
+        ("M6", 2, None, false),
+        ("M9", 2, None, false),
+        ("M10", 2, None, false),
+        ("M11", 2, None, false),
+        ("M12", 1, None, false),
+        ("N1", 3, None, false),
+        ("N14", 1, None, false),
+        ("P1", 2, None, false),
+        ("P2", 2, None, false),
+        ("P3", 2, None, false),
+        ("P4", 2, None, false),
+        ("P5", 2, None, false),
+        ("P7", 2, None, false),
+        ("P8", 2, None, false),
+        ("P9", 2, None, false),
+        ("P10", 2, None, false),
+        ("P11", 2, None, false),
+        ("P12", 2, None, false),
+    ],
+};
@@ -0,0 +1,32 @@
+use crate::board::{signal_at, Board, Toolchain};
+use rust_hdl_core::prelude::*;
+
+make_domain!(Mhz16, 16_000_000);
+
+pub struct TinyFpgaBx;
+
+impl Board for TinyFpgaBx {
+    const NAME: &'static str = "TinyFPGA BX";
+    const TOOLCHAIN: Toolchain = Toolchain::Ice40;
+    const DEVICE: &'static str = "lp8k";
+    const PACKAGE: &'static str = "cm81";
+    fn clock_pins() -> Vec<(&'static str, u64)> {
+        vec![("B2", Mhz16::FREQ)]
+    }
+    fn led_pins() -> Vec<&'static str> {
+        vec!["B3"]
+    }
+    fn button_pins() -> Vec<&'static str> {
+        vec![]
+    }
+}
+
+pub fn clock() -> Signal<In, Clock, Mhz16> {
+    let mut x = signal_at(&["B2"]);
+    x.connect();
+    x
+}
+
+pub fn led() -> Signal<Out, Bit, Async> {
+    signal_at(&TinyFpgaBx::led_pins())
+}
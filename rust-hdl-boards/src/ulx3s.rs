@@ -0,0 +1,49 @@
+use crate::board::{signal_at, Board, Toolchain};
+use rust_hdl_core::direction::Direction;
+use rust_hdl_core::prelude::*;
+
+make_domain!(Mhz25, 25_000_000);
+
+pub struct Ulx3s;
+
+impl Board for Ulx3s {
+    const NAME: &'static str = "ULX3S";
+    const TOOLCHAIN: Toolchain = Toolchain::Ecp5;
+    const DEVICE: &'static str = "85k";
+    const PACKAGE: &'static str = "CABGA381";
+    fn clock_pins() -> Vec<(&'static str, u64)> {
+        vec![("G2", Mhz25::FREQ)]
+    }
+    fn led_pins() -> Vec<&'static str> {
+        vec!["B2", "C2", "C1", "D2", "D1", "E2", "E1", "H3"]
+    }
+    // The power button (which reads low when pressed), then fire 1 and 2, up, down,
+    // left and right
+    fn button_pins() -> Vec<&'static str> {
+        vec!["D6", "R1", "T1", "R18", "V1", "U1", "H16"]
+    }
+}
+
+// Every I/O on the board is 3.3V
+fn lvcmos33<D: Direction, T: Synth, F: Domain>(mut x: Signal<D, T, F>) -> Signal<D, T, F> {
+    for ndx in 0..T::BITS {
+        x.add_signal_type(ndx, SignalType::LowVoltageCMOS_3v3);
+    }
+    x
+}
+
+pub fn clock() -> Signal<In, Clock, Mhz25> {
+    let mut x = lvcmos33(signal_at(&["G2"]));
+    x.connect();
+    x
+}
+
+pub fn leds() -> Signal<Out, Bits<8>, Async> {
+    lvcmos33(signal_at(&Ulx3s::led_pins()))
+}
+
+pub fn buttons() -> Signal<In, Bits<7>, Async> {
+    let mut x = lvcmos33(signal_at(&Ulx3s::button_pins()));
+    x.connect();
+    x
+}
@@ -1,4 +1,7 @@
 use crate::bits::Bits;
+use crate::fixed::Fixed;
+use crate::signed::Signed;
+use crate::synth::Synth;
 use num_bigint::BigUint;
 use std::fmt::{Display, Formatter, LowerHex};
 
@@ -6,8 +9,18 @@ use std::fmt::{Display, Formatter, LowerHex};
 pub enum Verilog {
     Empty,
     Combinatorial(VerilogBlock),
+    Sequential { clock: String, code: VerilogBlock },
     Custom(String),
     Blackbox(String),
+    Wrapper(Wrapper),
+}
+
+// A module whose body instantiates an external module.  The cores are the blackbox
+// declarations it needs, which are emitted once however often the wrapper is used.
+#[derive(Debug, Clone)]
+pub struct Wrapper {
+    pub code: String,
+    pub cores: String,
 }
 
 impl Default for Verilog {
@@ -31,6 +44,18 @@ pub enum VerilogStatement {
     Match(VerilogMatch),
     Loop(VerilogLoop),
     Comment(String),
+    Declaration(VerilogDeclaration),
+    // hdl_assert!(test, message)
+    Assert(VerilogExpression, String),
+    // hdl_assume!(test)
+    Assume(VerilogExpression),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerilogDeclaration {
+    pub name: String,
+    pub width: usize,
+    pub signed: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -74,13 +99,32 @@ pub struct VerilogCase {
     pub block: VerilogBlock,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct VerilogLiteral {
     val: BigUint,
     bits: usize,
 }
 
 impl VerilogLiteral {
+    pub(crate) fn new(val: BigUint, bits: usize) -> VerilogLiteral {
+        VerilogLiteral { val, bits }
+    }
+
+    pub fn zero(bits: usize) -> VerilogLiteral {
+        VerilogLiteral {
+            val: BigUint::default(),
+            bits,
+        }
+    }
+
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    pub(crate) fn value(&self) -> &BigUint {
+        &self.val
+    }
+
     pub fn as_usize(&self) -> usize {
         let m = self.val.to_u32_digits();
         match m.len() {
@@ -132,6 +176,18 @@ impl<const N: usize> From<Bits<N>> for VerilogLiteral {
     }
 }
 
+impl<const N: usize> From<Signed<N>> for VerilogLiteral {
+    fn from(x: Signed<N>) -> Self {
+        x.as_unsigned().into()
+    }
+}
+
+impl<T: Synth, const F: usize> From<Fixed<T, F>> for VerilogLiteral {
+    fn from(x: Fixed<T, F>) -> Self {
+        x.verilog()
+    }
+}
+
 impl Display for VerilogLiteral {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let bits = self.bits;
@@ -171,6 +227,21 @@ pub enum VerilogExpression {
         Box<VerilogExpression>,
         Box<VerilogExpression>,
     ),
+    Signed(Box<VerilogExpression>),
+    Unsigned(Box<VerilogExpression>),
+    Concat(Vec<VerilogExpression>),
+    // {n{x}}
+    Replicate(Box<VerilogExpression>, usize),
+    Call(Box<VerilogFunction>, Vec<VerilogExpression>),
+}
+
+#[derive(Debug, Clone)]
+pub struct VerilogFunction {
+    pub name: String,
+    pub width: usize,
+    pub signed: bool,
+    pub args: Vec<VerilogDeclaration>,
+    pub body: VerilogBlock,
 }
 
 #[derive(Debug, Clone)]
@@ -1,6 +1,6 @@
 use crate::ast::VerilogLiteral;
 use crate::constraint::PinConstraint;
-use crate::synth::VCDValue;
+use crate::synth::{SynthEnum, SynthStruct, VCDValue};
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum AtomKind {
@@ -10,6 +10,7 @@ pub enum AtomKind {
     StubOutputSignal,
     Constant,
     LocalSignal,
+    Register,
 }
 
 impl AtomKind {
@@ -33,10 +34,19 @@ pub trait Atom {
     fn changed(&self) -> bool;
     fn kind(&self) -> AtomKind;
     fn is_enum(&self) -> bool;
+    fn is_struct(&self) -> bool;
+    fn is_signed(&self) -> bool;
     fn name(&self, ndx: usize) -> &'static str;
     fn type_name(&self) -> &'static str;
+    fn enums(&self) -> Vec<SynthEnum>;
+    fn structs(&self) -> Vec<SynthStruct>;
     fn vcd(&self) -> VCDValue;
     fn id(&self) -> usize;
     fn verilog(&self) -> VerilogLiteral;
+    // The current and next values in simulation
+    fn val_literal(&self) -> VerilogLiteral;
+    fn next_literal(&self) -> VerilogLiteral;
     fn constraints(&self) -> Vec<PinConstraint>;
+    // The frequency of the domain, for clocks with one
+    fn clock_frequency(&self) -> Option<u64>;
 }
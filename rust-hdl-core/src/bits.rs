@@ -90,6 +90,34 @@ pub fn bit_cast<const M: usize, const N: usize>(x: Bits<N>) -> Bits<M> {
     }
 }
 
+// The first argument forms the most significant bits, as in {a, b}
+#[inline(always)]
+pub fn concat<const M: usize, const N: usize, const O: usize>(a: Bits<M>, b: Bits<N>) -> Bits<O> {
+    const {
+        assert!(O == M + N);
+    }
+    (bit_cast::<O, M>(a) << N) | bit_cast::<O, N>(b)
+}
+
+pub fn mul_wide<const M: usize, const N: usize, const O: usize>(a: Bits<M>, b: Bits<N>) -> Bits<O> {
+    const {
+        assert!(O == M + N);
+    }
+    if M <= 64 && N <= 64 {
+        let a: u64 = a.into();
+        let b: u64 = b.into();
+        return (a as u128 * b as u128).into();
+    }
+    let a = bit_cast::<O, M>(a);
+    let mut ret = Bits::<O>::default();
+    for i in 0..N {
+        if b.get_bit(i) {
+            ret = ret + (a << i);
+        }
+    }
+    ret
+}
+
 impl<const N: usize> Into<VCDValue> for Bits<N> {
     fn into(self) -> VCDValue {
         if N == 1 {
@@ -425,7 +453,7 @@ impl<const N: usize> std::ops::Add<bool> for Bits<N> {
 mod tests {
     use std::num::Wrapping;
 
-    use crate::bits::{bit_cast, bits, clog2, Bits};
+    use crate::bits::{bit_cast, bits, clog2, concat, mul_wide, Bits};
 
     #[test]
     fn test_short_from_u8() {
@@ -657,6 +685,28 @@ mod tests {
         assert!(b > a);
     }
     #[test]
+    fn test_concat() {
+        let c: Bits<12> = concat(bits::<4>(0xa), bits::<8>(0x5c));
+        assert_eq!(c, bits::<12>(0xa5c));
+        let c: Bits<80> = concat(bits::<48>(0xdead_cafe_babe), bits::<32>(0x1234_5678));
+        assert_eq!(c.get_bits::<32>(0), bits::<32>(0x1234_5678));
+        assert_eq!(c.get_bits::<48>(32), bits::<48>(0xdead_cafe_babe));
+    }
+    #[test]
+    fn test_mul_wide() {
+        let c: Bits<16> = mul_wide(bits::<8>(0xff), bits::<8>(0xff));
+        assert_eq!(c, bits::<16>(0xfe01));
+        let c: Bits<100> = mul_wide(bits::<64>(0xffff_ffff_ffff_ffff), bits::<36>(0x8_0000_0001));
+        let expected = 0xffff_ffff_ffff_ffff_u128 * 0x8_0000_0001_u128;
+        assert_eq!(
+            c.get_bits::<64>(0),
+            bits::<64>(expected & 0xffff_ffff_ffff_ffff)
+        );
+        assert_eq!(c.get_bits::<36>(64), bits::<36>(expected >> 64));
+        let c: Bits<130> = mul_wide(bits::<65>(3), bits::<65>(5));
+        assert_eq!(c, bits::<130>(15));
+    }
+    #[test]
     fn test_compare_long() {
         let a = bits::<160>(35);
         let b = bits::<160>(100);
@@ -0,0 +1,182 @@
+use crate::ast::{Verilog, VerilogLiteral, Wrapper};
+use crate::atom::{Atom, AtomKind};
+use crate::block::Block;
+use crate::code_writer::CodeWriter;
+use crate::named_path::NamedPath;
+use crate::probe::Probe;
+
+#[derive(Clone, Debug)]
+struct BlackBoxPort {
+    name: String,
+    input: bool,
+    width: usize,
+    signed: bool,
+}
+
+#[derive(Default)]
+struct PortCollector {
+    depth: usize,
+    namespace: NamedPath,
+    ports: Vec<BlackBoxPort>,
+    parameters: Vec<(String, VerilogLiteral)>,
+}
+
+impl Probe for PortCollector {
+    fn visit_start_scope(&mut self, _name: &str, _node: &dyn Block) {
+        self.depth += 1;
+    }
+
+    fn visit_start_namespace(&mut self, name: &str, _node: &dyn Block) {
+        if self.depth == 1 {
+            self.namespace.push(name);
+        }
+    }
+
+    fn visit_atom(&mut self, name: &str, signal: &dyn Atom) {
+        if self.depth != 1 {
+            return;
+        }
+        let namespace = self.namespace.flat("_");
+        let name = if namespace.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}_{}", namespace, name)
+        };
+        match signal.kind() {
+            AtomKind::InputParameter | AtomKind::OutputParameter => self.ports.push(BlackBoxPort {
+                name,
+                input: signal.kind() == AtomKind::InputParameter,
+                width: signal.bits(),
+                signed: signal.is_signed(),
+            }),
+            AtomKind::Constant => self.parameters.push((name, signal.verilog())),
+            _ => {}
+        }
+    }
+
+    fn visit_end_namespace(&mut self, _name: &str, _node: &dyn Block) {
+        if self.depth == 1 {
+            self.namespace.pop();
+        }
+    }
+
+    fn visit_end_scope(&mut self, _name: &str, _node: &dyn Block) {
+        self.depth -= 1;
+    }
+}
+
+// Describes an external module by a block that mirrors it.  The block's signals
+// are the ports, with the same names, and its constants are the parameters.  Its
+// update function serves as the simulation model, e.g.
+//
+//     fn hdl(&self) -> Verilog {
+//         BlackBox::new("SB_PLL40_CORE", self)
+//             .parameter("FEEDBACK_PATH", "\"SIMPLE\"")
+//             .hdl()
+//     }
+#[derive(Clone, Debug)]
+pub struct BlackBox {
+    module: String,
+    ports: Vec<BlackBoxPort>,
+    parameters: Vec<(String, String)>,
+}
+
+impl BlackBox {
+    pub fn new<B: Block>(module: &str, block: &B) -> BlackBox {
+        let mut collector = PortCollector::default();
+        block.accept(module, &mut collector);
+        BlackBox {
+            module: module.to_owned(),
+            ports: collector.ports,
+            parameters: collector
+                .parameters
+                .into_iter()
+                .map(|(name, value)| (name, value.to_string()))
+                .collect(),
+        }
+    }
+
+    // For parameters that are not bit vectors, such as strings.  The value is
+    // written into the Verilog as is.
+    pub fn parameter(mut self, name: &str, value: &str) -> BlackBox {
+        self.parameters.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn declaration(&self) -> String {
+        let mut io = CodeWriter::new();
+        io.add("(* blackbox *)");
+        io.add(format!(
+            "module {}({});",
+            self.module,
+            self.ports
+                .iter()
+                .map(|x| x.name.to_owned())
+                .collect::<Vec<_>>()
+                .join(",")
+        ));
+        io.push();
+        for port in &self.ports {
+            let direction = if port.input { "input" } else { "output" };
+            let signed = if port.signed { " signed" } else { "" };
+            if port.width == 1 {
+                io.add(format!("{}{} {};", direction, signed, port.name));
+            } else {
+                io.add(format!(
+                    "{}{} [{}:0] {};",
+                    direction,
+                    signed,
+                    port.width - 1,
+                    port.name
+                ));
+            }
+        }
+        // Each instance sets its own values, so that every instance can share the one
+        // declaration
+        for (name, _) in &self.parameters {
+            io.add(format!("parameter {} = 0;", name));
+        }
+        io.pop();
+        io.add("endmodule");
+        io.to_string()
+    }
+
+    // The wrapper's ports have the same names as the external module's, so they
+    // are connected straight through
+    pub fn instance(&self) -> String {
+        let mut io = CodeWriter::new();
+        if self.parameters.is_empty() {
+            io.add(format!("{} blackbox(", self.module));
+        } else {
+            io.add(format!("{} #(", self.module));
+            io.push();
+            io.add(
+                self.parameters
+                    .iter()
+                    .map(|(name, value)| format!(".{}({})", name, value))
+                    .collect::<Vec<_>>()
+                    .join(",\n"),
+            );
+            io.pop();
+            io.add(") blackbox(");
+        }
+        io.push();
+        io.add(
+            self.ports
+                .iter()
+                .map(|x| format!(".{}({})", x.name, x.name))
+                .collect::<Vec<_>>()
+                .join(",\n"),
+        );
+        io.pop();
+        io.add(");");
+        io.to_string()
+    }
+
+    pub fn hdl(&self) -> Verilog {
+        Verilog::Wrapper(Wrapper {
+            code: self.instance(),
+            cores: self.declaration(),
+        })
+    }
+}
@@ -4,7 +4,7 @@ use crate::block::Block;
 use crate::constraint::PinConstraint;
 use crate::logic::Logic;
 use crate::probe::Probe;
-use crate::synth::{Synth, VCDValue};
+use crate::synth::{Synth, SynthEnum, SynthStruct, VCDValue};
 
 #[derive(Copy, Clone, Debug)]
 pub struct Constant<T: Synth> {
@@ -47,6 +47,14 @@ impl<T: Synth> Atom for Constant<T> {
         T::ENUM_TYPE
     }
 
+    fn is_struct(&self) -> bool {
+        T::STRUCT_TYPE
+    }
+
+    fn is_signed(&self) -> bool {
+        T::SIGNED
+    }
+
     fn name(&self, ndx: usize) -> &'static str {
         T::name(ndx)
     }
@@ -55,6 +63,14 @@ impl<T: Synth> Atom for Constant<T> {
         T::TYPE_NAME
     }
 
+    fn enums(&self) -> Vec<SynthEnum> {
+        T::enums()
+    }
+
+    fn structs(&self) -> Vec<SynthStruct> {
+        T::structs()
+    }
+
     fn vcd(&self) -> VCDValue {
         self.val.vcd()
     }
@@ -63,6 +79,14 @@ impl<T: Synth> Atom for Constant<T> {
         self.val.verilog()
     }
 
+    fn val_literal(&self) -> VerilogLiteral {
+        self.val.verilog()
+    }
+
+    fn next_literal(&self) -> VerilogLiteral {
+        self.val.verilog()
+    }
+
     fn id(&self) -> usize {
         0_usize
     }
@@ -70,6 +94,10 @@ impl<T: Synth> Atom for Constant<T> {
     fn constraints(&self) -> Vec<PinConstraint> {
         vec![]
     }
+
+    fn clock_frequency(&self) -> Option<u64> {
+        None
+    }
 }
 
 impl<T: Synth> Block for Constant<T> {
@@ -0,0 +1,284 @@
+use crate::ast::VerilogLiteral;
+use crate::bits::{bit_cast, mul_wide, Bits};
+use crate::signed::{signed_bit_cast, Signed};
+use crate::synth::{Synth, VCDValue};
+use std::fmt::{Display, Formatter};
+
+// Fixed point value with F fraction bits, held in a Signed<N> or a Bits<N>.  The
+// arithmetic and the HDL are those of the underlying value, so Fixed<Signed<16>, 12>
+// has 4 integer bits (including the sign) and Fixed<Bits<16>, 12> has 4 without one.
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Fixed<T, const F: usize>(T);
+
+// The unsigned value of the bits, as near as an f64 gets to it
+fn bits_to_f64<const N: usize>(x: Bits<N>) -> f64 {
+    (0..N).rev().fold(0.0, |acc, i| {
+        acc * 2.0 + if x.get_bit(i) { 1.0 } else { 0.0 }
+    })
+}
+
+impl<T: Synth, const F: usize> Fixed<T, F> {
+    pub fn from_raw(x: T) -> Fixed<T, F> {
+        const {
+            assert!(F <= T::BITS);
+        }
+        Fixed(x)
+    }
+
+    pub fn as_raw(&self) -> T {
+        self.0
+    }
+
+    pub fn frac_bits(&self) -> usize {
+        F
+    }
+
+    pub fn int_bits(&self) -> usize {
+        T::BITS - F
+    }
+}
+
+impl<const N: usize, const F: usize> Fixed<Signed<N>, F> {
+    pub fn mul_full<const O: usize, const H: usize, const M: usize, const G: usize>(
+        self,
+        rhs: Fixed<Signed<M>, G>,
+    ) -> Fixed<Signed<O>, H> {
+        const {
+            assert!(O == N + M && H == F + G);
+        }
+        // The magnitude of min() is still right, read as unsigned
+        let magnitude = |x: Signed<N>| if x.is_negative() { -x } else { x }.as_unsigned();
+        let rhs_magnitude = |x: Signed<M>| if x.is_negative() { -x } else { x }.as_unsigned();
+        let product = mul_wide::<N, M, O>(magnitude(self.0), rhs_magnitude(rhs.0)).as_signed();
+        if self.0.is_negative() != rhs.0.is_negative() {
+            Fixed::from_raw(-product)
+        } else {
+            Fixed::from_raw(product)
+        }
+    }
+
+    pub fn truncate_to<const M: usize, const G: usize>(self) -> Fixed<Signed<M>, G> {
+        const {
+            assert!(G <= F);
+        }
+        Fixed::from_raw(signed_bit_cast::<M, N>(self.0 >> (F - G)))
+    }
+
+    // Round half up
+    pub fn round_to<const M: usize, const G: usize>(self) -> Fixed<Signed<M>, G> {
+        const {
+            assert!(G <= F);
+        }
+        if F == G {
+            return Fixed::from_raw(signed_bit_cast::<M, N>(self.0));
+        }
+        let half = Bits::<N>::default()
+            .replace_bit(F - G - 1, true)
+            .as_signed();
+        Fixed::from_raw(signed_bit_cast::<M, N>((self.0 + half) >> (F - G)))
+    }
+
+    pub fn max() -> Fixed<Signed<N>, F> {
+        Fixed::from_raw(Signed::max())
+    }
+
+    pub fn min() -> Fixed<Signed<N>, F> {
+        Fixed::from_raw(Signed::min())
+    }
+}
+
+impl<const N: usize, const F: usize> Fixed<Bits<N>, F> {
+    pub fn mul_full<const O: usize, const H: usize, const M: usize, const G: usize>(
+        self,
+        rhs: Fixed<Bits<M>, G>,
+    ) -> Fixed<Bits<O>, H> {
+        const {
+            assert!(O == N + M && H == F + G);
+        }
+        Fixed::from_raw(mul_wide::<N, M, O>(self.0, rhs.0))
+    }
+
+    pub fn truncate_to<const M: usize, const G: usize>(self) -> Fixed<Bits<M>, G> {
+        const {
+            assert!(G <= F);
+        }
+        Fixed::from_raw(bit_cast::<M, N>(self.0 >> (F - G)))
+    }
+
+    // Round half up
+    pub fn round_to<const M: usize, const G: usize>(self) -> Fixed<Bits<M>, G> {
+        const {
+            assert!(G <= F);
+        }
+        if F == G {
+            return Fixed::from_raw(bit_cast::<M, N>(self.0));
+        }
+        let half = Bits::<N>::default().replace_bit(F - G - 1, true);
+        Fixed::from_raw(bit_cast::<M, N>((self.0 + half) >> (F - G)))
+    }
+
+    pub fn max() -> Fixed<Bits<N>, F> {
+        Fixed::from_raw(Bits::mask())
+    }
+
+    pub fn min() -> Fixed<Bits<N>, F> {
+        Fixed::from_raw(Bits::default())
+    }
+}
+
+// Out of range values wrap, as they do in the HDL
+impl<const N: usize, const F: usize> From<f64> for Fixed<Signed<N>, F> {
+    fn from(x: f64) -> Self {
+        let raw = (x * (F as f64).exp2()).round() as i128;
+        Fixed::from_raw(signed_bit_cast::<N, 128>(
+            Bits::<128>::from(raw as u128).as_signed(),
+        ))
+    }
+}
+
+impl<const N: usize, const F: usize> From<f64> for Fixed<Bits<N>, F> {
+    fn from(x: f64) -> Self {
+        let raw = (x * (F as f64).exp2()).round() as i128;
+        Fixed::from_raw(bit_cast::<N, 128>(Bits::<128>::from(raw as u128)))
+    }
+}
+
+impl<const N: usize, const F: usize> From<Fixed<Signed<N>, F>> for f64 {
+    fn from(x: Fixed<Signed<N>, F>) -> Self {
+        // The magnitude of min() is still right, read as unsigned
+        let raw = if x.0.is_negative() {
+            -bits_to_f64((-x.0).as_unsigned())
+        } else {
+            bits_to_f64(x.0.as_unsigned())
+        };
+        raw / (F as f64).exp2()
+    }
+}
+
+impl<const N: usize, const F: usize> From<Fixed<Bits<N>, F>> for f64 {
+    fn from(x: Fixed<Bits<N>, F>) -> Self {
+        bits_to_f64(x.0) / (F as f64).exp2()
+    }
+}
+
+impl<T: Synth, const F: usize> Display for Fixed<T, F>
+where
+    f64: From<Fixed<T, F>>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&f64::from(*self), f)
+    }
+}
+
+impl<T: Synth, const F: usize> Synth for Fixed<T, F> {
+    const BITS: usize = T::BITS;
+    const SIGNED: bool = T::SIGNED;
+
+    fn vcd(self) -> VCDValue {
+        self.0.vcd()
+    }
+
+    fn verilog(self) -> VerilogLiteral {
+        self.0.verilog()
+    }
+}
+
+impl<T: Synth + std::ops::Add<Output = T>, const F: usize> std::ops::Add<Fixed<T, F>>
+    for Fixed<T, F>
+{
+    type Output = Fixed<T, F>;
+
+    fn add(self, rhs: Fixed<T, F>) -> Self::Output {
+        Fixed(self.0 + rhs.0)
+    }
+}
+
+impl<T: Synth + std::ops::Sub<Output = T>, const F: usize> std::ops::Sub<Fixed<T, F>>
+    for Fixed<T, F>
+{
+    type Output = Fixed<T, F>;
+
+    fn sub(self, rhs: Fixed<T, F>) -> Self::Output {
+        Fixed(self.0 - rhs.0)
+    }
+}
+
+impl<T: Synth + std::ops::Neg<Output = T>, const F: usize> std::ops::Neg for Fixed<T, F> {
+    type Output = Fixed<T, F>;
+
+    fn neg(self) -> Self::Output {
+        Fixed(-self.0)
+    }
+}
+
+impl<T: Synth + std::ops::Shr<usize, Output = T>, const F: usize> std::ops::Shr<usize>
+    for Fixed<T, F>
+{
+    type Output = Fixed<T, F>;
+
+    fn shr(self, rhs: usize) -> Self::Output {
+        Fixed(self.0 >> rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bits::Bits;
+    use crate::fixed::Fixed;
+    use crate::signed::Signed;
+
+    #[test]
+    fn test_f64_round_trip() {
+        for x in [-8.0, -1.25, -0.0625, 0.0, 0.5, 3.75, 7.9375] {
+            let y: Fixed<Signed<8>, 4> = x.into();
+            assert_eq!(f64::from(y), x);
+        }
+        let y: Fixed<Signed<8>, 4> = 8.0.into();
+        assert_eq!(y, Fixed::<Signed<8>, 4>::min());
+        let y: Fixed<Signed<8>, 4> = 0.03.into();
+        assert_eq!(y.as_raw(), 0);
+        for x in [0.0, 0.5, 3.75, 15.9375] {
+            let y: Fixed<Bits<8>, 4> = x.into();
+            assert_eq!(f64::from(y), x);
+        }
+        let y: Fixed<Bits<8>, 4> = 16.5.into();
+        assert_eq!(f64::from(y), 0.5);
+    }
+
+    #[test]
+    fn test_full_precision_multiply() {
+        let a: Fixed<Signed<16>, 12> = (-1.5).into();
+        let b: Fixed<Signed<16>, 12> = 2.25.into();
+        let c: Fixed<Signed<32>, 24> = a.mul_full(b);
+        assert_eq!(f64::from(c), -3.375);
+        let m = Fixed::<Signed<16>, 12>::min();
+        let c: Fixed<Signed<32>, 24> = m.mul_full(m);
+        assert_eq!(f64::from(c), 64.0);
+        let a: Fixed<Bits<16>, 12> = 15.5.into();
+        let c: Fixed<Bits<32>, 24> = a.mul_full(a);
+        assert_eq!(f64::from(c), 240.25);
+    }
+
+    #[test]
+    fn test_wide_multiply() {
+        let a: Fixed<Signed<100>, 40> = (-3.0).into();
+        let b: Fixed<Signed<100>, 40> = 1.0e9.into();
+        let c: Fixed<Signed<200>, 80> = a.mul_full(b);
+        assert_eq!(f64::from(c), -3.0e9);
+    }
+
+    #[test]
+    fn test_truncate_and_round() {
+        let a: Fixed<Signed<12>, 8> = 1.6875.into();
+        assert_eq!(f64::from(a.truncate_to::<6, 2>()), 1.5);
+        assert_eq!(f64::from(a.round_to::<6, 2>()), 1.75);
+        let a: Fixed<Signed<12>, 8> = (-1.6875).into();
+        assert_eq!(f64::from(a.truncate_to::<6, 2>()), -1.75);
+        assert_eq!(f64::from(a.round_to::<6, 2>()), -1.75);
+        let n = Fixed::<Signed<12>, 8>::from(5.5).truncate_to::<4, 2>();
+        assert_eq!(f64::from(n), 1.5);
+        let a: Fixed<Bits<12>, 8> = 1.6875.into();
+        assert_eq!(f64::from(a.truncate_to::<6, 2>()), 1.5);
+        assert_eq!(f64::from(a.round_to::<6, 2>()), 1.75);
+    }
+}
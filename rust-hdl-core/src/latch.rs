@@ -0,0 +1,212 @@
+use crate::ast::{VerilogBlock, VerilogBlockOrConditional, VerilogExpression, VerilogStatement};
+use crate::verilog_gen::{
+    ident_fixup, verilog_expression, with_local_defaults, with_register_defaults, LoopVariable,
+};
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Latch {
+    pub signal: String,
+    pub branch: String,
+}
+
+impl Display for Latch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is not assigned in the {}", self.signal, self.branch)
+    }
+}
+
+fn otherwise_block(x: &VerilogBlockOrConditional) -> VerilogBlock {
+    match x {
+        VerilogBlockOrConditional::Block(b) => b.clone(),
+        VerilogBlockOrConditional::Conditional(c) => vec![c.as_ref().clone()],
+        VerilogBlockOrConditional::None => vec![],
+    }
+}
+
+// Every path through the code is followed, with loops unrolled.  A partial write,
+// such as a bit or a slice, counts as an assignment of the whole signal.  Matches
+// are taken to be exhaustive, as the Rust they come from is.
+#[derive(Default)]
+struct LatchFinder {
+    loops: Vec<LoopVariable>,
+}
+
+impl LatchFinder {
+    fn name(&self, x: &str) -> String {
+        ident_fixup(x, &self.loops, &[])
+    }
+
+    fn target(&self, e: &VerilogExpression) -> Option<String> {
+        match e {
+            VerilogExpression::Signal(x)
+            | VerilogExpression::Index(x, _)
+            | VerilogExpression::Slice(x, _, _) => Some(self.name(x)),
+            _ => None,
+        }
+    }
+
+    // The signals assigned on some path
+    fn written(&mut self, block: &VerilogBlock, ret: &mut BTreeSet<String>) {
+        for s in block {
+            match s {
+                VerilogStatement::Assignment(l, _) => ret.extend(self.target(l)),
+                VerilogStatement::SliceAssignment { base, .. } => {
+                    ret.insert(self.name(base));
+                }
+                VerilogStatement::If(c) => {
+                    self.written(&c.then, ret);
+                    self.written(&otherwise_block(&c.otherwise), ret);
+                }
+                VerilogStatement::Match(m) => {
+                    for case in &m.cases {
+                        self.written(&case.block, ret);
+                    }
+                }
+                VerilogStatement::Loop(l) => {
+                    for i in l.from.as_usize()..l.to.as_usize() {
+                        self.loops.push(LoopVariable {
+                            variable: l.index.clone(),
+                            value: i,
+                        });
+                        self.written(&l.block, ret);
+                        self.loops.pop();
+                    }
+                }
+                VerilogStatement::Comment(_)
+                | VerilogStatement::Declaration(_)
+                | VerilogStatement::Assert(..)
+                | VerilogStatement::Assume(_) => {}
+            }
+        }
+    }
+
+    // The signals assigned on every path
+    fn assigned(&mut self, block: &VerilogBlock, mut ret: BTreeSet<String>) -> BTreeSet<String> {
+        for s in block {
+            match s {
+                VerilogStatement::Assignment(l, _) => ret.extend(self.target(l)),
+                VerilogStatement::SliceAssignment { base, .. } => {
+                    ret.insert(self.name(base));
+                }
+                VerilogStatement::If(c) => {
+                    let then = self.assigned(&c.then, ret.clone());
+                    let otherwise = self.assigned(&otherwise_block(&c.otherwise), ret);
+                    ret = then.intersection(&otherwise).cloned().collect();
+                }
+                VerilogStatement::Match(m) => {
+                    let mut cases = m.cases.iter().map(|x| self.assigned(&x.block, ret.clone()));
+                    if let Some(first) = cases.next() {
+                        ret = cases.fold(first, |a, b| a.intersection(&b).cloned().collect());
+                    }
+                }
+                VerilogStatement::Loop(l) => {
+                    for i in l.from.as_usize()..l.to.as_usize() {
+                        self.loops.push(LoopVariable {
+                            variable: l.index.clone(),
+                            value: i,
+                        });
+                        ret = self.assigned(&l.block, ret);
+                        self.loops.pop();
+                    }
+                }
+                VerilogStatement::Comment(_)
+                | VerilogStatement::Declaration(_)
+                | VerilogStatement::Assert(..)
+                | VerilogStatement::Assume(_) => {}
+            }
+        }
+        ret
+    }
+
+    fn assigns(&mut self, block: &VerilogBlock, signal: &str) -> bool {
+        self.assigned(block, BTreeSet::new()).contains(signal)
+    }
+
+    fn writes(&mut self, block: &VerilogBlock, signal: &str) -> bool {
+        let mut written = BTreeSet::new();
+        self.written(block, &mut written);
+        written.contains(signal)
+    }
+
+    // Follows the first conditional that assigns the signal down the branch that
+    // does not, e.g. "else branch of if (a), then case 2'h1 of case (b)"
+    fn missing_branch(&mut self, block: &VerilogBlock, signal: &str) -> Option<String> {
+        for s in block {
+            if !self.writes(&vec![s.clone()], signal) {
+                continue;
+            }
+            let (branch, code) = match s {
+                VerilogStatement::If(c) => {
+                    if !self.assigns(&c.then, signal) {
+                        (
+                            format!("if ({}) branch", verilog_expression(&c.test)),
+                            c.then.clone(),
+                        )
+                    } else {
+                        (
+                            format!("else branch of if ({})", verilog_expression(&c.test)),
+                            otherwise_block(&c.otherwise),
+                        )
+                    }
+                }
+                VerilogStatement::Match(m) => {
+                    let case = m
+                        .cases
+                        .iter()
+                        .find(|x| !self.assigns(&x.block, signal))
+                        .unwrap();
+                    (
+                        format!(
+                            "case {} of case ({})",
+                            self.name(&case.condition),
+                            verilog_expression(&m.test)
+                        ),
+                        case.block.clone(),
+                    )
+                }
+                VerilogStatement::Loop(l) => {
+                    for i in l.from.as_usize()..l.to.as_usize() {
+                        self.loops.push(LoopVariable {
+                            variable: l.index.clone(),
+                            value: i,
+                        });
+                        let branch = self.missing_branch(&l.block, signal);
+                        self.loops.pop();
+                        if branch.is_some() {
+                            return branch;
+                        }
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            return Some(match self.missing_branch(&code, signal) {
+                Some(inner) => format!("{}, then {}", branch, inner),
+                None => branch,
+            });
+        }
+        None
+    }
+}
+
+// Finds the signals of a combinatorial block (or of the combinatorial half of a
+// clocked kernel, given its registers) that keep their value on some path, and so
+// infer a latch.  Locals and registers get the same defaults as in the generated code.
+pub fn find_latches(code: &VerilogBlock, registers: &[String]) -> Vec<Latch> {
+    let block = with_local_defaults(&with_register_defaults(code, registers));
+    let mut finder = LatchFinder::default();
+    let mut written = BTreeSet::new();
+    finder.written(&block, &mut written);
+    let assigned = finder.assigned(&block, BTreeSet::new());
+    written
+        .difference(&assigned)
+        .map(|signal| Latch {
+            signal: signal.clone(),
+            branch: finder
+                .missing_branch(&block, signal)
+                .unwrap_or_else(|| "code".into()),
+        })
+        .collect()
+}
@@ -1,7 +1,10 @@
+extern crate self as rust_hdl_core;
+
 pub mod ast;
 pub mod atom;
 pub mod bits;
 pub mod bitvec;
+pub mod blackbox;
 pub mod block;
 pub mod check_connected;
 pub mod clock;
@@ -9,17 +12,25 @@ pub mod code_writer;
 pub mod constant;
 pub mod constraint;
 pub mod direction;
+pub mod fixed;
+pub mod latch;
+pub mod lint;
 pub mod logic;
 pub mod module_defines;
 pub mod named_path;
 pub mod prelude;
 pub mod probe;
+pub mod reg;
 pub mod shortbitvec;
 pub mod signal;
+pub mod signed;
 pub mod simulate;
 pub mod struct_valued;
 pub mod synth;
 mod tagged;
 pub mod vcd_probe;
+pub mod verilog_eval;
 pub mod verilog_gen;
 pub mod verilog_visitor;
+pub mod vhdl_gen;
+pub mod yosys_gen;
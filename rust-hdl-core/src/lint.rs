@@ -0,0 +1,313 @@
+use crate::ast::{Verilog, VerilogExpression, VerilogLoop};
+use crate::atom::{Atom, AtomKind};
+use crate::block::Block;
+use crate::latch::find_latches;
+use crate::named_path::NamedPath;
+use crate::probe::Probe;
+use crate::verilog_gen::{ident_fixup, LoopVariable};
+use crate::verilog_visitor::{walk_block, walk_expression, VerilogVisitor};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DiagnosticKind {
+    Undriven,
+    MultiplyDriven,
+    UnusedOutput,
+    UnreadInput,
+    Latch,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub path: String,
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {} {}", self.severity, self.path, self.message)
+    }
+}
+
+struct AtomInfo {
+    name: String,
+    scope: usize,
+    kind: AtomKind,
+    connected: bool,
+}
+
+struct ScopeInfo {
+    path: String,
+    parent: Option<usize>,
+    code: Verilog,
+    // Local names as they appear in the scope's code, including the stubs of the
+    // child ports, e.g. child_port
+    names: BTreeMap<String, usize>,
+}
+
+#[derive(Default)]
+struct SignalAccess {
+    loops: Vec<LoopVariable>,
+    writes: BTreeSet<String>,
+    reads: BTreeSet<String>,
+}
+
+impl SignalAccess {
+    fn write(&mut self, e: &VerilogExpression) {
+        match e {
+            VerilogExpression::Signal(name) => {
+                self.writes.insert(ident_fixup(name, &self.loops, &[]));
+            }
+            VerilogExpression::Index(name, index) => {
+                self.writes.insert(ident_fixup(name, &self.loops, &[]));
+                self.visit_expression(index);
+            }
+            VerilogExpression::Slice(name, _, offset) => {
+                self.writes.insert(ident_fixup(name, &self.loops, &[]));
+                self.visit_expression(offset);
+            }
+            _ => walk_expression(self, e),
+        }
+    }
+}
+
+impl VerilogVisitor for SignalAccess {
+    fn visit_loop(&mut self, a: &VerilogLoop) {
+        for i in a.from.as_usize()..a.to.as_usize() {
+            self.loops.push(LoopVariable {
+                variable: a.index.clone(),
+                value: i,
+            });
+            walk_block(self, &a.block);
+            self.loops.pop();
+        }
+    }
+
+    fn visit_slice_assignment(
+        &mut self,
+        base: &str,
+        _width: &usize,
+        offset: &VerilogExpression,
+        replacement: &VerilogExpression,
+    ) {
+        self.writes.insert(ident_fixup(base, &self.loops, &[]));
+        self.visit_expression(offset);
+        self.visit_expression(replacement);
+    }
+
+    fn visit_signal(&mut self, c: &str) {
+        self.reads.insert(ident_fixup(c, &self.loops, &[]));
+    }
+
+    fn visit_assignment(&mut self, l: &VerilogExpression, r: &VerilogExpression) {
+        self.write(l);
+        self.visit_expression(r);
+    }
+}
+
+// Blocks whose code is opaque (custom Verilog, blackboxes, or none at all) are
+// taken at their word, i.e. their outputs are driven if they were connected.
+fn signal_access(code: &Verilog) -> Option<SignalAccess> {
+    let mut access = SignalAccess::default();
+    match code {
+        Verilog::Combinatorial(block) => access.visit_block(block),
+        Verilog::Sequential { clock, code } => {
+            access.reads.insert(ident_fixup(clock, &[], &[]));
+            access.visit_block(code);
+        }
+        _ => return None,
+    }
+    Some(access)
+}
+
+#[derive(Default)]
+struct Lint {
+    path: NamedPath,
+    stack: Vec<(usize, String, NamedPath)>,
+    scopes: Vec<ScopeInfo>,
+    atoms: Vec<AtomInfo>,
+}
+
+impl Probe for Lint {
+    fn visit_start_scope(&mut self, name: &str, node: &dyn Block) {
+        self.path.push(name);
+        self.scopes.push(ScopeInfo {
+            path: self.path.flat("::"),
+            parent: self.stack.last().map(|x| x.0),
+            code: node.hdl(),
+            names: BTreeMap::new(),
+        });
+        self.stack
+            .push((self.scopes.len() - 1, name.to_owned(), NamedPath::default()));
+    }
+
+    fn visit_start_namespace(&mut self, name: &str, _node: &dyn Block) {
+        self.stack.last_mut().unwrap().2.push(name);
+    }
+
+    fn visit_atom(&mut self, name: &str, signal: &dyn Atom) {
+        let (scope, scope_name, namespace) = self.stack.last().unwrap();
+        let namespace = namespace.flat("_");
+        let name = if namespace.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}_{}", namespace, name)
+        };
+        let ndx = self.atoms.len();
+        self.atoms.push(AtomInfo {
+            name: name.clone(),
+            scope: *scope,
+            kind: signal.kind(),
+            connected: signal.connected(),
+        });
+        if signal.kind().is_parameter() {
+            if let Some(parent) = self.scopes[*scope].parent {
+                self.scopes[parent]
+                    .names
+                    .insert(format!("{}_{}", scope_name, name), ndx);
+            }
+        }
+        self.scopes[*scope].names.insert(name, ndx);
+    }
+
+    fn visit_end_namespace(&mut self, _name: &str, _node: &dyn Block) {
+        self.stack.last_mut().unwrap().2.pop();
+    }
+
+    fn visit_end_scope(&mut self, _name: &str, _node: &dyn Block) {
+        self.stack.pop();
+        self.path.pop();
+    }
+}
+
+impl Lint {
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut drivers = vec![BTreeSet::new(); self.atoms.len()];
+        let mut readers = vec![BTreeSet::new(); self.atoms.len()];
+        let mut analyzed = vec![false; self.scopes.len()];
+        for (ndx, scope) in self.scopes.iter().enumerate() {
+            if let Some(access) = signal_access(&scope.code) {
+                analyzed[ndx] = true;
+                for name in &access.writes {
+                    if let Some(atom) = scope.names.get(name) {
+                        drivers[*atom].insert(ndx);
+                    }
+                }
+                for name in &access.reads {
+                    if let Some(atom) = scope.names.get(name) {
+                        readers[*atom].insert(ndx);
+                    }
+                }
+            }
+        }
+        let mut ret = vec![];
+        for (ndx, atom) in self.atoms.iter().enumerate() {
+            let scope = &self.scopes[atom.scope];
+            let path = format!("{}::{}", scope.path, atom.name);
+            let diagnostic = |severity, kind, message: String| Diagnostic {
+                path: path.clone(),
+                severity,
+                kind,
+                message,
+            };
+            let parent_analyzed = scope.parent.is_some_and(|x| analyzed[x]);
+            if drivers[ndx].len() > 1 {
+                let names = drivers[ndx]
+                    .iter()
+                    .map(|x| self.scopes[*x].path.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                ret.push(diagnostic(
+                    Severity::Error,
+                    DiagnosticKind::MultiplyDriven,
+                    format!("is driven by more than one block ({})", names),
+                ));
+            }
+            // The driver of an output is the block itself.  An input may also
+            // be driven from outside the design, e.g. by a testbench.
+            let driven = !drivers[ndx].is_empty()
+                || match atom.kind {
+                    AtomKind::InputParameter => atom.connected,
+                    AtomKind::OutputParameter | AtomKind::LocalSignal => {
+                        !analyzed[atom.scope] && atom.connected
+                    }
+                    _ => true,
+                };
+            if !driven {
+                ret.push(diagnostic(
+                    Severity::Error,
+                    DiagnosticKind::Undriven,
+                    "has no driver".into(),
+                ));
+            }
+            match atom.kind {
+                AtomKind::OutputParameter
+                    if parent_analyzed && !readers[ndx].contains(&scope.parent.unwrap()) =>
+                {
+                    ret.push(diagnostic(
+                        Severity::Warning,
+                        DiagnosticKind::UnusedOutput,
+                        "is an output that is never used".into(),
+                    ))
+                }
+                AtomKind::InputParameter
+                    if analyzed[atom.scope] && !readers[ndx].contains(&atom.scope) =>
+                {
+                    ret.push(diagnostic(
+                        Severity::Warning,
+                        DiagnosticKind::UnreadInput,
+                        "is an input that is never read".into(),
+                    ))
+                }
+                _ => {}
+            }
+        }
+        for (ndx, scope) in self.scopes.iter().enumerate() {
+            let registers = self
+                .atoms
+                .iter()
+                .filter(|x| x.kind == AtomKind::Register && x.scope == ndx)
+                .map(|x| x.name.clone())
+                .collect::<Vec<_>>();
+            let latches = match &scope.code {
+                Verilog::Combinatorial(code) => find_latches(code, &[]),
+                Verilog::Sequential { code, .. } => find_latches(code, &registers),
+                _ => vec![],
+            };
+            for latch in latches {
+                ret.push(Diagnostic {
+                    path: format!("{}::{}", scope.path, latch.signal),
+                    severity: Severity::Error,
+                    kind: DiagnosticKind::Latch,
+                    message: format!(
+                        "is not assigned in the {}, and infers a latch",
+                        latch.branch
+                    ),
+                });
+            }
+        }
+        ret
+    }
+}
+
+pub fn lint(uut: &dyn Block) -> Vec<Diagnostic> {
+    let mut lint = Lint::default();
+    uut.accept("uut", &mut lint);
+    lint.diagnostics()
+}
+
+pub fn lint_errors(uut: &dyn Block) -> Vec<Diagnostic> {
+    lint(uut)
+        .into_iter()
+        .filter(|x| x.severity == Severity::Error)
+        .collect()
+}
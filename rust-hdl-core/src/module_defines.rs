@@ -1,11 +1,23 @@
-use crate::ast::{Verilog, VerilogLiteral};
+use crate::ast::{Verilog, VerilogDeclaration, VerilogLiteral};
 use crate::atom::AtomKind::{StubInputSignal, StubOutputSignal};
 use crate::atom::{Atom, AtomKind};
 use crate::block::Block;
 use crate::code_writer::CodeWriter;
 use crate::named_path::NamedPath;
 use crate::probe::Probe;
-use crate::verilog_gen::verilog_combinatorial;
+use crate::synth::SynthStruct;
+use crate::verilog_gen::{
+    verilog_combinatorial, verilog_declarations, verilog_function, verilog_functions,
+    verilog_sequential, VerilogDialect,
+};
+use crate::vhdl_gen::{
+    vhdl_combinatorial, vhdl_function, vhdl_ident, vhdl_literal, vhdl_sequential, VhdlType,
+    VHDL_CONTEXT, VHDL_SUPPORT_PACKAGE,
+};
+use crate::yosys_gen::{
+    json_entry, json_object, yosys_combinatorial, yosys_sequential, YosysDirection, YosysModule,
+    YosysNet, YosysNetlist, YosysPort, YosysValue,
+};
 use std::collections::BTreeMap;
 
 #[derive(Clone, Debug, Default)]
@@ -19,6 +31,7 @@ struct ModuleDetails {
     atoms: Vec<AtomDetails>,
     sub_modules: Vec<SubModuleInvocation>,
     enums: Vec<EnumDefinition>,
+    structs: Vec<SynthStruct>,
     code: Verilog,
 }
 
@@ -34,6 +47,8 @@ struct AtomDetails {
     name: String,
     kind: AtomKind,
     width: usize,
+    signed: bool,
+    type_name: Option<String>,
     const_val: VerilogLiteral,
 }
 
@@ -45,39 +60,78 @@ fn verilog_atom_name(x: &AtomKind) -> &str {
         AtomKind::StubOutputSignal => "wire",
         AtomKind::Constant => "localparam",
         AtomKind::LocalSignal => "wire",
+        AtomKind::Register => "reg",
     }
 }
 
-fn decl(x: &AtomDetails) -> String {
+fn sv_type(width: usize, signed: bool, type_name: Option<&str>) -> String {
+    if let Some(type_name) = type_name {
+        return type_name.to_owned();
+    }
+    let signed = if signed { " signed" } else { "" };
+    if width == 1 {
+        format!("logic{}", signed)
+    } else {
+        format!("logic{} [{}:0]", signed, width - 1)
+    }
+}
+
+fn decl(x: &AtomDetails, dialect: VerilogDialect) -> String {
+    if dialect == VerilogDialect::SystemVerilog && x.kind != AtomKind::Constant {
+        let data_type = sv_type(x.width, x.signed, x.type_name.as_deref());
+        return match x.kind {
+            AtomKind::InputParameter => format!("input {} {};", data_type, x.name),
+            AtomKind::OutputParameter => format!("output {} {};", data_type, x.name),
+            _ => format!("{} {};", data_type, x.name),
+        };
+    }
+    let kind = if x.signed {
+        format!("{} signed", verilog_atom_name(&x.kind))
+    } else {
+        verilog_atom_name(&x.kind).to_owned()
+    };
     if x.kind == AtomKind::Constant {
-        format!(
-            "{} {} = {};",
-            verilog_atom_name(&x.kind),
-            x.name,
-            x.const_val
-        )
+        if x.signed {
+            format!("{} [{}:0] {} = {};", kind, x.width - 1, x.name, x.const_val)
+        } else {
+            format!("{} {} = {};", kind, x.name, x.const_val)
+        }
     } else {
         if x.width == 1 {
-            format!("{} {};", verilog_atom_name(&x.kind), x.name)
+            format!("{} {};", kind, x.name)
         } else {
-            format!(
-                "{} [{}:0] {};",
-                verilog_atom_name(&x.kind),
-                x.width - 1,
-                x.name
-            )
+            format!("{} [{}:0] {};", kind, x.width - 1, x.name)
         }
     }
 }
 
+fn local_decl(x: &VerilogDeclaration, dialect: VerilogDialect) -> String {
+    if dialect == VerilogDialect::SystemVerilog {
+        return format!("{} {};", sv_type(x.width, x.signed, None), x.name);
+    }
+    let kind = if x.signed { "reg signed" } else { "reg" };
+    if x.width == 1 {
+        format!("{} {};", kind, x.name)
+    } else {
+        format!("{} [{}:0] {};", kind, x.width - 1, x.name)
+    }
+}
+
 #[derive(Default)]
 pub struct ModuleDefines {
     path: NamedPath,
     namespace: NamedPath,
     details: BTreeMap<String, ModuleDetails>,
+    dialect: VerilogDialect,
 }
 
 impl ModuleDefines {
+    pub fn new(dialect: VerilogDialect) -> ModuleDefines {
+        ModuleDefines {
+            dialect,
+            ..Default::default()
+        }
+    }
     fn add_atom(&mut self, module: &str, atom: AtomDetails) {
         let entry = self.details.entry(module.into()).or_default();
         entry.atoms.push(atom)
@@ -91,16 +145,27 @@ impl ModuleDefines {
     }
     fn add_enum(&mut self, module: &str, signal: &dyn Atom) {
         let entry = self.details.entry(module.into()).or_default();
-        let enum_name = signal.type_name();
-        let enum_values = (0..(1 << signal.bits()))
-            .map(|x| EnumDefinition {
-                type_name: enum_name.into(),
-                discriminant: signal.name(x).into(),
-                value: x,
-            })
-            .filter(|x| x.discriminant.len() != 0)
-            .collect::<Vec<_>>();
-        entry.enums.extend(enum_values.into_iter())
+        for synth_enum in signal.enums() {
+            for (discriminant, value) in synth_enum.discriminants {
+                let value = EnumDefinition {
+                    type_name: synth_enum.type_name.into(),
+                    discriminant: discriminant.into(),
+                    value,
+                };
+                if !entry.enums.contains(&value) {
+                    entry.enums.push(value)
+                }
+            }
+        }
+        for synth_struct in signal.structs() {
+            if !entry
+                .structs
+                .iter()
+                .any(|x| x.type_name == synth_struct.type_name)
+            {
+                entry.structs.push(synth_struct)
+            }
+        }
     }
     fn add_code(&mut self, module: &str, code: Verilog) {
         let entry = self.details.entry(module.into()).or_default();
@@ -138,10 +203,18 @@ impl Probe for ModuleDefines {
         } else {
             format!("{}_{}", namespace, name)
         };
+        // Enums and structs are declared with their typedef in SystemVerilog
+        let type_name = if signal.is_struct() || signal.is_enum() {
+            Some(signal.type_name().to_owned())
+        } else {
+            None
+        };
         let param = AtomDetails {
             name: name.clone(),
             kind: signal.kind(),
             width: signal.bits(),
+            signed: signal.is_signed(),
+            type_name: type_name.clone(),
             const_val: signal.verilog(),
         };
         if param.kind.is_parameter() {
@@ -154,14 +227,15 @@ impl Probe for ModuleDefines {
                 name: format!("{}_{}", module_name, name.to_owned()),
                 kind,
                 width: signal.bits(),
+                signed: signal.is_signed(),
+                type_name: type_name.clone(),
                 const_val: signal.verilog(),
             };
             let parent_name = self.path.parent();
             self.add_atom(&parent_name, parent_param);
+            self.add_enum(&parent_name, signal);
         }
-        if signal.is_enum() {
-            self.add_enum(&module_path, signal);
-        }
+        self.add_enum(&module_path, signal);
         self.add_atom(&module_path, param);
     }
 
@@ -174,114 +248,754 @@ impl Probe for ModuleDefines {
     }
 }
 
+// The narrowest width that holds every discriminant
+fn enum_width(variants: &[&EnumDefinition]) -> usize {
+    let max = variants.iter().map(|x| x.value).max().unwrap_or(0);
+    ((usize::BITS - max.leading_zeros()) as usize).max(1)
+}
+
+// Enums become typedefs, with the same <Type>_<Variant> names the localparams use
+fn sv_enum_typedefs(enums: &[EnumDefinition]) -> Vec<String> {
+    let mut type_names: Vec<&str> = vec![];
+    for x in enums {
+        if !type_names.contains(&x.type_name.as_str()) {
+            type_names.push(&x.type_name);
+        }
+    }
+    type_names
+        .iter()
+        .map(|type_name| {
+            let variants = enums
+                .iter()
+                .filter(|x| x.type_name == *type_name)
+                .collect::<Vec<_>>();
+            let width = enum_width(&variants);
+            let mut io = CodeWriter::new();
+            io.add(format!("typedef enum {} {{", sv_type(width, false, None)));
+            io.push();
+            let last = variants.len() - 1;
+            for (ndx, x) in variants.iter().enumerate() {
+                io.add(format!(
+                    "{}_{} = {}'d{}{}",
+                    x.type_name,
+                    x.discriminant,
+                    width,
+                    x.value,
+                    if ndx == last { "" } else { "," }
+                ));
+            }
+            io.pop();
+            io.add(format!("}} {};", type_name));
+            io.to_string()
+        })
+        .collect()
+}
+
+// A packed struct lists its fields MSB first, so they are reversed here
+fn sv_struct_typedef(x: &SynthStruct) -> String {
+    let mut io = CodeWriter::new();
+    io.add("typedef struct packed {");
+    io.push();
+    for field in x.fields.iter().rev() {
+        io.add(format!(
+            "{} {};",
+            sv_type(field.bits, field.signed, field.type_name),
+            field.name
+        ));
+    }
+    io.pop();
+    io.add(format!("}} {};", x.type_name));
+    io.to_string()
+}
+
 impl ModuleDefines {
     pub fn defines(&self) -> String {
         let mut io = CodeWriter::new();
-        self.details
+        let mut modules =
+            self.unique_modules(|name, details, kinds| self.verilog_module(name, details, kinds));
+        modules.reverse();
+        modules.iter().for_each(|x| io.add(x));
+        self.blackboxes().iter().for_each(|x| io.add(x));
+        io.to_string()
+    }
+
+    fn verilog_module(
+        &self,
+        module_name: &str,
+        module_details: &ModuleDetails,
+        kinds: &BTreeMap<String, String>,
+    ) -> String {
+        let dialect = self.dialect;
+        let system_verilog = dialect == VerilogDialect::SystemVerilog;
+        let mut io = CodeWriter::new();
+        let atoms = &module_details.atoms;
+        let args = atoms
             .iter()
-            .filter(|x| x.0.len() != 0)
-            .filter(|x| !matches!(x.1.code, Verilog::Blackbox(_)))
-            .for_each(|k| {
-                let module_name = k.0;
-                let module_details = k.1;
-                let atoms = &module_details.atoms;
-                let args = atoms
-                    .iter()
-                    .filter(|x| x.kind.is_parameter())
-                    .collect::<Vec<_>>();
-                let stubs = atoms
-                    .iter()
-                    .filter(|x| x.kind.is_stub())
-                    .collect::<Vec<_>>();
-                let consts = atoms
-                    .iter()
-                    .filter(|x| x.kind == AtomKind::Constant)
-                    .collect::<Vec<_>>();
-                let locals = atoms
+            .filter(|x| x.kind.is_parameter())
+            .collect::<Vec<_>>();
+        let stubs = atoms
+            .iter()
+            .filter(|x| x.kind.is_stub())
+            .collect::<Vec<_>>();
+        let consts = atoms
+            .iter()
+            .filter(|x| x.kind == AtomKind::Constant)
+            .collect::<Vec<_>>();
+        let locals = atoms
+            .iter()
+            .filter(|x| x.kind == AtomKind::LocalSignal)
+            .collect::<Vec<_>>();
+        let module_args = args
+            .iter()
+            .map(|x| x.name.to_owned())
+            .collect::<Vec<_>>()
+            .join(",");
+        io.add(format!("\n\nmodule {}({});", module_name, module_args));
+        io.push();
+        if system_verilog
+            && (!module_details.enums.is_empty() || !module_details.structs.is_empty())
+        {
+            io.add("\n// Types");
+            sv_enum_typedefs(&module_details.enums)
+                .into_iter()
+                .for_each(|x| io.add(x));
+            module_details
+                .structs
+                .iter()
+                .for_each(|x| io.add(sv_struct_typedef(x)));
+        }
+        if !args.is_empty() {
+            io.add("\n// Module arguments");
+            // The outputs of a wrapper are driven by the instance, so they are not regs
+            let wrapper = matches!(module_details.code, Verilog::Wrapper(_));
+            args.iter().for_each(|x| match decl(x, dialect) {
+                d if wrapper => io.add(d.replacen("output reg", "output wire", 1)),
+                d => io.add(d),
+            });
+        }
+        let submodules = &module_details.sub_modules;
+        if !consts.is_empty() {
+            io.add("\n// Constant declarations");
+            consts.iter().for_each(|x| io.add(decl(x, dialect)));
+        }
+        if !system_verilog && !module_details.enums.is_empty() {
+            io.add("\n// Enums");
+            module_details.enums.iter().for_each(|x| {
+                io.add(format!(
+                    "localparam {}_{} = {};",
+                    x.type_name, x.discriminant, x.value
+                ))
+            });
+        }
+        if !stubs.is_empty() {
+            io.add("\n// Stub signals");
+            stubs.iter().for_each(|x| io.add(decl(x, dialect)));
+        }
+        let registers = atoms
+            .iter()
+            .filter(|x| x.kind == AtomKind::Register)
+            .collect::<Vec<_>>();
+        if !registers.is_empty() {
+            io.add("\n// Registers");
+            for register in &registers {
+                let next = AtomDetails {
+                    name: format!("{}_next", register.name),
+                    ..(*register).clone()
+                };
+                if system_verilog {
+                    // always_ff must be the only driver, so no initial block
+                    let init = match &register.type_name {
+                        Some(type_name) => format!("{}'({})", type_name, register.const_val),
+                        None => register.const_val.to_string(),
+                    };
+                    io.add(format!(
+                        "{} {} = {};",
+                        sv_type(
+                            register.width,
+                            register.signed,
+                            register.type_name.as_deref()
+                        ),
+                        register.name,
+                        init
+                    ));
+                    io.add(decl(&next, dialect));
+                } else {
+                    io.add(decl(register, dialect));
+                    io.add(decl(&next, dialect));
+                    io.add(format!(
+                        "initial {} = {};",
+                        register.name, register.const_val
+                    ));
+                }
+            }
+        }
+        let declarations = match &module_details.code {
+            Verilog::Combinatorial(code) | Verilog::Sequential { code, .. } => {
+                verilog_declarations(code)
+            }
+            _ => vec![],
+        };
+        if !locals.is_empty() || !declarations.is_empty() {
+            io.add("\n// Local signals");
+            locals.iter().for_each(|x| io.add(decl(x, dialect)));
+            declarations
+                .iter()
+                .for_each(|x| io.add(local_decl(x, dialect)));
+        }
+        if !submodules.is_empty() {
+            io.add("\n// Sub module instances");
+            for child in submodules {
+                let entry = self.details.get(&child.kind).unwrap();
+                if !matches!(entry.code, Verilog::Blackbox(_)) {
+                    let child_args = entry
+                        .atoms
+                        .iter()
+                        .filter(|x| {
+                            x.kind == AtomKind::InputParameter
+                                || x.kind == AtomKind::OutputParameter
+                        })
+                        .map(|x| format!(".{}({}_{})", x.name, child.name, x.name))
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    io.add(format!(
+                        "{} {}({});",
+                        kinds[&child.kind], child.name, child_args
+                    ))
+                }
+            }
+        }
+        let mut signed = atoms
+            .iter()
+            .filter(|x| x.signed)
+            .map(|x| x.name.to_owned())
+            .collect::<Vec<_>>();
+        signed.extend(
+            registers
+                .iter()
+                .filter(|x| x.signed)
+                .map(|x| format!("{}_next", x.name)),
+        );
+        if let Verilog::Combinatorial(code) | Verilog::Sequential { code, .. } =
+            &module_details.code
+        {
+            let functions = verilog_functions(code);
+            if !functions.is_empty() {
+                io.add("\n// Functions");
+                functions
                     .iter()
-                    .filter(|x| x.kind == AtomKind::LocalSignal)
-                    .collect::<Vec<_>>();
-                let module_args = args
+                    .for_each(|x| io.add(verilog_function(x, dialect)));
+            }
+        }
+        match &module_details.code {
+            Verilog::Combinatorial(code) => {
+                io.add("\n// Update code");
+                io.add(verilog_combinatorial(code, &signed, dialect));
+            }
+            Verilog::Sequential { clock, code } => {
+                let registers = registers
                     .iter()
                     .map(|x| x.name.to_owned())
-                    .collect::<Vec<_>>()
-                    .join(",");
-                io.add(format!("\n\nmodule {}({});", module_name, module_args));
-                io.push();
-                if !args.is_empty() {
-                    io.add("\n// Module arguments");
-                    args.iter().for_each(|x| io.add(decl(x)));
-                }
-                let submodules = &module_details.sub_modules;
-                if !consts.is_empty() {
-                    io.add("\n// Constant declarations");
-                    consts.iter().for_each(|x| io.add(decl(x)));
-                }
-                if !module_details.enums.is_empty() {
-                    io.add("\n// Enums");
-                    module_details.enums.iter().for_each(|x| {
-                        io.add(format!(
-                            "localparam {}_{} = {}",
-                            x.type_name, x.discriminant, x.value
-                        ))
-                    });
-                }
-                if !stubs.is_empty() {
-                    io.add("\n// Stub signals");
-                    stubs.iter().for_each(|x| io.add(decl(x)));
-                }
-                if !locals.is_empty() {
-                    io.add("\n// Local signals");
-                    locals.iter().for_each(|x| io.add(decl(x)));
-                }
-                if !submodules.is_empty() {
-                    io.add("\n// Sub module instances");
-                    for child in submodules {
-                        let entry = self.details.get(&child.kind).unwrap();
-                        if !matches!(entry.code, Verilog::Blackbox(_)) {
-                            let child_args = entry
-                                .atoms
-                                .iter()
-                                .filter(|x| {
-                                    x.kind == AtomKind::InputParameter
-                                        || x.kind == AtomKind::OutputParameter
-                                })
-                                .map(|x| format!(".{}({}_{})", x.name, child.name, x.name))
-                                .collect::<Vec<_>>()
-                                .join(",");
-                            io.add(format!("{} {}({});", child.kind, child.name, child_args))
-                        }
-                    }
-                }
-                match &module_details.code {
-                    Verilog::Combinatorial(code) => {
-                        io.add("\n// Update code");
-                        io.add(verilog_combinatorial(code));
-                    }
-                    Verilog::Custom(code) => {
-                        io.add("\n// Update code (custom)");
-                        io.add(code);
+                    .collect::<Vec<_>>();
+                io.add("\n// Update code");
+                io.add(verilog_sequential(
+                    code, clock, &signed, &registers, dialect,
+                ));
+            }
+            Verilog::Custom(code) => {
+                io.add("\n// Update code (custom)");
+                io.add(code);
+            }
+            Verilog::Wrapper(wrapper) => {
+                io.add("\n// Update code (wrapper)");
+                io.add(&wrapper.code);
+            }
+            Verilog::Blackbox(_) => {}
+            Verilog::Empty => {}
+        }
+        io.pop();
+        io.add(format!("endmodule // {}", module_name));
+        io.to_string()
+    }
+
+    fn check_modules(&self) {
+        for (module_name, module_details) in &self.details {
+            let atoms = &module_details.atoms;
+            if atoms.iter().any(|x| x.kind == AtomKind::Register)
+                && !matches!(module_details.code, Verilog::Sequential { .. })
+            {
+                panic!(
+                    "Module {} has registers, so its update needs #[hdl_gen(clock = ...)]",
+                    module_name
+                );
+            }
+            if let Verilog::Combinatorial(code) | Verilog::Sequential { code, .. } =
+                &module_details.code
+            {
+                for declaration in verilog_declarations(code) {
+                    if atoms.iter().any(|x| x.name == declaration.name) {
+                        panic!(
+                            "Local {} in module {} has the same name as a signal",
+                            declaration.name, module_name
+                        );
                     }
-                    Verilog::Blackbox(_) => {}
-                    Verilog::Empty => {}
                 }
-                io.pop();
-                io.add(format!("endmodule // {}", module_name));
-            });
+            }
+        }
+    }
+
+    // Modules are rendered bottom up, and each one refers to its children by the kind
+    // of the first module with the same definition.  Identical subtrees thus collapse
+    // into a single definition (named after the first path in the group) that is
+    // instantiated several times.
+    fn module_kinds<F>(&self, render: F) -> BTreeMap<String, String>
+    where
+        F: Fn(&str, &ModuleDetails, &BTreeMap<String, String>) -> String,
+    {
+        self.check_modules();
+        let mut kinds = BTreeMap::new();
+        let mut shapes: BTreeMap<String, String> = BTreeMap::new();
+        for (module_name, module_details) in self.details.iter().rev() {
+            if module_name.is_empty() {
+                continue;
+            }
+            if matches!(module_details.code, Verilog::Blackbox(_)) {
+                kinds.insert(module_name.clone(), module_name.clone());
+                continue;
+            }
+            let shape = render("", module_details, &kinds);
+            let kind = shapes
+                .entry(shape)
+                .or_insert_with(|| module_name.clone())
+                .clone();
+            kinds.insert(module_name.clone(), kind);
+        }
+        let mut first: BTreeMap<String, String> = BTreeMap::new();
+        for (module_name, kind) in &kinds {
+            first
+                .entry(kind.clone())
+                .or_insert_with(|| module_name.clone());
+        }
+        kinds
+            .into_iter()
+            .map(|(module_name, kind)| (module_name, first[&kind].clone()))
+            .collect()
+    }
+
+    // The modules that get a definition of their own, children first
+    fn unique_details(&self, kinds: &BTreeMap<String, String>) -> Vec<(&String, &ModuleDetails)> {
         self.details
             .iter()
-            .filter(|x| matches!(x.1.code, Verilog::Blackbox(_)))
-            .for_each(|k| {
-                if let Verilog::Blackbox(b) = &k.1.code {
-                    io.add(b)
+            .rev()
+            .filter(|(module_name, module_details)| {
+                !module_name.is_empty()
+                    && !matches!(module_details.code, Verilog::Blackbox(_))
+                    && kinds[*module_name] == **module_name
+            })
+            .collect()
+    }
+
+    fn unique_modules<F>(&self, render: F) -> Vec<String>
+    where
+        F: Fn(&str, &ModuleDetails, &BTreeMap<String, String>) -> String,
+    {
+        let kinds = self.module_kinds(&render);
+        self.unique_details(&kinds)
+            .into_iter()
+            .map(|(module_name, module_details)| render(module_name, module_details, &kinds))
+            .collect()
+    }
+
+    // A module can only be declared once, so declarations are matched by the name of
+    // the module they declare, and by their text otherwise
+    fn blackboxes(&self) -> Vec<&String> {
+        let mut blackboxes: Vec<&String> = vec![];
+        let mut modules = vec![];
+        for details in self.details.values() {
+            let b = match &details.code {
+                Verilog::Blackbox(b) => b,
+                Verilog::Wrapper(wrapper) => &wrapper.cores,
+                _ => continue,
+            };
+            match declared_module(b) {
+                Some(module) if modules.contains(&module) => {}
+                Some(module) => {
+                    modules.push(module);
+                    blackboxes.push(b);
                 }
-            });
-        io.to_string()
+                None if !blackboxes.contains(&b) => blackboxes.push(b),
+                None => {}
+            }
+        }
+        blackboxes
     }
 }
 
+fn declared_module(code: &str) -> Option<&str> {
+    let mut words = code.split(|x: char| x.is_whitespace() || x == '(' || x == ';');
+    words.find(|x| *x == "module")?;
+    words.find(|x| !x.is_empty())
+}
+
 pub fn generate_verilog<U: Block>(uut: &U) -> String {
     let mut defines = ModuleDefines::default();
     uut.accept("top", &mut defines);
     defines.defines()
 }
+
+// Interfaces are flattened into <namespace>_<signal> ports, the same as in the
+// Verilog output, so the generated modules are drop-in replacements.
+pub fn generate_system_verilog<U: Block>(uut: &U) -> String {
+    let mut defines = ModuleDefines::new(VerilogDialect::SystemVerilog);
+    uut.accept("top", &mut defines);
+    defines.defines()
+}
+
+impl ModuleDefines {
+    // Each module becomes an entity/architecture pair.  Children are emitted before
+    // their parents so the file can be analyzed in order.
+    pub fn vhdl(&self) -> String {
+        let mut io = CodeWriter::new();
+        io.add(VHDL_SUPPORT_PACKAGE);
+        for (module_name, module_details) in &self.details {
+            if let Verilog::Custom(_) | Verilog::Blackbox(_) | Verilog::Wrapper(_) =
+                module_details.code
+            {
+                panic!(
+                    "Module {} is written in Verilog, which the VHDL back end cannot translate",
+                    module_name
+                );
+            }
+        }
+        self.unique_modules(|name, details, kinds| self.vhdl_module(name, details, kinds))
+            .iter()
+            .for_each(|x| io.add(x));
+        io.to_string()
+    }
+
+    fn vhdl_module(
+        &self,
+        module_name: &str,
+        module_details: &ModuleDetails,
+        kinds: &BTreeMap<String, String>,
+    ) -> String {
+        let mut io = CodeWriter::new();
+        let atoms = &module_details.atoms;
+        let mut types = BTreeMap::new();
+        for atom in atoms {
+            types.insert(atom.name.clone(), VhdlType::new(atom.width, atom.signed));
+            if atom.kind == AtomKind::Register {
+                types.insert(
+                    format!("{}_next", atom.name),
+                    VhdlType::new(atom.width, atom.signed),
+                );
+            }
+        }
+        let mut enums: Vec<(String, usize, usize)> = vec![];
+        for x in &module_details.enums {
+            let variants = module_details
+                .enums
+                .iter()
+                .filter(|y| y.type_name == x.type_name)
+                .collect::<Vec<_>>();
+            let name = format!("{}_{}", x.type_name, x.discriminant);
+            let width = enum_width(&variants);
+            types.insert(name.clone(), VhdlType::new(width, false));
+            enums.push((name, width, x.value));
+        }
+        let ports = atoms
+            .iter()
+            .filter(|x| x.kind.is_parameter())
+            .map(|x| {
+                format!(
+                    "{} : {} {}",
+                    vhdl_ident(&x.name),
+                    if x.kind == AtomKind::InputParameter {
+                        "in"
+                    } else {
+                        "out"
+                    },
+                    VhdlType::new(x.width, x.signed)
+                )
+            })
+            .collect::<Vec<_>>();
+        let entity = vhdl_ident(module_name);
+        io.add_line("");
+        io.add(VHDL_CONTEXT);
+        io.add_line("");
+        io.add(format!("entity {} is", entity));
+        if !ports.is_empty() {
+            io.push();
+            io.add("port (");
+            io.push();
+            io.add(ports.join(";\n"));
+            io.pop();
+            io.add(");");
+            io.pop();
+        }
+        io.add(format!("end entity {};", entity));
+        io.add_line("");
+        io.add(format!("architecture rtl of {} is", entity));
+        io.push();
+        let signal = |x: &AtomDetails| {
+            format!(
+                "signal {} : {};",
+                vhdl_ident(&x.name),
+                VhdlType::new(x.width, x.signed)
+            )
+        };
+        let consts = atoms
+            .iter()
+            .filter(|x| x.kind == AtomKind::Constant)
+            .collect::<Vec<_>>();
+        if !consts.is_empty() {
+            io.add("-- Constant declarations");
+            consts.iter().for_each(|x| {
+                let kind = VhdlType::new(x.width, x.signed);
+                io.add(format!(
+                    "constant {} : {} := {};",
+                    vhdl_ident(&x.name),
+                    kind,
+                    vhdl_literal(x.const_val.value(), kind)
+                ))
+            });
+        }
+        if !enums.is_empty() {
+            io.add("-- Enums");
+            enums.iter().for_each(|(name, width, value)| {
+                let kind = VhdlType::new(*width, false);
+                io.add(format!(
+                    "constant {} : {} := {};",
+                    vhdl_ident(name),
+                    kind,
+                    vhdl_literal(&(*value).into(), kind)
+                ))
+            });
+        }
+        let stubs = atoms
+            .iter()
+            .filter(|x| x.kind.is_stub())
+            .collect::<Vec<_>>();
+        if !stubs.is_empty() {
+            io.add("-- Stub signals");
+            stubs.iter().for_each(|x| io.add(signal(x)));
+        }
+        let registers = atoms
+            .iter()
+            .filter(|x| x.kind == AtomKind::Register)
+            .collect::<Vec<_>>();
+        if !registers.is_empty() {
+            io.add("-- Registers");
+            for register in &registers {
+                let kind = VhdlType::new(register.width, register.signed);
+                io.add(format!(
+                    "signal {} : {} := {};",
+                    vhdl_ident(&register.name),
+                    kind,
+                    vhdl_literal(register.const_val.value(), kind)
+                ));
+                io.add(format!(
+                    "signal {} : {};",
+                    vhdl_ident(&format!("{}_next", register.name)),
+                    kind
+                ));
+            }
+        }
+        let locals = atoms
+            .iter()
+            .filter(|x| x.kind == AtomKind::LocalSignal)
+            .collect::<Vec<_>>();
+        if !locals.is_empty() {
+            io.add("-- Local signals");
+            locals.iter().for_each(|x| io.add(signal(x)));
+        }
+        if let Verilog::Combinatorial(code) | Verilog::Sequential { code, .. } =
+            &module_details.code
+        {
+            let functions = verilog_functions(code);
+            if !functions.is_empty() {
+                io.add("-- Functions");
+                functions.iter().for_each(|x| io.add(vhdl_function(x)));
+            }
+        }
+        io.pop();
+        io.add("begin");
+        io.push();
+        for child in &module_details.sub_modules {
+            let entry = self.details.get(&child.kind).unwrap();
+            let child_args = entry
+                .atoms
+                .iter()
+                .filter(|x| x.kind.is_parameter())
+                .map(|x| {
+                    format!(
+                        "{} => {}",
+                        vhdl_ident(&x.name),
+                        vhdl_ident(&format!("{}_{}", child.name, x.name))
+                    )
+                })
+                .collect::<Vec<_>>();
+            io.add(format!(
+                "{} : entity work.{} port map (",
+                vhdl_ident(&child.name),
+                vhdl_ident(&kinds[&child.kind])
+            ));
+            io.push();
+            io.add(child_args.join(",\n"));
+            io.pop();
+            io.add(");");
+        }
+        let register_names = registers
+            .iter()
+            .map(|x| x.name.to_owned())
+            .collect::<Vec<_>>();
+        match &module_details.code {
+            Verilog::Combinatorial(code) => {
+                io.add(vhdl_combinatorial(code, &types, &register_names));
+            }
+            Verilog::Sequential { clock, code } => {
+                io.add(vhdl_sequential(code, clock, &types, &register_names));
+            }
+            _ => {}
+        }
+        io.pop();
+        io.add("end architecture rtl;");
+        io.to_string()
+    }
+}
+
+pub fn generate_vhdl<U: Block>(uut: &U) -> String {
+    let mut defines = ModuleDefines::default();
+    uut.accept("top", &mut defines);
+    defines.vhdl()
+}
+
+impl ModuleDefines {
+    // Modules with custom Verilog are left for yosys to read from the Verilog side
+    pub fn yosys_netlist(&self) -> YosysNetlist {
+        let kinds =
+            self.module_kinds(|name, details, kinds| self.verilog_module(name, details, kinds));
+        let mut modules = vec![];
+        let mut verilog = CodeWriter::new();
+        for (module_name, module_details) in self.unique_details(&kinds) {
+            match module_details.code {
+                Verilog::Custom(_) | Verilog::Wrapper(_) => {
+                    verilog.add(self.verilog_module(module_name, module_details, &kinds))
+                }
+                _ => modules.push(json_entry(
+                    module_name,
+                    &self
+                        .yosys_module(module_details, &kinds)
+                        .json(module_name == "top"),
+                )),
+            }
+        }
+        self.blackboxes().iter().for_each(|x| verilog.add(x));
+        YosysNetlist {
+            json: json_object(&[
+                "\"creator\": \"rust-hdl\"".into(),
+                json_entry("modules", &json_object(&modules)),
+            ]),
+            verilog: verilog.to_string(),
+        }
+    }
+
+    fn yosys_module(
+        &self,
+        module_details: &ModuleDetails,
+        kinds: &BTreeMap<String, String>,
+    ) -> YosysModule {
+        let mut module = YosysModule::default();
+        let mut signals = BTreeMap::new();
+        for atom in &module_details.atoms {
+            if atom.kind == AtomKind::Constant {
+                signals.insert(
+                    atom.name.clone(),
+                    YosysValue::constant(atom.const_val.value(), atom.width, atom.signed),
+                );
+                continue;
+            }
+            let bits = module.wire(atom.width);
+            if atom.kind.is_parameter() {
+                module.ports.push(YosysPort {
+                    name: atom.name.clone(),
+                    direction: port_direction(&atom.kind),
+                    bits: bits.clone(),
+                });
+            }
+            let init = if atom.kind == AtomKind::Register {
+                let next = format!("{}_next", atom.name);
+                let next_bits = module.wire(atom.width);
+                module.netnames.push(YosysNet {
+                    name: next.clone(),
+                    bits: next_bits.clone(),
+                    init: None,
+                });
+                signals.insert(next, YosysValue::new(next_bits, atom.signed));
+                Some(atom.const_val.value().clone())
+            } else {
+                None
+            };
+            module.netnames.push(YosysNet {
+                name: atom.name.clone(),
+                bits: bits.clone(),
+                init,
+            });
+            signals.insert(atom.name.clone(), YosysValue::new(bits, atom.signed));
+        }
+        for x in &module_details.enums {
+            let variants = module_details
+                .enums
+                .iter()
+                .filter(|y| y.type_name == x.type_name)
+                .collect::<Vec<_>>();
+            signals.insert(
+                format!("{}_{}", x.type_name, x.discriminant),
+                YosysValue::constant(&x.value.into(), enum_width(&variants), false),
+            );
+        }
+        for child in &module_details.sub_modules {
+            let entry = self.details.get(&child.kind).unwrap();
+            let connections = entry
+                .atoms
+                .iter()
+                .filter(|x| x.kind.is_parameter())
+                .map(|x| YosysPort {
+                    name: x.name.clone(),
+                    direction: port_direction(&x.kind),
+                    bits: signals[&format!("{}_{}", child.name, x.name)].bits.clone(),
+                })
+                .collect();
+            module.instance(&kinds[&child.kind], &child.name, connections);
+        }
+        let registers = module_details
+            .atoms
+            .iter()
+            .filter(|x| x.kind == AtomKind::Register)
+            .map(|x| x.name.to_owned())
+            .collect::<Vec<_>>();
+        match &module_details.code {
+            Verilog::Combinatorial(code) => {
+                yosys_combinatorial(&mut module, code, &signals, &[]);
+            }
+            Verilog::Sequential { clock, code } => {
+                yosys_sequential(&mut module, code, clock, &signals, &registers);
+            }
+            _ => {}
+        }
+        module
+    }
+}
+
+fn port_direction(kind: &AtomKind) -> YosysDirection {
+    if *kind == AtomKind::InputParameter {
+        YosysDirection::Input
+    } else {
+        YosysDirection::Output
+    }
+}
+
+pub fn generate_yosys_netlist<U: Block>(uut: &U) -> YosysNetlist {
+    let mut defines = ModuleDefines::default();
+    uut.accept("top", &mut defines);
+    defines.yosys_netlist()
+}
@@ -2,7 +2,8 @@ pub use crate::ast::Verilog;
 pub use crate::ast::VerilogLiteral;
 pub use crate::atom::{Atom, AtomKind};
 pub use crate::bits::clog2;
-pub use crate::bits::{Bit, Bits};
+pub use crate::bits::{bit_cast, concat, mul_wide, Bit, Bits};
+pub use crate::blackbox::BlackBox;
 pub use crate::block::Block;
 pub use crate::check_connected::check_connected;
 pub use crate::clock::freq_hz_to_period_femto;
@@ -13,20 +14,32 @@ pub use crate::clock::NANOS_PER_FEMTO;
 pub use crate::constant::Constant;
 pub use crate::constraint::{Constraint, PeriodicTiming, PinConstraint, SignalType, Timing};
 pub use crate::direction::{In, Local, Out};
+pub use crate::fixed::Fixed;
+pub use crate::hdl_assert;
+pub use crate::hdl_assume;
+pub use crate::latch::{find_latches, Latch};
+pub use crate::lint::{lint, lint_errors, Diagnostic, DiagnosticKind, Severity};
 pub use crate::logic::Logic;
 pub use crate::make_domain;
-pub use crate::module_defines::generate_verilog;
 pub use crate::module_defines::ModuleDefines;
+pub use crate::module_defines::{
+    generate_system_verilog, generate_verilog, generate_vhdl, generate_yosys_netlist,
+};
 pub use crate::named_path::NamedPath;
 pub use crate::probe::Probe;
+pub use crate::reg::Reg;
 pub use crate::signal::Signal;
+pub use crate::signed::{signed_bit_cast, Signed, SignedOutOfRange};
 pub use crate::simulate::simulate;
-pub use crate::simulate::{Sim, Simulation};
+pub use crate::simulate::{check_assertions, AssertionFailure, Sim, SimError, Simulation};
 pub use crate::synth::Synth;
+pub use crate::synth::SynthEnum;
 pub use crate::synth::VCDValue;
 pub use crate::tagged::tagged_bit_cast;
 pub use crate::tagged::Tagged;
 pub use crate::vcd_probe::{write_vcd_change, write_vcd_dump, write_vcd_header};
-pub use crate::verilog_gen::VerilogCodeGenerator;
+pub use crate::verilog_eval::{cross_check, EvalValue, Mismatch, VerilogEvaluator};
+pub use crate::verilog_gen::{VerilogCodeGenerator, VerilogDialect};
 pub use crate::verilog_visitor::VerilogVisitor;
-pub use rust_hdl_macros::{hdl_gen, LogicBlock};
+pub use crate::yosys_gen::YosysNetlist;
+pub use rust_hdl_macros::{hdl_function, hdl_gen, LogicBlock, LogicState, LogicStruct};
@@ -0,0 +1,173 @@
+use std::marker::PhantomData;
+
+use crate::ast::VerilogLiteral;
+use crate::atom::{Atom, AtomKind};
+use crate::block::Block;
+use crate::clock::Domain;
+use crate::constraint::PinConstraint;
+use crate::direction::Direction;
+use crate::logic::Logic;
+use crate::probe::Probe;
+use crate::signal::{get_signal_id, Signal};
+use crate::synth::{Synth, SynthEnum, SynthStruct, VCDValue};
+use crate::tagged::Tagged;
+
+// A register owned by a clocked kernel (#[hdl_gen(clock = ...)]).  The kernel assigns
+// the D input through .next, and .val() gives the Q output, which takes the value of
+// .next on each rising edge of the kernel clock.
+#[derive(Clone, Debug)]
+pub struct Reg<T: Synth, F: Domain> {
+    pub next: Tagged<T, F>,
+    val: T,
+    init: T,
+    last_val: T,
+    last_next: T,
+    changed: bool,
+    claimed: bool,
+    id: usize,
+}
+
+impl<T: Synth, F: Domain> Reg<T, F> {
+    pub fn new(init: T) -> Reg<T, F> {
+        Self {
+            next: Tagged(init, PhantomData),
+            val: init,
+            init,
+            last_val: init,
+            last_next: init,
+            changed: true,
+            claimed: false,
+            id: get_signal_id(),
+        }
+    }
+
+    pub fn val(&self) -> Tagged<T, F> {
+        Tagged(self.val, PhantomData)
+    }
+}
+
+impl<T: Synth, F: Domain> Default for Reg<T, F> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+// Called at the start of every pass through a clocked kernel.  On the clock edge
+// the register loads its D input, and D then defaults to Q so that a register the
+// kernel does not assign holds its value, as it does in the generated Verilog.
+pub trait Clocked {
+    fn clock_edge(&mut self, edge: bool);
+}
+
+impl<T: Synth, F: Domain> Clocked for Reg<T, F> {
+    fn clock_edge(&mut self, edge: bool) {
+        if edge {
+            self.val = self.next.0;
+        }
+        self.next = Tagged(self.val, PhantomData);
+    }
+}
+
+impl<D: Direction, T: Synth, F: Domain> Clocked for Signal<D, T, F> {
+    fn clock_edge(&mut self, _edge: bool) {}
+}
+
+impl<T: Synth, F: Domain> Atom for Reg<T, F> {
+    fn bits(&self) -> usize {
+        T::BITS
+    }
+
+    fn connected(&self) -> bool {
+        self.claimed
+    }
+
+    fn changed(&self) -> bool {
+        self.changed
+    }
+
+    fn kind(&self) -> AtomKind {
+        AtomKind::Register
+    }
+
+    fn is_enum(&self) -> bool {
+        T::ENUM_TYPE
+    }
+
+    fn is_struct(&self) -> bool {
+        T::STRUCT_TYPE
+    }
+
+    fn is_signed(&self) -> bool {
+        T::SIGNED
+    }
+
+    fn name(&self, ndx: usize) -> &'static str {
+        T::name(ndx)
+    }
+
+    fn type_name(&self) -> &'static str {
+        T::TYPE_NAME
+    }
+
+    fn enums(&self) -> Vec<SynthEnum> {
+        T::enums()
+    }
+
+    fn structs(&self) -> Vec<SynthStruct> {
+        T::structs()
+    }
+
+    fn vcd(&self) -> VCDValue {
+        self.val.vcd()
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    // The power on value, which is what the generated Verilog needs
+    fn verilog(&self) -> VerilogLiteral {
+        self.init.verilog()
+    }
+
+    fn val_literal(&self) -> VerilogLiteral {
+        self.val.verilog()
+    }
+
+    fn next_literal(&self) -> VerilogLiteral {
+        self.next.0.verilog()
+    }
+
+    fn constraints(&self) -> Vec<PinConstraint> {
+        vec![]
+    }
+
+    fn clock_frequency(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl<T: Synth, F: Domain> Logic for Reg<T, F> {
+    fn update(&mut self) {}
+    fn connect(&mut self) {
+        self.claimed = true;
+    }
+}
+
+impl<T: Synth, F: Domain> Block for Reg<T, F> {
+    fn connect_all(&mut self) {}
+
+    fn update_all(&mut self) {
+        self.changed = self.val != self.last_val || self.next.0 != self.last_next;
+        self.last_val = self.val;
+        self.last_next = self.next.0;
+    }
+
+    fn has_changed(&self) -> bool {
+        self.changed
+    }
+
+    fn accept(&self, name: &str, probe: &mut dyn Probe) {
+        probe.visit_atom(name, self);
+    }
+}
@@ -5,16 +5,16 @@ use crate::ast::VerilogLiteral;
 use crate::atom::{Atom, AtomKind};
 use crate::block::Block;
 use crate::clock::{Clock, Domain};
-use crate::constraint::{Constraint, PinConstraint};
+use crate::constraint::{Constraint, PinConstraint, SignalType};
 use crate::direction::{Direction, In, Out};
 use crate::logic::Logic;
 use crate::probe::Probe;
-use crate::synth::{Synth, VCDValue};
+use crate::synth::{Synth, SynthEnum, SynthStruct, VCDValue};
 use crate::tagged::Tagged;
 
 static GLOBAL_THREAD_COUNT: AtomicUsize = AtomicUsize::new(1);
 
-fn get_signal_id() -> usize {
+pub(crate) fn get_signal_id() -> usize {
     GLOBAL_THREAD_COUNT.fetch_add(1, Ordering::SeqCst)
 }
 
@@ -46,6 +46,13 @@ impl<D: Direction, T: Synth, F: Domain> Signal<D, T, F> {
             constraint: Constraint::Location(location.to_owned()),
         });
     }
+
+    pub fn add_signal_type(&mut self, index: usize, signal: SignalType) {
+        self.constraints.push(PinConstraint {
+            index,
+            constraint: Constraint::Kind(signal),
+        });
+    }
 }
 
 impl<D: Direction, T: Synth, F: Domain> Atom for Signal<D, T, F> {
@@ -69,6 +76,14 @@ impl<D: Direction, T: Synth, F: Domain> Atom for Signal<D, T, F> {
         T::ENUM_TYPE
     }
 
+    fn is_struct(&self) -> bool {
+        T::STRUCT_TYPE
+    }
+
+    fn is_signed(&self) -> bool {
+        T::SIGNED
+    }
+
     fn name(&self, ndx: usize) -> &'static str {
         T::name(ndx)
     }
@@ -77,6 +92,14 @@ impl<D: Direction, T: Synth, F: Domain> Atom for Signal<D, T, F> {
         T::TYPE_NAME
     }
 
+    fn enums(&self) -> Vec<SynthEnum> {
+        T::enums()
+    }
+
+    fn structs(&self) -> Vec<SynthStruct> {
+        T::structs()
+    }
+
     fn vcd(&self) -> VCDValue {
         self.val.vcd()
     }
@@ -89,9 +112,25 @@ impl<D: Direction, T: Synth, F: Domain> Atom for Signal<D, T, F> {
         self.val.verilog()
     }
 
+    fn val_literal(&self) -> VerilogLiteral {
+        self.val.verilog()
+    }
+
+    fn next_literal(&self) -> VerilogLiteral {
+        self.next.0.verilog()
+    }
+
     fn constraints(&self) -> Vec<PinConstraint> {
         self.constraints.clone()
     }
+
+    fn clock_frequency(&self) -> Option<u64> {
+        if T::CLOCK && F::FREQ != 0 {
+            Some(F::FREQ)
+        } else {
+            None
+        }
+    }
 }
 
 impl<D: Direction, T: Synth, F: Domain> Logic for Signal<D, T, F> {
@@ -163,3 +202,28 @@ impl<D: Direction, T: Synth, F: Domain> Default for Signal<D, T, F> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::atom::Atom;
+    use crate::bits::Bit;
+    use crate::clock::{Async, Clock, Domain};
+    use crate::direction::In;
+    use crate::make_domain;
+    use crate::signal::Signal;
+
+    make_domain!(Mhz100, 100_000_000);
+
+    #[test]
+    fn test_clock_frequency() {
+        assert_eq!(
+            Signal::<In, Clock, Mhz100>::default().clock_frequency(),
+            Some(100_000_000)
+        );
+        assert_eq!(
+            Signal::<In, Clock, Async>::default().clock_frequency(),
+            None
+        );
+        assert_eq!(Signal::<In, Bit, Mhz100>::default().clock_frequency(), None);
+    }
+}
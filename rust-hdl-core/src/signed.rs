@@ -0,0 +1,307 @@
+use crate::ast::VerilogLiteral;
+use crate::bits::{bit_cast, Bits};
+use crate::synth::{Synth, VCDValue};
+use num_bigint::BigUint;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+// Two's complement - the bit pattern is the same as Bits<N>, only the declaration is signed
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Signed<const N: usize>(Bits<N>);
+
+#[inline(always)]
+pub fn signed_bit_cast<const M: usize, const N: usize>(x: Signed<N>) -> Signed<M> {
+    if M > N && x.is_negative() {
+        Signed(bit_cast::<M, N>(x.0) | !bit_cast::<M, N>(Bits::<N>::mask()))
+    } else {
+        Signed(bit_cast::<M, N>(x.0))
+    }
+}
+
+impl<const N: usize> Signed<N> {
+    #[inline(always)]
+    pub fn sign_bit(&self) -> bool {
+        self.0.get_bit(N - 1)
+    }
+
+    #[inline(always)]
+    pub fn is_negative(&self) -> bool {
+        self.sign_bit()
+    }
+
+    #[inline(always)]
+    pub fn as_unsigned(self) -> Bits<N> {
+        self.0
+    }
+
+    pub fn max() -> Signed<N> {
+        Signed(Bits::<N>::mask().replace_bit(N - 1, false))
+    }
+
+    pub fn min() -> Signed<N> {
+        Signed(Bits::<N>::default().replace_bit(N - 1, true))
+    }
+
+    pub fn saturating_add(self, rhs: Signed<N>) -> Signed<N> {
+        let sum = self + rhs;
+        if self.sign_bit() == rhs.sign_bit() && sum.sign_bit() != self.sign_bit() {
+            if self.sign_bit() {
+                Self::min()
+            } else {
+                Self::max()
+            }
+        } else {
+            sum
+        }
+    }
+
+    pub const fn width() -> usize {
+        N
+    }
+}
+
+impl<const N: usize> Bits<N> {
+    #[inline(always)]
+    pub fn as_signed(self) -> Signed<N> {
+        Signed(self)
+    }
+}
+
+impl<const N: usize> From<i64> for Signed<N> {
+    fn from(x: i64) -> Self {
+        let wide = Signed::<128>(Bits::<128>::from(x as i128 as u128));
+        signed_bit_cast::<N, 128>(wide)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SignedOutOfRange;
+
+impl Display for SignedOutOfRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Signed value does not fit in the target type")
+    }
+}
+
+// Wider values only convert if they fit
+impl<const N: usize> TryFrom<Signed<N>> for i64 {
+    type Error = SignedOutOfRange;
+    fn try_from(x: Signed<N>) -> Result<Self, Self::Error> {
+        let narrow = signed_bit_cast::<64, N>(x);
+        if N > 64 && signed_bit_cast::<N, 64>(narrow) != x {
+            return Err(SignedOutOfRange);
+        }
+        let wide: u64 = narrow.0.into();
+        Ok(wide as i64)
+    }
+}
+
+// Formatted from the bits, so it works at any width
+impl<const N: usize> Display for Signed<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // The magnitude of min() is still right, read as unsigned
+        let magnitude = if self.is_negative() {
+            (-*self).0
+        } else {
+            self.0
+        };
+        let mut x = BigUint::default();
+        for i in (0..N).rev() {
+            x = (x << 1_usize) + BigUint::from(magnitude.get_bit(i) as u8);
+        }
+        f.pad_integral(!self.is_negative(), "", &x.to_string())
+    }
+}
+
+impl<const N: usize> Synth for Signed<N> {
+    const BITS: usize = N;
+    const SIGNED: bool = true;
+
+    fn vcd(self) -> VCDValue {
+        self.0.into()
+    }
+
+    fn verilog(self) -> VerilogLiteral {
+        self.0.into()
+    }
+}
+
+impl<const N: usize> std::ops::Add<Signed<N>> for Signed<N> {
+    type Output = Signed<N>;
+
+    #[inline(always)]
+    fn add(self, rhs: Signed<N>) -> Self::Output {
+        Signed(self.0 + rhs.0)
+    }
+}
+
+impl<const N: usize> std::ops::Sub<Signed<N>> for Signed<N> {
+    type Output = Signed<N>;
+
+    #[inline(always)]
+    fn sub(self, rhs: Signed<N>) -> Self::Output {
+        Signed(self.0 - rhs.0)
+    }
+}
+
+impl<const N: usize> std::ops::Neg for Signed<N> {
+    type Output = Signed<N>;
+
+    #[inline(always)]
+    fn neg(self) -> Self::Output {
+        Signed(Bits::<N>::default() - self.0)
+    }
+}
+
+impl<const N: usize> std::ops::Not for Signed<N> {
+    type Output = Signed<N>;
+
+    #[inline(always)]
+    fn not(self) -> Self::Output {
+        Signed(!self.0)
+    }
+}
+
+// Right shifts of a signed value are arithmetic (>>> in Verilog)
+impl<const N: usize> std::ops::Shr<usize> for Signed<N> {
+    type Output = Signed<N>;
+
+    #[inline(always)]
+    fn shr(self, rhs: usize) -> Self::Output {
+        let rhs = rhs.min(N - 1);
+        let shifted = self.0 >> rhs;
+        if self.sign_bit() {
+            Signed(shifted | !(Bits::<N>::mask() >> rhs))
+        } else {
+            Signed(shifted)
+        }
+    }
+}
+
+impl<const N: usize> std::ops::Shl<usize> for Signed<N> {
+    type Output = Signed<N>;
+
+    #[inline(always)]
+    fn shl(self, rhs: usize) -> Self::Output {
+        Signed(self.0 << rhs)
+    }
+}
+
+impl<const N: usize> std::cmp::PartialOrd for Signed<N> {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &Signed<N>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> std::cmp::Ord for Signed<N> {
+    // Flipping the sign bit maps two's complement order onto unsigned order
+    fn cmp(&self, other: &Signed<N>) -> Ordering {
+        let a = self.0.replace_bit(N - 1, !self.sign_bit());
+        let b = other.0.replace_bit(N - 1, !other.sign_bit());
+        a.cmp(&b)
+    }
+}
+
+impl<const N: usize> std::cmp::PartialEq<i64> for Signed<N> {
+    fn eq(&self, other: &i64) -> bool {
+        let other_as_signed: Signed<N> = (*other).into();
+        self.eq(&other_as_signed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bits::Bits;
+    use crate::signed::{signed_bit_cast, Signed, SignedOutOfRange};
+    use std::convert::{TryFrom, TryInto};
+
+    #[test]
+    fn test_round_trip() {
+        for x in [-128_i64, -35, -1, 0, 1, 42, 127] {
+            let y: Signed<8> = x.into();
+            let z: i64 = y.try_into().unwrap();
+            assert_eq!(x, z);
+        }
+        let y: Signed<96> = (-5_i64).into();
+        assert_eq!(i64::try_from(y), Ok(-5));
+        assert_eq!(i64::try_from(Signed::<96>::max()), Err(SignedOutOfRange));
+        assert_eq!(i64::try_from(Signed::<96>::min()), Err(SignedOutOfRange));
+        let y: Signed<8> = 200_i64.into();
+        assert_eq!(y, -56);
+    }
+
+    #[test]
+    fn test_long_round_trip() {
+        let x: Signed<48> = (-123_456_789_i64).into();
+        assert!(x.is_negative());
+        assert_eq!(x, -123_456_789);
+        let y: Signed<160> = (-5_i64).into();
+        assert!(y.as_unsigned().get_bit(159));
+    }
+
+    #[test]
+    fn test_display() {
+        let x: Signed<8> = (-128_i64).into();
+        assert_eq!(x.to_string(), "-128");
+        assert_eq!(format!("{:>5}", Signed::<8>::from(42_i64)), "   42");
+        let x: Signed<96> = (-5_i64).into();
+        assert_eq!(x.to_string(), "-5");
+        assert_eq!(
+            Signed::<96>::max().to_string(),
+            "39614081257132168796771975167"
+        );
+        assert_eq!(
+            Signed::<96>::min().to_string(),
+            "-39614081257132168796771975168"
+        );
+    }
+
+    #[test]
+    fn test_sign_extension() {
+        let x: Signed<4> = (-3_i64).into();
+        let y: Signed<12> = signed_bit_cast(x);
+        assert_eq!(y, -3);
+        assert_eq!(y.as_unsigned(), Bits::<12>::from(0xFFD_u32));
+        let z: Signed<40> = signed_bit_cast(y);
+        assert_eq!(z, -3);
+        let x: Signed<4> = 5_i64.into();
+        let y: Signed<12> = signed_bit_cast(x);
+        assert_eq!(y, 5);
+        let t: Signed<3> = signed_bit_cast(y);
+        assert_eq!(t, -3);
+    }
+
+    #[test]
+    fn test_arithmetic_shift() {
+        let x: Signed<8> = (-100_i64).into();
+        assert_eq!(x >> 2, -25);
+        assert_eq!(x >> 20, -1);
+        let x: Signed<8> = 100_i64.into();
+        assert_eq!(x >> 2, 25);
+        let x: Signed<48> = (-1_000_000_i64).into();
+        assert_eq!(x >> 4, -62_500);
+    }
+
+    #[test]
+    fn test_signed_compare() {
+        let a: Signed<8> = (-5_i64).into();
+        let b: Signed<8> = 3_i64.into();
+        assert!(a < b);
+        assert!(b > a);
+        assert!(Signed::<8>::min() < a);
+        assert!(Signed::<8>::max() > b);
+        assert!(a.as_unsigned() > b.as_unsigned());
+    }
+
+    #[test]
+    fn test_saturating_add() {
+        let a: Signed<8> = 100_i64.into();
+        let b: Signed<8> = 50_i64.into();
+        assert_eq!(a.saturating_add(b), 127);
+        assert_eq!((-a).saturating_add(-b), -128);
+        assert_eq!(a.saturating_add(-b), 50);
+        assert_eq!(a + b, -106);
+    }
+}
@@ -3,23 +3,148 @@ use crossbeam::channel::{RecvError, SendError};
 
 use crate::block::Block;
 use crate::check_connected::check_connected;
+use crate::named_path::NamedPath;
+use crate::probe::Probe;
 use crate::vcd_probe::{write_vcd_change, write_vcd_dump, write_vcd_header};
+use crate::verilog_eval::{verify, KernelCache, Mismatch};
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
 use std::io::Write;
 use std::thread::JoinHandle;
 
+// Once the circuit settles, the kernels must agree with what update() computed
+fn check_hdl(uut: &dyn Block, time: u64, kernels: &KernelCache) -> Result<()> {
+    let mismatches = verify(uut, kernels);
+    if !mismatches.is_empty() {
+        return Err(SimError::HdlMismatch { time, mismatches });
+    }
+    Ok(())
+}
+
+thread_local! {
+    // The address and size of each block whose hdl_assert! failed, with its message
+    static ASSERTION_FAILURES: RefCell<Vec<(usize, usize, String)>> = const { RefCell::new(vec![]) };
+}
+
+fn block_id<T: ?Sized>(block: &T) -> (usize, usize) {
+    (
+        block as *const T as *const u8 as usize,
+        std::mem::size_of_val(block),
+    )
+}
+
+#[doc(hidden)]
+pub fn assertion_failed<T: ?Sized>(block: &T, message: &str) {
+    let (addr, size) = block_id(block);
+    ASSERTION_FAILURES.with(|x| x.borrow_mut().push((addr, size, message.to_owned())));
+}
+
+// Assertions only have to hold once the circuit settles, so the failures of
+// earlier passes are dropped
+fn clear_assertions() {
+    ASSERTION_FAILURES.with(|x| x.borrow_mut().clear());
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssertionFailure {
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for AssertionFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: assertion failed: {}", self.path, self.message)
+    }
+}
+
+struct BlockFinder {
+    id: (usize, usize),
+    path: NamedPath,
+    found: Option<String>,
+}
+
+impl Probe for BlockFinder {
+    fn visit_start_scope(&mut self, name: &str, node: &dyn Block) {
+        self.path.push(name);
+        // A block can share its address with its first field, so the deepest match wins
+        if block_id(node) == self.id {
+            self.found = Some(self.path.flat("::"));
+        }
+    }
+
+    fn visit_end_scope(&mut self, _name: &str, _node: &dyn Block) {
+        self.path.pop();
+    }
+}
+
+// The hdl_assert!s that failed in the last update of uut, by the path of their block
+pub fn check_assertions(uut: &dyn Block) -> Vec<AssertionFailure> {
+    ASSERTION_FAILURES
+        .with(|x| std::mem::take(&mut *x.borrow_mut()))
+        .into_iter()
+        .map(|(addr, size, message)| {
+            let mut finder = BlockFinder {
+                id: (addr, size),
+                path: NamedPath::default(),
+                found: None,
+            };
+            uut.accept("uut", &mut finder);
+            AssertionFailure {
+                path: finder.found.unwrap_or_else(|| "uut".into()),
+                message,
+            }
+        })
+        .collect()
+}
+
+// Within an hdl_gen function, the block is passed in ahead of the condition
+#[macro_export]
+macro_rules! hdl_assert {
+    ($block: expr; $cond: expr, $message: expr) => {{
+        if !$cond {
+            $crate::simulate::assertion_failed($block, $message);
+        }
+    }};
+    ($cond: expr, $message: expr) => {{
+        let _: bool = $cond;
+        let _: &str = $message;
+    }};
+}
+
+// Only formal tools use assumptions
+#[macro_export]
+macro_rules! hdl_assume {
+    ($cond: expr) => {{
+        let _: bool = $cond;
+    }};
+}
+
 pub fn simulate<B: Block>(uut: &mut B, max_iters: usize) -> bool {
     for _ in 0..max_iters {
+        clear_assertions();
         uut.update_all();
         if !uut.has_changed() {
+            if let Some(failure) = check_assertions(uut).first() {
+                panic!("{}", failure);
+            }
             return true;
         }
     }
     false
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum SimError {
     SimTerminated,
+    AssertionFailed {
+        time: u64,
+        path: String,
+        message: String,
+    },
+    HdlMismatch {
+        time: u64,
+        mismatches: Vec<Mismatch>,
+    },
 }
 
 impl From<RecvError> for SimError {
@@ -60,6 +185,9 @@ pub struct Simulation<T> {
     channel_to_sim: Sender<Message<T>>,
     time: u64,
     testbenches: Vec<JoinHandle<Result<()>>>,
+    cross_check: bool,
+    // Only set when cross checking
+    kernels: Option<KernelCache>,
 }
 
 pub struct Sim<T> {
@@ -83,8 +211,21 @@ impl<T: Send + 'static + Block> Simulation<T> {
             channel_to_sim: send,
             time: 0,
             testbenches: vec![],
+            cross_check: false,
+            kernels: None,
         }
     }
+    // Checks that hdl() computes what update() does each time the circuit settles
+    pub fn cross_check_hdl(&mut self, enable: bool) {
+        self.cross_check = enable;
+    }
+    fn load_kernels(&mut self, x: &T) {
+        self.kernels = if self.cross_check {
+            Some(KernelCache::new(x))
+        } else {
+            None
+        };
+    }
     pub fn add_clock<F>(&mut self, interval: u64, clock_fn: F)
     where
         F: Fn(&mut T) -> () + Send + 'static,
@@ -130,8 +271,19 @@ impl<T: Send + 'static + Block> Simulation<T> {
         worker.kind = x.kind;
         // Update the circuit
         for _ in 0..10 {
+            clear_assertions();
             x.circuit.update_all();
             if !x.circuit.has_changed() {
+                if let Some(failure) = check_assertions(&x.circuit).into_iter().next() {
+                    return Err(SimError::AssertionFailed {
+                        time: self.time,
+                        path: failure.path,
+                        message: failure.message,
+                    });
+                }
+                if let Some(kernels) = &self.kernels {
+                    check_hdl(&x.circuit, self.time, kernels)?;
+                }
                 break;
             }
         }
@@ -181,6 +333,7 @@ impl<T: Send + 'static + Block> Simulation<T> {
     }
     pub fn run(&mut self, mut x: T, max_time: u64) -> Result<()> {
         check_connected(&mut x);
+        self.load_kernels(&x);
         // First initialize the workers.
         for id in 0..self.workers.len() {
             x = self.dispatch(id, x)?;
@@ -199,6 +352,7 @@ impl<T: Send + 'static + Block> Simulation<T> {
     }
     pub fn run_traced<W: Write>(&mut self, mut x: T, max_time: u64, trace: W) -> Result<()> {
         check_connected(&mut x);
+        self.load_kernels(&x);
         let mut vcd = write_vcd_header(trace, &x);
         // First initialize the workers.
         for id in 0..self.workers.len() {
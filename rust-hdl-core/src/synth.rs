@@ -21,17 +21,73 @@ impl From<bool> for VCDValue {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct SynthEnum {
+    pub type_name: &'static str,
+    pub discriminants: Vec<(&'static str, usize)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SynthStructField {
+    pub name: &'static str,
+    pub bits: usize,
+    pub signed: bool,
+    // Set when the field is itself an enum or a struct
+    pub type_name: Option<&'static str>,
+}
+
+// Fields are listed in declaration order, which is LSB first in the packed layout
+#[derive(Clone, Debug, PartialEq)]
+pub struct SynthStruct {
+    pub type_name: &'static str,
+    pub fields: Vec<SynthStructField>,
+}
+
 pub trait Synth: Default + Copy + PartialEq + Debug {
     const BITS: usize;
     const ENUM_TYPE: bool = false;
+    const STRUCT_TYPE: bool = false;
+    const SIGNED: bool = false;
+    const CLOCK: bool = false;
     const TYPE_NAME: &'static str = "Bits";
     fn name(_ndx: usize) -> &'static str {
         ""
     }
+    fn enums() -> Vec<SynthEnum> {
+        if !Self::ENUM_TYPE {
+            return vec![];
+        }
+        vec![SynthEnum {
+            type_name: Self::TYPE_NAME,
+            discriminants: (0..(1_usize << Self::BITS))
+                .map(|x| (Self::name(x), x))
+                .filter(|x| !x.0.is_empty())
+                .collect(),
+        }]
+    }
+    fn structs() -> Vec<SynthStruct> {
+        vec![]
+    }
     fn vcd(self) -> VCDValue;
     fn verilog(self) -> VerilogLiteral;
 }
 
+// Lets generated code query the width of a value that may or may not be tagged
+pub trait SynthWidth {
+    fn synth_width(&self) -> usize;
+    fn synth_signed(&self) -> bool;
+}
+
+impl<T: Synth> SynthWidth for T {
+    fn synth_width(&self) -> usize {
+        T::BITS
+    }
+
+    fn synth_signed(&self) -> bool {
+        T::SIGNED
+    }
+}
+
 impl<const N: usize> Synth for Bits<N> {
     const BITS: usize = N;
 
@@ -62,6 +118,7 @@ impl Synth for Bit {
 
 impl Synth for Clock {
     const BITS: usize = 1;
+    const CLOCK: bool = true;
 
     fn vcd(self) -> VCDValue {
         self.0.into()
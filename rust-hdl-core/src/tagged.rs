@@ -3,9 +3,12 @@ use std::marker::PhantomData;
 use crate::bits::bit_cast;
 use crate::bits::Bits;
 use crate::clock::{Clock, Domain};
+use crate::fixed::Fixed;
 use crate::prelude::Synth;
+use crate::signed::Signed;
+use crate::synth::SynthWidth;
 use std::cmp::Ordering;
-use std::ops::{Add, BitAnd, Not, Sub};
+use std::ops::{Add, BitAnd, Deref, DerefMut, Neg, Not, Shr, Sub};
 
 #[derive(Clone, Debug, Copy, PartialEq)]
 pub struct Tagged<T: Synth, F: Domain>(pub T, pub PhantomData<F>);
@@ -40,6 +43,30 @@ impl<T: Synth, F: Domain> Tagged<T, F> {
     }
 }
 
+impl<T: Synth, F: Domain> SynthWidth for Tagged<T, F> {
+    fn synth_width(&self) -> usize {
+        T::BITS
+    }
+
+    fn synth_signed(&self) -> bool {
+        T::SIGNED
+    }
+}
+
+impl<T: Synth, F: Domain> Deref for Tagged<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Synth, F: Domain> DerefMut for Tagged<T, F> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
 impl<T: Synth + BitAnd<bool, Output = T>, F: Domain> BitAnd<bool> for Tagged<T, F> {
     type Output = Tagged<T, F>;
 
@@ -87,6 +114,30 @@ impl<T: Synth + Add<Output = T>, F: Domain> Add<Tagged<T, F>> for Tagged<T, F> {
     }
 }
 
+impl<T: Synth + Sub<Output = T>, F: Domain> Sub<Tagged<T, F>> for Tagged<T, F> {
+    type Output = Tagged<T, F>;
+
+    fn sub(self, rhs: Tagged<T, F>) -> Self::Output {
+        Tagged(self.0 - rhs.0, PhantomData)
+    }
+}
+
+impl<T: Synth + Shr<usize, Output = T>, F: Domain> Shr<usize> for Tagged<T, F> {
+    type Output = Tagged<T, F>;
+
+    fn shr(self, rhs: usize) -> Self::Output {
+        Tagged(self.0 >> rhs, PhantomData)
+    }
+}
+
+impl<T: Synth + Neg<Output = T>, F: Domain> Neg for Tagged<T, F> {
+    type Output = Tagged<T, F>;
+
+    fn neg(self) -> Self::Output {
+        Tagged(-self.0, PhantomData)
+    }
+}
+
 impl<T: Synth + Not<Output = T>, F: Domain> Not for Tagged<T, F> {
     type Output = Tagged<T, F>;
 
@@ -107,6 +158,18 @@ impl<F: Domain, const N: usize> From<Bits<N>> for Tagged<Bits<N>, F> {
     }
 }
 
+impl<F: Domain, const N: usize> From<Signed<N>> for Tagged<Signed<N>, F> {
+    fn from(x: Signed<N>) -> Self {
+        Tagged(x, PhantomData)
+    }
+}
+
+impl<F: Domain, T: Synth, const Q: usize> From<Fixed<T, Q>> for Tagged<Fixed<T, Q>, F> {
+    fn from(x: Fixed<T, Q>) -> Self {
+        Tagged(x, PhantomData)
+    }
+}
+
 impl<T: Synth, F: Domain> PartialEq<T> for Tagged<T, F> {
     fn eq(&self, other: &T) -> bool {
         self.0.eq(other)
@@ -125,6 +188,12 @@ impl<T: Synth + PartialOrd, F: Domain> PartialOrd for Tagged<T, F> {
     }
 }
 
+impl<T: Synth + PartialOrd, F: Domain> PartialOrd<T> for Tagged<T, F> {
+    fn partial_cmp(&self, other: &T) -> Option<Ordering> {
+        self.0.partial_cmp(other)
+    }
+}
+
 impl<F: Domain> From<Clock> for Tagged<Clock, F> {
     fn from(x: Clock) -> Self {
         Tagged(x, PhantomData)
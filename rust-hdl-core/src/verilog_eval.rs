@@ -0,0 +1,667 @@
+use crate::ast::{
+    Verilog, VerilogBlock, VerilogBlockOrConditional, VerilogConditional, VerilogExpression,
+    VerilogFunction, VerilogLiteral, VerilogMatch, VerilogOp, VerilogOpUnary, VerilogStatement,
+};
+use crate::atom::{Atom, AtomKind};
+use crate::block::Block;
+use crate::named_path::NamedPath;
+use crate::probe::Probe;
+use crate::verilog_gen::{
+    ident_fixup, verilog_declarations, with_local_defaults, with_register_defaults, LoopVariable,
+};
+use crate::vhdl_gen::parse_int;
+use num_bigint::{BigInt, BigUint};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalValue {
+    pub value: BigUint,
+    pub width: usize,
+    pub signed: bool,
+    // Unsized literals do not make the other operand unsigned
+    literal: bool,
+}
+
+fn modulus(width: usize) -> BigUint {
+    BigUint::from(1_u32) << width
+}
+
+impl EvalValue {
+    pub fn new(value: &BigUint, width: usize, signed: bool) -> EvalValue {
+        EvalValue {
+            value: value % modulus(width),
+            width,
+            signed,
+            literal: false,
+        }
+    }
+
+    fn bit(x: bool) -> EvalValue {
+        EvalValue::new(&BigUint::from(x as u32), 1, false)
+    }
+
+    fn is_negative(&self) -> bool {
+        self.signed && self.width > 0 && self.value.bit(self.width as u64 - 1)
+    }
+
+    fn extend(&self, width: usize, signed: bool) -> BigUint {
+        let mut value = self.value.clone();
+        if signed && self.is_negative() && width > self.width {
+            value += modulus(width) - modulus(self.width);
+        }
+        value % modulus(width)
+    }
+
+    fn as_bigint(&self) -> BigInt {
+        if self.is_negative() {
+            BigInt::from(self.value.clone()) - BigInt::from(modulus(self.width))
+        } else {
+            BigInt::from(self.value.clone())
+        }
+    }
+
+    fn as_usize(&self) -> usize {
+        let digits = self.value.to_u64_digits();
+        match digits.len() {
+            0 => 0,
+            1 if digits[0] <= usize::MAX as u64 => digits[0] as usize,
+            _ => usize::MAX,
+        }
+    }
+
+    fn is_true(&self) -> bool {
+        self.value != BigUint::default()
+    }
+
+    pub fn literal(&self) -> VerilogLiteral {
+        VerilogLiteral::new(self.value.clone(), self.width)
+    }
+}
+
+// Verilog mixes signed and unsigned operands as unsigned
+fn common_sign(a: &EvalValue, b: &EvalValue) -> bool {
+    match (a.literal, b.literal) {
+        (false, false) => a.signed && b.signed,
+        (false, true) => a.signed,
+        (true, false) => b.signed,
+        (true, true) => false,
+    }
+}
+
+// Executes a kernel on concrete values, with the widths and signedness of the
+// Verilog it is written out as.
+pub struct VerilogEvaluator {
+    env: BTreeMap<String, EvalValue>,
+    loops: Vec<LoopVariable>,
+    registers: Vec<String>,
+    assigned: BTreeSet<String>,
+}
+
+impl VerilogEvaluator {
+    pub fn new(signals: &BTreeMap<String, EvalValue>, registers: &[String]) -> VerilogEvaluator {
+        VerilogEvaluator {
+            env: signals.clone(),
+            loops: vec![],
+            registers: registers.to_vec(),
+            assigned: BTreeSet::new(),
+        }
+    }
+
+    pub fn run(&mut self, code: &VerilogBlock) {
+        self.declare(code);
+        self.block(&with_local_defaults(code));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&EvalValue> {
+        self.env.get(name)
+    }
+
+    // The signals the kernel assigned, with the values they ended up with
+    pub fn assigned(&self) -> BTreeMap<String, EvalValue> {
+        self.assigned
+            .iter()
+            .filter_map(|x| self.env.get(x).map(|v| (x.clone(), v.clone())))
+            .collect()
+    }
+
+    fn fixup(&self, name: &str) -> String {
+        ident_fixup(name, &self.loops, &self.registers)
+    }
+
+    fn signal(&self, name: &str) -> EvalValue {
+        let name = self.fixup(name);
+        if !name.is_empty() && name.chars().all(|c| c.is_ascii_digit()) {
+            let mut value = EvalValue::new(&name.parse().unwrap(), 32, false);
+            value.literal = true;
+            return value;
+        }
+        match self.env.get(&name) {
+            Some(x) => x.clone(),
+            None => panic!("Signal {} has no value in the evaluator", name),
+        }
+    }
+
+    fn declare(&mut self, code: &VerilogBlock) {
+        for x in verilog_declarations(code) {
+            self.env.insert(
+                x.name.clone(),
+                EvalValue::new(&BigUint::default(), x.width, x.signed),
+            );
+        }
+    }
+
+    fn natural_width(&self, e: &VerilogExpression) -> usize {
+        match e {
+            VerilogExpression::Signal(s) => self.signal(s).width,
+            VerilogExpression::Literal(l) => l.bits(),
+            VerilogExpression::Cast(_, bits) => *bits,
+            VerilogExpression::Paren(x)
+            | VerilogExpression::Signed(x)
+            | VerilogExpression::Unsigned(x)
+            | VerilogExpression::IndexReplace(x, _, _) => self.natural_width(x),
+            VerilogExpression::Binary(l, op, r) => match op {
+                VerilogOp::Add
+                | VerilogOp::Sub
+                | VerilogOp::Mul
+                | VerilogOp::BitXor
+                | VerilogOp::BitAnd
+                | VerilogOp::BitOr => self.natural_width(l).max(self.natural_width(r)),
+                VerilogOp::Shl | VerilogOp::Shr => self.natural_width(l),
+                _ => 1,
+            },
+            VerilogExpression::Unary(op, x) => match op {
+                VerilogOpUnary::Not | VerilogOpUnary::Neg => self.natural_width(x),
+                VerilogOpUnary::All | VerilogOpUnary::Any => 1,
+            },
+            VerilogExpression::Index(_, _) => 1,
+            VerilogExpression::Slice(_, width, _) => *width,
+            VerilogExpression::Concat(x) => x.iter().map(|x| self.natural_width(x)).sum(),
+            VerilogExpression::Replicate(x, count) => count * self.natural_width(x),
+            VerilogExpression::Call(f, _) => f.width,
+        }
+    }
+
+    // The hint is the width of the context an expression is evaluated in
+    fn expression(&mut self, e: &VerilogExpression, hint: Option<usize>) -> EvalValue {
+        match e {
+            VerilogExpression::Signal(s) => self.signal(s),
+            VerilogExpression::Literal(l) => {
+                let mut value = EvalValue::new(l.value(), l.bits(), false);
+                value.literal = true;
+                value
+            }
+            VerilogExpression::Cast(x, bits) => {
+                let x = self.expression(x, Some(*bits));
+                EvalValue::new(&x.extend(*bits, x.signed), *bits, false)
+            }
+            VerilogExpression::Paren(x) => self.expression(x, hint),
+            VerilogExpression::Binary(l, op, r) => self.binary(l, op, r, hint),
+            VerilogExpression::Unary(op, x) => self.unary(op, x, hint),
+            VerilogExpression::Index(sig, ndx) => {
+                let base = self.signal(sig);
+                let offset = self.expression(ndx, None).as_usize();
+                slice(&base, 1, offset)
+            }
+            VerilogExpression::Slice(sig, width, offset) => {
+                let base = self.signal(sig);
+                let offset = self.expression(offset, None).as_usize();
+                slice(&base, *width, offset)
+            }
+            VerilogExpression::IndexReplace(sig, ndx, val) => {
+                let base = self.expression(sig, None);
+                let offset = self.expression(ndx, None).as_usize();
+                let value = self.expression(val, None);
+                replace(
+                    &base,
+                    offset,
+                    &EvalValue::new(&value.extend(1, false), 1, false),
+                )
+            }
+            VerilogExpression::Signed(x) | VerilogExpression::Unsigned(x) => {
+                let width = self.natural_width(x);
+                let x = self.expression(x, None);
+                EvalValue::new(
+                    &x.extend(width, x.signed),
+                    width,
+                    matches!(e, VerilogExpression::Signed(_)),
+                )
+            }
+            VerilogExpression::Replicate(x, count) => self.expression(
+                &VerilogExpression::Concat(vec![(**x).clone(); *count]),
+                hint,
+            ),
+            VerilogExpression::Concat(parts) => {
+                let mut value = BigUint::default();
+                let mut total = 0;
+                for x in parts.iter().rev() {
+                    let width = self.natural_width(x);
+                    let x = self.expression(x, None);
+                    value |= x.extend(width, x.signed) << total;
+                    total += width;
+                }
+                EvalValue::new(&value, total, false)
+            }
+            VerilogExpression::Call(f, args) => self.call(f, args),
+        }
+    }
+
+    fn binary(
+        &mut self,
+        l: &VerilogExpression,
+        op: &VerilogOp,
+        r: &VerilogExpression,
+        hint: Option<usize>,
+    ) -> EvalValue {
+        match op {
+            VerilogOp::Add
+            | VerilogOp::Sub
+            | VerilogOp::Mul
+            | VerilogOp::BitXor
+            | VerilogOp::BitAnd
+            | VerilogOp::BitOr => {
+                let width =
+                    hint.unwrap_or_else(|| self.natural_width(l).max(self.natural_width(r)));
+                let lx = self.expression(l, Some(width));
+                let rx = self.expression(r, Some(width));
+                let signed = common_sign(&lx, &rx);
+                let x = lx.extend(width, signed);
+                let y = rx.extend(width, signed);
+                let value = match op {
+                    VerilogOp::Add => x + y,
+                    VerilogOp::Sub => x + modulus(width) - y,
+                    VerilogOp::Mul => x * y,
+                    VerilogOp::BitXor => x ^ y,
+                    VerilogOp::BitAnd => x & y,
+                    _ => x | y,
+                };
+                EvalValue::new(&value, width, signed)
+            }
+            VerilogOp::Shl | VerilogOp::Shr => {
+                let width = self.natural_width(l).max(hint.unwrap_or(0));
+                let lx = self.expression(l, Some(width));
+                let signed = lx.signed && !lx.literal;
+                let a = EvalValue::new(&lx.extend(width, signed), width, signed);
+                let amount = self.expression(r, None).as_usize();
+                let value = match op {
+                    VerilogOp::Shl if amount >= width => BigUint::default(),
+                    VerilogOp::Shl => &a.value << amount,
+                    _ if a.is_negative() => {
+                        let fill = modulus(width) - modulus(width.saturating_sub(amount));
+                        (&a.value >> amount) | fill
+                    }
+                    _ => &a.value >> amount,
+                };
+                EvalValue::new(&value, width, signed)
+            }
+            VerilogOp::LogicalAnd | VerilogOp::LogicalOr => {
+                let a = self.condition(l);
+                let b = self.condition(r);
+                EvalValue::bit(match op {
+                    VerilogOp::LogicalAnd => a && b,
+                    _ => a || b,
+                })
+            }
+            VerilogOp::Eq
+            | VerilogOp::Ne
+            | VerilogOp::Lt
+            | VerilogOp::Le
+            | VerilogOp::Gt
+            | VerilogOp::Ge => {
+                let width = self.natural_width(l).max(self.natural_width(r));
+                let lx = self.expression(l, Some(width));
+                let rx = self.expression(r, Some(width));
+                let signed = common_sign(&lx, &rx);
+                let a = EvalValue::new(&lx.extend(width, signed), width, signed);
+                let b = EvalValue::new(&rx.extend(width, signed), width, signed);
+                let (x, y) = (a.as_bigint(), b.as_bigint());
+                EvalValue::bit(match op {
+                    VerilogOp::Eq => x == y,
+                    VerilogOp::Ne => x != y,
+                    VerilogOp::Lt => x < y,
+                    VerilogOp::Le => x <= y,
+                    VerilogOp::Gt => x > y,
+                    _ => x >= y,
+                })
+            }
+        }
+    }
+
+    fn unary(
+        &mut self,
+        op: &VerilogOpUnary,
+        x: &VerilogExpression,
+        hint: Option<usize>,
+    ) -> EvalValue {
+        match op {
+            VerilogOpUnary::Not | VerilogOpUnary::Neg => {
+                let width = hint.unwrap_or_else(|| self.natural_width(x));
+                let ex = self.expression(x, Some(width));
+                let signed = ex.signed && !ex.literal;
+                let value = ex.extend(width, signed);
+                let value = match op {
+                    VerilogOpUnary::Not => value ^ (modulus(width) - 1_u32),
+                    _ => modulus(width) - value,
+                };
+                EvalValue::new(&value, width, signed)
+            }
+            VerilogOpUnary::All => {
+                let ex = self.expression(x, None);
+                EvalValue::bit(ex.value == modulus(ex.width) - 1_u32)
+            }
+            VerilogOpUnary::Any => {
+                let ex = self.expression(x, None);
+                EvalValue::bit(ex.is_true())
+            }
+        }
+    }
+
+    fn condition(&mut self, e: &VerilogExpression) -> bool {
+        self.expression(e, None).is_true()
+    }
+
+    fn call(&mut self, f: &VerilogFunction, args: &[VerilogExpression]) -> EvalValue {
+        let mut env = BTreeMap::new();
+        for (decl, arg) in f.args.iter().zip(args.iter()) {
+            let x = self.expression(arg, Some(decl.width));
+            env.insert(
+                decl.name.clone(),
+                EvalValue::new(&x.extend(decl.width, x.signed), decl.width, decl.signed),
+            );
+        }
+        env.insert(
+            f.name.clone(),
+            EvalValue::new(&BigUint::default(), f.width, f.signed),
+        );
+        let env = std::mem::replace(&mut self.env, env);
+        let loops = std::mem::take(&mut self.loops);
+        let assigned = std::mem::take(&mut self.assigned);
+        self.run(&f.body);
+        let result = self.env[&f.name].clone();
+        self.env = env;
+        self.loops = loops;
+        self.assigned = assigned;
+        result
+    }
+
+    fn block(&mut self, b: &VerilogBlock) {
+        for s in b {
+            self.statement(s);
+        }
+    }
+
+    fn assign(&mut self, name: &str, value: BigUint) {
+        let target = self.signal(name);
+        self.env.insert(
+            name.to_owned(),
+            EvalValue::new(&value, target.width, target.signed),
+        );
+        self.assigned.insert(name.to_owned());
+    }
+
+    fn statement(&mut self, s: &VerilogStatement) {
+        match s {
+            VerilogStatement::Assignment(target, value) => {
+                let name = match target {
+                    VerilogExpression::Signal(name) => self.fixup(name),
+                    _ => panic!("Only signals can be assigned: {:?}", target),
+                };
+                let width = self.signal(&name).width;
+                let value = self.expression(value, Some(width));
+                self.assign(&name, value.extend(width, value.signed));
+            }
+            VerilogStatement::SliceAssignment {
+                base,
+                width,
+                offset,
+                replacement,
+            } => {
+                let name = self.fixup(base);
+                let target = self.signal(&name);
+                let offset = self.expression(offset, None).as_usize();
+                let value = self.expression(replacement, Some(*width));
+                let value = EvalValue::new(&value.extend(*width, value.signed), *width, false);
+                self.assign(&name, replace(&target, offset, &value).value);
+            }
+            VerilogStatement::If(c) => self.conditional(c),
+            VerilogStatement::Match(m) => self.match_statement(m),
+            VerilogStatement::Loop(l) => {
+                for i in l.from.as_usize()..l.to.as_usize() {
+                    self.loops.push(LoopVariable {
+                        variable: l.index.clone(),
+                        value: i,
+                    });
+                    self.block(&l.block);
+                    self.loops.pop();
+                }
+            }
+            // Assertions are checked by update(), and assumptions constrain the
+            // inputs a formal tool may pick, where a simulation picks its own
+            VerilogStatement::Assert(_, _)
+            | VerilogStatement::Assume(_)
+            | VerilogStatement::Comment(_)
+            | VerilogStatement::Declaration(_) => {}
+        }
+    }
+
+    fn conditional(&mut self, c: &VerilogConditional) {
+        if self.condition(&c.test) {
+            self.block(&c.then);
+        } else {
+            match &c.otherwise {
+                VerilogBlockOrConditional::Block(b) => self.block(b),
+                VerilogBlockOrConditional::Conditional(s) => self.statement(s),
+                VerilogBlockOrConditional::None => {}
+            }
+        }
+    }
+
+    // The first matching case wins, and the default only if none does
+    fn match_statement(&mut self, m: &VerilogMatch) {
+        let mut default = None;
+        for case in &m.cases {
+            let condition = self.fixup(&case.condition);
+            if condition == "default" {
+                default = Some(&case.block);
+                continue;
+            }
+            let value = match parse_int(&condition) {
+                Some(x) => VerilogExpression::Literal(VerilogLiteral::from(x as u64)),
+                None => VerilogExpression::Signal(case.condition.clone()),
+            };
+            let test =
+                VerilogExpression::Binary(Box::new(m.test.clone()), VerilogOp::Eq, Box::new(value));
+            if self.condition(&test) {
+                self.block(&case.block);
+                return;
+            }
+        }
+        if let Some(block) = default {
+            self.block(block);
+        }
+    }
+}
+
+fn slice(base: &EvalValue, width: usize, offset: usize) -> EvalValue {
+    let value = if offset >= base.width {
+        BigUint::default()
+    } else {
+        &base.value >> offset
+    };
+    EvalValue::new(&value, width, false)
+}
+
+fn replace(base: &EvalValue, offset: usize, value: &EvalValue) -> EvalValue {
+    if offset >= base.width {
+        return base.clone();
+    }
+    let mask = (modulus(value.width) - 1_u32) << offset;
+    let kept = &base.value & (modulus(base.width) - 1_u32 - (mask % modulus(base.width)));
+    let value = (&value.value << offset) % modulus(base.width);
+    EvalValue::new(&(kept | value), base.width, base.signed)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    pub path: String,
+    pub update: VerilogLiteral,
+    pub hdl: VerilogLiteral,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: update() gives {:x} but hdl() gives {:x}",
+            self.path, self.update, self.hdl
+        )
+    }
+}
+
+struct ScopeValues {
+    path: String,
+    signals: BTreeMap<String, EvalValue>,
+    // What update() left in .next for each signal, by its name in the kernel
+    next: BTreeMap<String, EvalValue>,
+    registers: Vec<String>,
+}
+
+// The kernel of each block by its path, so that hdl() is only called once per
+// simulation run
+#[derive(Default)]
+pub(crate) struct KernelCache {
+    kernels: BTreeMap<String, Verilog>,
+    path: NamedPath,
+}
+
+impl KernelCache {
+    pub(crate) fn new(uut: &dyn Block) -> KernelCache {
+        let mut cache = KernelCache::default();
+        uut.accept("uut", &mut cache);
+        cache
+    }
+}
+
+impl Probe for KernelCache {
+    fn visit_start_scope(&mut self, name: &str, node: &dyn Block) {
+        self.path.push(name);
+        self.kernels.insert(self.path.flat("::"), node.hdl());
+    }
+
+    fn visit_end_scope(&mut self, _name: &str, _node: &dyn Block) {
+        self.path.pop();
+    }
+}
+
+#[derive(Default)]
+struct ValueCollector {
+    path: NamedPath,
+    stack: Vec<(usize, String, NamedPath)>,
+    scopes: Vec<ScopeValues>,
+}
+
+fn eval_value(x: &VerilogLiteral, width: usize, signed: bool) -> EvalValue {
+    EvalValue::new(x.value(), width, signed)
+}
+
+impl Probe for ValueCollector {
+    fn visit_start_scope(&mut self, name: &str, _node: &dyn Block) {
+        self.path.push(name);
+        self.scopes.push(ScopeValues {
+            path: self.path.flat("::"),
+            signals: BTreeMap::new(),
+            next: BTreeMap::new(),
+            registers: vec![],
+        });
+        self.stack
+            .push((self.scopes.len() - 1, name.to_owned(), NamedPath::default()));
+    }
+
+    fn visit_start_namespace(&mut self, name: &str, _node: &dyn Block) {
+        self.stack.last_mut().unwrap().2.push(name);
+    }
+
+    fn visit_atom(&mut self, name: &str, signal: &dyn Atom) {
+        let (scope, scope_name, namespace) = self.stack.last().unwrap();
+        let namespace = namespace.flat("_");
+        let name = if namespace.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}_{}", namespace, name)
+        };
+        let (width, signed) = (signal.bits(), signal.is_signed());
+        let val = eval_value(&signal.val_literal(), width, signed);
+        let next = eval_value(&signal.next_literal(), width, signed);
+        let mut names = vec![(*scope, name.clone())];
+        if signal.kind().is_parameter() && self.stack.len() > 1 {
+            let parent = self.stack[self.stack.len() - 2].0;
+            names.push((parent, format!("{}_{}", scope_name, name)));
+        }
+        for (scope, name) in names {
+            let values = &mut self.scopes[scope];
+            for x in signal.enums() {
+                for (discriminant, value) in x.discriminants {
+                    values.signals.insert(
+                        format!("{}_{}", x.type_name, discriminant),
+                        EvalValue::new(&BigUint::from(value), 32, false),
+                    );
+                }
+            }
+            if signal.kind() == AtomKind::Register {
+                let d = format!("{}_next", name);
+                values.signals.insert(d.clone(), val.clone());
+                values.next.insert(d, next.clone());
+                values.registers.push(name.clone());
+            } else {
+                values.next.insert(name.clone(), next.clone());
+            }
+            values.signals.insert(name, val.clone());
+        }
+    }
+
+    fn visit_end_namespace(&mut self, _name: &str, _node: &dyn Block) {
+        self.stack.last_mut().unwrap().2.pop();
+    }
+
+    fn visit_end_scope(&mut self, _name: &str, _node: &dyn Block) {
+        self.stack.pop();
+        self.path.pop();
+    }
+}
+
+// Runs the kernel of every block on the current values of the design, and compares
+// what each computes with what update() computed.  This only holds once the design
+// has settled, when each signal's next value is also its current one.
+pub(crate) fn verify(uut: &dyn Block, kernels: &KernelCache) -> Vec<Mismatch> {
+    let mut collector = ValueCollector::default();
+    uut.accept("uut", &mut collector);
+    let mut mismatches = vec![];
+    for scope in &collector.scopes {
+        let (code, registers) = match &kernels.kernels[&scope.path] {
+            Verilog::Combinatorial(code) => (code.clone(), vec![]),
+            Verilog::Sequential { code, .. } => (
+                with_register_defaults(code, &scope.registers),
+                scope.registers.clone(),
+            ),
+            _ => continue,
+        };
+        let mut eval = VerilogEvaluator::new(&scope.signals, &registers);
+        eval.run(&code);
+        for (name, value) in eval.assigned() {
+            if let Some(next) = scope.next.get(&name) {
+                if next.value != value.value {
+                    mismatches.push(Mismatch {
+                        path: format!("{}::{}", scope.path, name),
+                        update: next.literal(),
+                        hdl: value.literal(),
+                    });
+                }
+            }
+        }
+    }
+    mismatches
+}
+
+pub fn cross_check(uut: &dyn Block) -> Vec<Mismatch> {
+    verify(uut, &KernelCache::new(uut))
+}
@@ -1,21 +1,59 @@
 use crate::ast::{
-    VerilogBlock, VerilogBlockOrConditional, VerilogCase, VerilogConditional, VerilogExpression,
-    VerilogLiteral, VerilogLoop, VerilogMatch, VerilogOp, VerilogOpUnary,
+    VerilogBlock, VerilogBlockOrConditional, VerilogCase, VerilogConditional, VerilogDeclaration,
+    VerilogExpression, VerilogFunction, VerilogLiteral, VerilogLoop, VerilogMatch, VerilogOp,
+    VerilogOpUnary, VerilogStatement,
 };
 use crate::code_writer::CodeWriter;
-use crate::verilog_visitor::{walk_block, VerilogVisitor};
+use crate::verilog_visitor::{walk_block, walk_call, VerilogVisitor};
 use evalexpr::ContextWithMutableVariables;
 use num_bigint::BigUint;
 use regex::Regex;
 
-struct LoopVariable {
-    variable: String,
-    value: usize,
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum VerilogDialect {
+    #[default]
+    Verilog,
+    SystemVerilog,
+}
+
+impl VerilogDialect {
+    pub(crate) fn always_comb(&self) -> &'static str {
+        match self {
+            VerilogDialect::Verilog => "always @(*)",
+            VerilogDialect::SystemVerilog => "always_comb",
+        }
+    }
+
+    pub(crate) fn always_ff(&self) -> &'static str {
+        match self {
+            VerilogDialect::Verilog => "always",
+            VerilogDialect::SystemVerilog => "always_ff",
+        }
+    }
+
+    // SystemVerilog uses logic for every variable, and ports and functions get it
+    // as their data type.
+    pub(crate) fn data_type(&self, kind: &str) -> String {
+        match self {
+            VerilogDialect::Verilog => kind.to_owned(),
+            VerilogDialect::SystemVerilog => match kind {
+                "reg" | "wire" => "logic".to_owned(),
+                _ => format!("{} logic", kind),
+            },
+        }
+    }
+}
+
+pub(crate) struct LoopVariable {
+    pub(crate) variable: String,
+    pub(crate) value: usize,
 }
 
 pub struct VerilogCodeGenerator {
     io: CodeWriter,
     loops: Vec<LoopVariable>,
+    signed: Vec<String>,
+    registers: Vec<String>,
 }
 
 impl VerilogCodeGenerator {
@@ -23,46 +61,95 @@ impl VerilogCodeGenerator {
         Self {
             io: CodeWriter::new(),
             loops: vec![],
+            signed: vec![],
+            registers: vec![],
         }
     }
 
-    fn array_index_simplification(&self, a: &str) -> String {
-        let re = Regex::new(r"\[([^\]]*)\]").unwrap();
-        let mut context = evalexpr::HashMapContext::new();
-        for lvar in &self.loops {
-            let _ = context.set_value(lvar.variable.clone(), (lvar.value as i64).into());
+    pub fn new_with_signed(signed: &[String]) -> VerilogCodeGenerator {
+        Self {
+            io: CodeWriter::new(),
+            loops: vec![],
+            signed: signed.to_vec(),
+            registers: vec![],
         }
-        for x in re.captures(a) {
-            if x.len() == 2 {
-                if let Some(txt) = x.get(1) {
-                    let arg = evalexpr::eval_with_context(txt.as_str(), &context).unwrap();
-                    return re.replace(a, format!("_{}", arg)).to_string();
-                }
+    }
+
+    fn is_signed(&self, e: &VerilogExpression) -> bool {
+        match e {
+            VerilogExpression::Signal(s) => self.signed.contains(&self.ident_fixup(s)),
+            VerilogExpression::Paren(x) => self.is_signed(x),
+            VerilogExpression::Binary(l, op, r) => match op {
+                VerilogOp::Add
+                | VerilogOp::Sub
+                | VerilogOp::Mul
+                | VerilogOp::BitXor
+                | VerilogOp::BitAnd
+                | VerilogOp::BitOr => self.is_signed(l) || self.is_signed(r),
+                VerilogOp::Shl | VerilogOp::Shr => self.is_signed(l),
+                _ => false,
+            },
+            VerilogExpression::Unary(VerilogOpUnary::Not | VerilogOpUnary::Neg, x) => {
+                self.is_signed(x)
             }
+            VerilogExpression::Signed(_) => true,
+            VerilogExpression::Call(f, _) => f.signed,
+            _ => false,
+        }
+    }
+
+    // Verilog evaluates a mix of signed and unsigned operands as unsigned, so
+    // literals used alongside a signed operand are promoted with $signed
+    fn visit_operand(&mut self, e: &VerilogExpression, other: &VerilogExpression) {
+        if matches!(e, VerilogExpression::Literal(_)) && self.is_signed(other) {
+            self.visit_signed(e);
+        } else {
+            self.visit_expression(e);
         }
-        a.to_string()
     }
 
     fn ident_fixup(&self, a: &str) -> String {
-        let mut x = a.to_owned();
-        for index in &self.loops {
-            if x == index.variable {
-                x = format!("{}", index.value);
+        ident_fixup(a, &self.loops, &self.registers)
+    }
+}
+
+fn array_index_simplification(a: &str, loops: &[LoopVariable]) -> String {
+    let re = Regex::new(r"\[([^\]]*)\]").unwrap();
+    let mut context = evalexpr::HashMapContext::new();
+    for lvar in loops {
+        let _ = context.set_value(lvar.variable.clone(), (lvar.value as i64).into());
+    }
+    for x in re.captures(a) {
+        if x.len() == 2 {
+            if let Some(txt) = x.get(1) {
+                let arg = evalexpr::eval_with_context(txt.as_str(), &context).unwrap();
+                return re.replace(a, format!("_{}", arg)).to_string();
             }
         }
-        if x.starts_with(".") {
-            x.remove(0);
-        }
-        x = x
-            .replace(".", "_")
-            .replace("::", "_")
-            .trim_end_matches("_next")
-            .to_owned();
-        if x.contains('[') {
-            x = self.array_index_simplification(&x);
+    }
+    a.to_string()
+}
+
+pub(crate) fn ident_fixup(a: &str, loops: &[LoopVariable], registers: &[String]) -> String {
+    let mut x = a.to_owned();
+    for index in loops {
+        if x == index.variable {
+            x = format!("{}", index.value);
         }
-        x
     }
+    if x.starts_with(".") {
+        x.remove(0);
+    }
+    x = x.replace(".", "_").replace("::", "_");
+    // The D input of a register keeps its _next suffix
+    let base = x.trim_end_matches("_next");
+    if !registers.iter().any(|r| r == base) {
+        x = base.to_owned();
+    }
+    if x.contains('[') {
+        x = array_index_simplification(&x, loops);
+    }
+    x
 }
 
 impl ToString for VerilogCodeGenerator {
@@ -71,10 +158,198 @@ impl ToString for VerilogCodeGenerator {
     }
 }
 
-pub fn verilog_combinatorial(code: &VerilogBlock) -> String {
+#[derive(Default)]
+struct DeclarationCollector {
+    declarations: Vec<VerilogDeclaration>,
+}
+
+impl VerilogVisitor for DeclarationCollector {
+    fn visit_declaration(&mut self, d: &VerilogDeclaration) {
+        match self.declarations.iter().find(|x| x.name == d.name) {
+            None => self.declarations.push(d.clone()),
+            Some(x) if x == d => {}
+            Some(_) => panic!(
+                "Local {} is declared more than once with different types",
+                d.name
+            ),
+        }
+    }
+}
+
+pub fn verilog_declarations(code: &VerilogBlock) -> Vec<VerilogDeclaration> {
+    let mut collector = DeclarationCollector::default();
+    collector.visit_block(code);
+    collector.declarations
+}
+
+#[derive(Default)]
+struct FunctionCollector {
+    functions: Vec<VerilogFunction>,
+}
+
+impl VerilogVisitor for FunctionCollector {
+    fn visit_call(&mut self, f: &VerilogFunction, args: &[VerilogExpression]) {
+        if !self.functions.iter().any(|x| x.name == f.name) {
+            self.visit_block(&f.body);
+            self.functions.push(f.clone());
+        }
+        walk_call(self, f, args);
+    }
+}
+
+pub fn verilog_functions(code: &VerilogBlock) -> Vec<VerilogFunction> {
+    let mut collector = FunctionCollector::default();
+    collector.visit_block(code);
+    collector.functions
+}
+
+pub(crate) fn verilog_expression(e: &VerilogExpression) -> String {
     let mut gen = VerilogCodeGenerator::new();
-    gen.visit_block(code);
-    format!("always @(*) {}", gen.to_string())
+    gen.visit_expression(e);
+    gen.io.flush();
+    gen.to_string().trim_end().to_owned()
+}
+
+// Locals declared inside a branch get a default value so they do not infer latches
+pub(crate) fn with_local_defaults(code: &VerilogBlock) -> VerilogBlock {
+    let mut block = verilog_declarations(code)
+        .iter()
+        .filter(|x| {
+            !code
+                .iter()
+                .any(|s| matches!(s, VerilogStatement::Declaration(d) if d.name == x.name))
+        })
+        .map(|x| {
+            VerilogStatement::Assignment(
+                VerilogExpression::Signal(x.name.clone()),
+                VerilogExpression::Literal(VerilogLiteral::zero(x.width)),
+            )
+        })
+        .collect::<Vec<_>>();
+    block.extend(code.iter().cloned());
+    block
+}
+
+// The D input of every register defaults to its current value, so registers the
+// kernel leaves alone hold.
+pub(crate) fn with_register_defaults(code: &VerilogBlock, registers: &[String]) -> VerilogBlock {
+    let mut block = registers
+        .iter()
+        .map(|x| {
+            VerilogStatement::Assignment(
+                VerilogExpression::Signal(format!("{}_next", x)),
+                VerilogExpression::Signal(x.clone()),
+            )
+        })
+        .collect::<Vec<_>>();
+    block.extend(code.iter().cloned());
+    block
+}
+
+fn generate_block(code: &VerilogBlock, signed: &[String], registers: &[String]) -> String {
+    let block = with_local_defaults(code);
+    let mut signed = signed.to_vec();
+    signed.extend(
+        verilog_declarations(code)
+            .iter()
+            .filter(|x| x.signed)
+            .map(|x| x.name.clone()),
+    );
+    let mut gen = VerilogCodeGenerator::new_with_signed(&signed);
+    gen.registers = registers.to_vec();
+    gen.visit_block(&block);
+    gen.to_string()
+}
+
+pub fn verilog_combinatorial(
+    code: &VerilogBlock,
+    signed: &[String],
+    dialect: VerilogDialect,
+) -> String {
+    format!(
+        "{} {}",
+        dialect.always_comb(),
+        generate_block(code, signed, &[])
+    )
+}
+
+// The kernel computes each register's D input (<name>_next) combinatorially, and a
+// single clocked block loads them all.
+pub fn verilog_sequential(
+    code: &VerilogBlock,
+    clock: &str,
+    signed: &[String],
+    registers: &[String],
+    dialect: VerilogDialect,
+) -> String {
+    let block = with_register_defaults(code, registers);
+    let mut io = CodeWriter::new();
+    io.add(format!(
+        "{} {}",
+        dialect.always_comb(),
+        generate_block(&block, signed, registers)
+    ));
+    io.add(format!(
+        "{} @(posedge {}) begin",
+        dialect.always_ff(),
+        clock
+    ));
+    io.push();
+    for x in registers {
+        io.add(format!("{} <= {}_next;", x, x));
+    }
+    io.pop();
+    io.add("end");
+    io.to_string()
+}
+
+fn declaration(kind: &str, name: &str, width: usize, signed: bool) -> String {
+    let signed = if signed { "signed " } else { "" };
+    if width == 1 {
+        format!("{} {}{};", kind, signed, name)
+    } else {
+        format!("{} {}[{}:0] {};", kind, signed, width - 1, name)
+    }
+}
+
+pub fn verilog_function(f: &VerilogFunction, dialect: VerilogDialect) -> String {
+    let mut io = CodeWriter::new();
+    io.add(declaration(
+        &dialect.data_type("function"),
+        &f.name,
+        f.width,
+        f.signed,
+    ));
+    io.push();
+    for arg in &f.args {
+        io.add(declaration(
+            &dialect.data_type("input"),
+            &arg.name,
+            arg.width,
+            arg.signed,
+        ));
+    }
+    for local in verilog_declarations(&f.body) {
+        io.add(declaration(
+            &dialect.data_type("reg"),
+            &local.name,
+            local.width,
+            local.signed,
+        ));
+    }
+    let mut signed = f
+        .args
+        .iter()
+        .filter(|x| x.signed)
+        .map(|x| x.name.clone())
+        .collect::<Vec<_>>();
+    if f.signed {
+        signed.push(f.name.clone());
+    }
+    io.add(generate_block(&f.body, &signed, &[]));
+    io.pop();
+    io.add("endfunction");
+    io.to_string()
 }
 
 impl VerilogVisitor for VerilogCodeGenerator {
@@ -106,7 +381,7 @@ impl VerilogVisitor for VerilogCodeGenerator {
         offset: &VerilogExpression,
         replacement: &VerilogExpression,
     ) {
-        self.io.write(format!("{}[(", base));
+        self.io.write(format!("{}[(", self.ident_fixup(base)));
         self.visit_expression(offset);
         self.io.write(format!(")+:({})] = ", width));
         self.visit_expression(replacement);
@@ -149,6 +424,37 @@ impl VerilogVisitor for VerilogCodeGenerator {
         self.io.add(format!("// {}", x));
     }
 
+    // A property for formal tools (yosys -formal defines FORMAL), and a check that
+    // stops a simulator.  Synthesis sees neither.
+    fn visit_assert(&mut self, test: &VerilogExpression, message: &str) {
+        let message = message.replace('\\', "\\\\").replace('"', "\\\"");
+        self.io.add("`ifdef FORMAL");
+        self.io.write("assert (");
+        self.visit_expression(test);
+        self.io.writeln(");");
+        self.io.add("`else");
+        self.io.add("`ifndef SYNTHESIS");
+        self.io.write("if (!(");
+        self.visit_expression(test);
+        self.io.writeln(")) begin");
+        self.io.push();
+        self.io
+            .add(format!("$display(\"Assertion failed: {}\");", message));
+        self.io.add("$finish;");
+        self.io.pop();
+        self.io.add("end");
+        self.io.add("`endif");
+        self.io.add("`endif");
+    }
+
+    fn visit_assume(&mut self, test: &VerilogExpression) {
+        self.io.add("`ifdef FORMAL");
+        self.io.write("assume (");
+        self.visit_expression(test);
+        self.io.writeln(");");
+        self.io.add("`endif");
+    }
+
     fn visit_signal(&mut self, sig: &str) {
         self.io.write(self.ident_fixup(sig));
     }
@@ -166,7 +472,12 @@ impl VerilogVisitor for VerilogCodeGenerator {
     }
 
     fn visit_binop(&mut self, l: &VerilogExpression, o: &VerilogOp, r: &VerilogExpression) {
-        self.visit_expression(l);
+        let shift = matches!(o, VerilogOp::Shl | VerilogOp::Shr);
+        if shift {
+            self.visit_expression(l);
+        } else {
+            self.visit_operand(l, r);
+        }
         self.io.write(" ");
         self.io.write(match o {
             VerilogOp::Add => "+",
@@ -178,6 +489,7 @@ impl VerilogVisitor for VerilogCodeGenerator {
             VerilogOp::BitAnd => "&",
             VerilogOp::BitOr => "|",
             VerilogOp::Shl => "<<",
+            VerilogOp::Shr if self.is_signed(l) => ">>>",
             VerilogOp::Shr => ">>",
             VerilogOp::Eq => "==",
             VerilogOp::Lt => "<",
@@ -187,7 +499,11 @@ impl VerilogVisitor for VerilogCodeGenerator {
             VerilogOp::Gt => ">",
         });
         self.io.write(" ");
-        self.visit_expression(r);
+        if shift {
+            self.visit_expression(r);
+        } else {
+            self.visit_operand(r, l);
+        }
     }
 
     fn visit_unop(&mut self, o: &VerilogOpUnary, r: &VerilogExpression) {
@@ -234,6 +550,46 @@ impl VerilogVisitor for VerilogCodeGenerator {
         self.io.write(format!(")+:({})]", width));
     }
 
+    fn visit_signed(&mut self, a: &VerilogExpression) {
+        self.io.write("$signed(");
+        self.visit_expression(a);
+        self.io.write(")");
+    }
+
+    fn visit_unsigned(&mut self, a: &VerilogExpression) {
+        self.io.write("$unsigned(");
+        self.visit_expression(a);
+        self.io.write(")");
+    }
+
+    fn visit_concat(&mut self, a: &[VerilogExpression]) {
+        self.io.write("{");
+        for (ndx, x) in a.iter().enumerate() {
+            if ndx != 0 {
+                self.io.write(", ");
+            }
+            self.visit_expression(x);
+        }
+        self.io.write("}");
+    }
+
+    fn visit_replicate(&mut self, a: &VerilogExpression, count: &usize) {
+        self.io.write(format!("{{{}{{", count));
+        self.visit_expression(a);
+        self.io.write("}}");
+    }
+
+    fn visit_call(&mut self, f: &VerilogFunction, args: &[VerilogExpression]) {
+        self.io.write(format!("{}(", f.name));
+        for (ndx, x) in args.iter().enumerate() {
+            if ndx != 0 {
+                self.io.write(", ");
+            }
+            self.visit_expression(x);
+        }
+        self.io.write(")");
+    }
+
     fn visit_index_replace(
         &mut self,
         sig: &VerilogExpression,
@@ -1,7 +1,7 @@
 use crate::ast::{
-    VerilogBlock, VerilogBlockOrConditional, VerilogCase, VerilogConditional, VerilogExpression,
-    VerilogIndexAssignment, VerilogLiteral, VerilogLoop, VerilogMatch, VerilogOp, VerilogOpUnary,
-    VerilogStatement,
+    VerilogBlock, VerilogBlockOrConditional, VerilogCase, VerilogConditional, VerilogDeclaration,
+    VerilogExpression, VerilogFunction, VerilogIndexAssignment, VerilogLiteral, VerilogLoop,
+    VerilogMatch, VerilogOp, VerilogOpUnary, VerilogStatement,
 };
 
 pub trait VerilogVisitor {
@@ -47,6 +47,18 @@ pub trait VerilogVisitor {
         // Terminal
     }
 
+    fn visit_declaration(&mut self, _d: &VerilogDeclaration) {
+        // Terminal
+    }
+
+    fn visit_assert(&mut self, test: &VerilogExpression, _message: &str) {
+        self.visit_expression(test);
+    }
+
+    fn visit_assume(&mut self, test: &VerilogExpression) {
+        self.visit_expression(test);
+    }
+
     fn visit_signal(&mut self, _c: &str) {
         // Terminal
     }
@@ -103,6 +115,58 @@ pub trait VerilogVisitor {
     ) {
         walk_index_replacement(self, a, b, c);
     }
+
+    fn visit_signed(&mut self, a: &VerilogExpression) {
+        walk_signed(self, a);
+    }
+
+    fn visit_unsigned(&mut self, a: &VerilogExpression) {
+        walk_unsigned(self, a);
+    }
+
+    fn visit_concat(&mut self, a: &[VerilogExpression]) {
+        walk_concat(self, a);
+    }
+
+    fn visit_replicate(&mut self, a: &VerilogExpression, count: &usize) {
+        walk_replicate(self, a, count);
+    }
+
+    fn visit_call(&mut self, f: &VerilogFunction, args: &[VerilogExpression]) {
+        walk_call(self, f, args);
+    }
+}
+
+pub fn walk_call<V: VerilogVisitor + ?Sized>(
+    visitor: &mut V,
+    _f: &VerilogFunction,
+    args: &[VerilogExpression],
+) {
+    for x in args {
+        visitor.visit_expression(x);
+    }
+}
+
+pub fn walk_concat<V: VerilogVisitor + ?Sized>(visitor: &mut V, a: &[VerilogExpression]) {
+    for x in a {
+        visitor.visit_expression(x);
+    }
+}
+
+pub fn walk_replicate<V: VerilogVisitor + ?Sized>(
+    visitor: &mut V,
+    a: &VerilogExpression,
+    _count: &usize,
+) {
+    visitor.visit_expression(a);
+}
+
+pub fn walk_signed<V: VerilogVisitor + ?Sized>(visitor: &mut V, a: &VerilogExpression) {
+    visitor.visit_expression(a);
+}
+
+pub fn walk_unsigned<V: VerilogVisitor + ?Sized>(visitor: &mut V, a: &VerilogExpression) {
+    visitor.visit_expression(a);
 }
 
 pub fn walk_index_replacement<V: VerilogVisitor + ?Sized>(
@@ -197,6 +261,15 @@ pub fn walk_statement<V: VerilogVisitor + ?Sized>(visitor: &mut V, s: &VerilogSt
         VerilogStatement::Loop(l) => {
             visitor.visit_loop(l);
         }
+        VerilogStatement::Declaration(d) => {
+            visitor.visit_declaration(d);
+        }
+        VerilogStatement::Assert(test, message) => {
+            visitor.visit_assert(test, message);
+        }
+        VerilogStatement::Assume(test) => {
+            visitor.visit_assume(test);
+        }
     }
 }
 
@@ -294,5 +367,20 @@ pub fn walk_expression<V: VerilogVisitor + ?Sized>(visitor: &mut V, e: &VerilogE
         VerilogExpression::IndexReplace(a, b, c) => {
             visitor.visit_index_replace(a, b, c);
         }
+        VerilogExpression::Signed(a) => {
+            visitor.visit_signed(a);
+        }
+        VerilogExpression::Unsigned(a) => {
+            visitor.visit_unsigned(a);
+        }
+        VerilogExpression::Concat(a) => {
+            visitor.visit_concat(a);
+        }
+        VerilogExpression::Replicate(a, count) => {
+            visitor.visit_replicate(a, count);
+        }
+        VerilogExpression::Call(f, args) => {
+            visitor.visit_call(f, args);
+        }
     }
 }
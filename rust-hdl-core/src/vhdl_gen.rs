@@ -0,0 +1,1081 @@
+use crate::ast::{
+    VerilogBlock, VerilogBlockOrConditional, VerilogConditional, VerilogExpression,
+    VerilogFunction, VerilogLiteral, VerilogMatch, VerilogOp, VerilogOpUnary, VerilogStatement,
+};
+use crate::code_writer::CodeWriter;
+use crate::verilog_gen::{
+    ident_fixup, verilog_declarations, with_local_defaults, with_register_defaults, LoopVariable,
+};
+use num_bigint::BigUint;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+// Shared by every entity, so the expression generator does not have to spell out
+// the conversions between std_logic, boolean and the numeric_std vectors inline.
+pub const VHDL_SUPPORT_PACKAGE: &str = r#"library ieee;
+use ieee.std_logic_1164.all;
+use ieee.numeric_std.all;
+
+package rust_hdl_support is
+    function hdl_bit(x : boolean) return std_logic;
+    function hdl_vec(x : std_logic) return unsigned;
+    function hdl_lsb(x : unsigned) return std_logic;
+    function hdl_lsb(x : signed) return std_logic;
+    function hdl_replace_bit(x : unsigned; ndx : natural; b : std_logic) return unsigned;
+    function hdl_replace_bit(x : signed; ndx : natural; b : std_logic) return signed;
+end package rust_hdl_support;
+
+package body rust_hdl_support is
+    function hdl_bit(x : boolean) return std_logic is
+    begin
+        if x then
+            return '1';
+        end if;
+        return '0';
+    end function;
+
+    function hdl_vec(x : std_logic) return unsigned is
+        variable ret : unsigned(0 downto 0);
+    begin
+        ret(0) := x;
+        return ret;
+    end function;
+
+    function hdl_lsb(x : unsigned) return std_logic is
+    begin
+        return x(x'low);
+    end function;
+
+    function hdl_lsb(x : signed) return std_logic is
+    begin
+        return x(x'low);
+    end function;
+
+    function hdl_replace_bit(x : unsigned; ndx : natural; b : std_logic) return unsigned is
+        variable ret : unsigned(x'length - 1 downto 0) := x;
+    begin
+        ret(ndx) := b;
+        return ret;
+    end function;
+
+    function hdl_replace_bit(x : signed; ndx : natural; b : std_logic) return signed is
+        variable ret : signed(x'length - 1 downto 0) := x;
+    begin
+        ret(ndx) := b;
+        return ret;
+    end function;
+end package body rust_hdl_support;
+"#;
+
+pub const VHDL_CONTEXT: &str = "library ieee;
+use ieee.std_logic_1164.all;
+use ieee.numeric_std.all;
+use work.rust_hdl_support.all;";
+
+const VHDL_KEYWORDS: [&str; 115] = [
+    "abs",
+    "access",
+    "after",
+    "alias",
+    "all",
+    "and",
+    "architecture",
+    "array",
+    "assert",
+    "assume",
+    "assume_guarantee",
+    "attribute",
+    "begin",
+    "block",
+    "body",
+    "buffer",
+    "bus",
+    "case",
+    "component",
+    "configuration",
+    "constant",
+    "context",
+    "cover",
+    "default",
+    "disconnect",
+    "downto",
+    "else",
+    "elsif",
+    "end",
+    "entity",
+    "exit",
+    "fairness",
+    "file",
+    "for",
+    "force",
+    "function",
+    "generate",
+    "generic",
+    "group",
+    "guarded",
+    "if",
+    "impure",
+    "in",
+    "inertial",
+    "inout",
+    "is",
+    "label",
+    "library",
+    "linkage",
+    "literal",
+    "loop",
+    "map",
+    "mod",
+    "nand",
+    "new",
+    "next",
+    "nor",
+    "not",
+    "null",
+    "of",
+    "on",
+    "open",
+    "or",
+    "others",
+    "out",
+    "package",
+    "parameter",
+    "port",
+    "postponed",
+    "procedure",
+    "process",
+    "property",
+    "protected",
+    "pure",
+    "range",
+    "record",
+    "register",
+    "reject",
+    "release",
+    "rem",
+    "report",
+    "restrict",
+    "restrict_guarantee",
+    "return",
+    "rol",
+    "ror",
+    "select",
+    "sequence",
+    "severity",
+    "shared",
+    "signal",
+    "sla",
+    "sll",
+    "sra",
+    "srl",
+    "strong",
+    "subtype",
+    "then",
+    "to",
+    "transport",
+    "type",
+    "unaffected",
+    "units",
+    "until",
+    "use",
+    "variable",
+    "vmode",
+    "vprop",
+    "vunit",
+    "wait",
+    "when",
+    "while",
+    "with",
+    "xnor",
+    "xor",
+];
+
+// Names that are keywords, or that VHDL's identifier rules reject (leading,
+// trailing or doubled underscores), are written as extended identifiers.
+pub fn vhdl_ident(name: &str) -> String {
+    let lower = name.to_lowercase();
+    let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && !name.ends_with('_')
+        && !name.contains("__")
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid || VHDL_KEYWORDS.contains(&lower.as_str()) {
+        format!("\\{}\\", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VhdlType {
+    Boolean,
+    Bit,
+    Vector { width: usize, signed: bool },
+    Integer,
+}
+
+impl VhdlType {
+    // Single bit signals are std_logic, everything else is a numeric_std vector
+    pub fn new(width: usize, signed: bool) -> VhdlType {
+        if width == 1 && !signed {
+            VhdlType::Bit
+        } else {
+            VhdlType::Vector { width, signed }
+        }
+    }
+
+    fn width(&self) -> usize {
+        match self {
+            VhdlType::Boolean | VhdlType::Bit => 1,
+            VhdlType::Vector { width, .. } => *width,
+            VhdlType::Integer => 32,
+        }
+    }
+
+    fn signed(&self) -> Option<bool> {
+        match self {
+            VhdlType::Vector { signed, .. } => Some(*signed),
+            VhdlType::Bit => Some(false),
+            _ => None,
+        }
+    }
+}
+
+impl Display for VhdlType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VhdlType::Boolean => write!(f, "boolean"),
+            VhdlType::Bit => write!(f, "std_logic"),
+            VhdlType::Vector { width, signed } => write!(
+                f,
+                "{}({} downto 0)",
+                if *signed { "signed" } else { "unsigned" },
+                width - 1
+            ),
+            VhdlType::Integer => write!(f, "integer"),
+        }
+    }
+}
+
+pub fn vhdl_literal(value: &BigUint, kind: VhdlType) -> String {
+    match kind {
+        VhdlType::Boolean => (value.bit(0)).to_string(),
+        VhdlType::Bit => if value.bit(0) { "'1'" } else { "'0'" }.into(),
+        VhdlType::Integer => value.to_string(),
+        VhdlType::Vector { width, signed } => {
+            let mask = (BigUint::from(1_u32) << width) - 1_u32;
+            let value = value & mask;
+            let type_name = if signed { "signed" } else { "unsigned" };
+            if width % 4 == 0 {
+                format!("{}'(x\"{:0w$x}\")", type_name, value, w = width / 4)
+            } else {
+                format!("{}'(\"{:0w$b}\")", type_name, value, w = width)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct VhdlExpr {
+    text: String,
+    kind: VhdlType,
+    // Literals take on the width and signedness of wherever they are used
+    literal: Option<BigUint>,
+}
+
+impl VhdlExpr {
+    fn new(text: String, kind: VhdlType) -> VhdlExpr {
+        VhdlExpr {
+            text,
+            kind,
+            literal: None,
+        }
+    }
+
+    fn signed(&self) -> Option<bool> {
+        if self.literal.is_some() {
+            None
+        } else {
+            self.kind.signed()
+        }
+    }
+}
+
+fn cast(text: String, signed: bool) -> String {
+    format!("{}({})", if signed { "signed" } else { "unsigned" }, text)
+}
+
+// Verilog mixes signed and unsigned operands as unsigned
+fn common_sign(a: &VhdlExpr, b: &VhdlExpr) -> bool {
+    match (a.signed(), b.signed()) {
+        (Some(x), Some(y)) => x && y,
+        (Some(x), None) | (None, Some(x)) => x,
+        (None, None) => false,
+    }
+}
+
+// Converts an expression to the given type.  Operands are reinterpreted and then
+// extended, while assigned values are extended according to their own signedness
+// and then reinterpreted, which is what Verilog does in each case.
+fn convert(x: &VhdlExpr, to: VhdlType, operand: bool) -> String {
+    if x.kind == to {
+        return x.text.clone();
+    }
+    if let Some(value) = &x.literal {
+        return vhdl_literal(value, to);
+    }
+    match to {
+        VhdlType::Boolean => match x.kind {
+            VhdlType::Bit => format!("({} = '1')", x.text),
+            _ => format!("({} /= 0)", x.text),
+        },
+        VhdlType::Integer => match x.kind {
+            VhdlType::Vector { .. } => format!("to_integer({})", x.text),
+            VhdlType::Bit => format!("to_integer(hdl_vec({}))", x.text),
+            _ => format!("to_integer(hdl_vec(hdl_bit({})))", x.text),
+        },
+        VhdlType::Bit => match x.kind {
+            VhdlType::Boolean => format!("hdl_bit({})", x.text),
+            VhdlType::Integer => format!("hdl_lsb(to_unsigned({}, 32))", x.text),
+            _ => format!("hdl_lsb({})", x.text),
+        },
+        VhdlType::Vector { width, signed } => {
+            let (text, from_width, from_signed) = match x.kind {
+                VhdlType::Integer => {
+                    return format!(
+                        "to_{}({}, {})",
+                        if signed { "signed" } else { "unsigned" },
+                        x.text,
+                        width
+                    )
+                }
+                VhdlType::Boolean => (format!("hdl_vec(hdl_bit({}))", x.text), 1, false),
+                VhdlType::Bit => (format!("hdl_vec({})", x.text), 1, false),
+                VhdlType::Vector { width, signed } => (x.text.clone(), width, signed),
+            };
+            let mut text = text;
+            if operand && from_signed != signed {
+                text = cast(text, signed);
+            }
+            if from_width != width {
+                text = format!("resize({}, {})", text, width);
+            }
+            if !operand && from_signed != signed {
+                text = cast(text, signed);
+            }
+            text
+        }
+    }
+}
+
+pub struct VhdlCodeGenerator {
+    io: CodeWriter,
+    loops: Vec<LoopVariable>,
+    registers: Vec<String>,
+    types: BTreeMap<String, VhdlType>,
+    variables: Vec<String>,
+    result: Option<String>,
+}
+
+impl VhdlCodeGenerator {
+    pub fn new(types: &BTreeMap<String, VhdlType>, registers: &[String]) -> VhdlCodeGenerator {
+        Self {
+            io: CodeWriter::new(),
+            loops: vec![],
+            registers: registers.to_vec(),
+            types: types.clone(),
+            variables: vec![],
+            result: None,
+        }
+    }
+
+    fn fixup(&self, name: &str) -> String {
+        let name = ident_fixup(name, &self.loops, &self.registers);
+        match &self.result {
+            Some(result) if *result == name => format!("{}_result", name),
+            _ => name,
+        }
+    }
+
+    fn lookup(&self, name: &str) -> VhdlType {
+        match self.types.get(name) {
+            Some(kind) => *kind,
+            None => panic!("Signal {} has no known type in the VHDL generator", name),
+        }
+    }
+
+    fn signal(&self, name: &str) -> VhdlExpr {
+        let name = self.fixup(name);
+        if name.chars().all(|c| c.is_ascii_digit()) {
+            VhdlExpr::new(name, VhdlType::Integer)
+        } else {
+            VhdlExpr::new(vhdl_ident(&name), self.lookup(&name))
+        }
+    }
+
+    fn natural_width(&self, e: &VerilogExpression) -> usize {
+        match e {
+            VerilogExpression::Signal(s) => self.signal(s).kind.width(),
+            VerilogExpression::Literal(l) => l.bits(),
+            VerilogExpression::Cast(_, bits) => *bits,
+            VerilogExpression::Paren(x)
+            | VerilogExpression::Signed(x)
+            | VerilogExpression::Unsigned(x)
+            | VerilogExpression::IndexReplace(x, _, _) => self.natural_width(x),
+            VerilogExpression::Binary(l, op, r) => match op {
+                VerilogOp::Add
+                | VerilogOp::Sub
+                | VerilogOp::Mul
+                | VerilogOp::BitXor
+                | VerilogOp::BitAnd
+                | VerilogOp::BitOr => self.natural_width(l).max(self.natural_width(r)),
+                VerilogOp::Shl | VerilogOp::Shr => self.natural_width(l),
+                _ => 1,
+            },
+            VerilogExpression::Unary(op, x) => match op {
+                VerilogOpUnary::Not | VerilogOpUnary::Neg => self.natural_width(x),
+                VerilogOpUnary::All | VerilogOpUnary::Any => 1,
+            },
+            VerilogExpression::Index(_, _) => 1,
+            VerilogExpression::Slice(_, width, _) => *width,
+            VerilogExpression::Concat(x) => x.iter().map(|x| self.natural_width(x)).sum(),
+            VerilogExpression::Replicate(x, count) => count * self.natural_width(x),
+            VerilogExpression::Call(f, _) => f.width,
+        }
+    }
+
+    fn integer(&self, e: &VerilogExpression) -> (String, Option<usize>) {
+        let x = self.expression(e, None);
+        match (&x.literal, x.kind) {
+            (Some(value), _) => (value.to_string(), Some(value.to_string().parse().unwrap())),
+            (None, VhdlType::Integer) => {
+                let value = x.text.parse().ok();
+                (x.text, value)
+            }
+            _ => (convert(&x, VhdlType::Integer, true), None),
+        }
+    }
+
+    // The hint is the width of the context an arithmetic expression is evaluated in.
+    // Only the low bits of +, -, *, ~ and the bitwise operators reach the result, so
+    // they are computed at that width directly.
+    fn expression(&self, e: &VerilogExpression, hint: Option<usize>) -> VhdlExpr {
+        match e {
+            VerilogExpression::Signal(s) => self.signal(s),
+            VerilogExpression::Literal(l) => {
+                let kind = VhdlType::new(l.bits(), false);
+                VhdlExpr {
+                    text: vhdl_literal(l.value(), kind),
+                    kind,
+                    literal: Some(l.value().clone()),
+                }
+            }
+            VerilogExpression::Cast(x, bits) => {
+                let x = self.expression(x, Some(*bits));
+                let kind = VhdlType::Vector {
+                    width: *bits,
+                    signed: false,
+                };
+                VhdlExpr::new(convert(&x, kind, false), kind)
+            }
+            // Every compound expression is already parenthesized
+            VerilogExpression::Paren(x) => self.expression(x, hint),
+            VerilogExpression::Binary(l, op, r) => self.binary(l, op, r, hint),
+            VerilogExpression::Unary(op, x) => self.unary(op, x, hint),
+            VerilogExpression::Index(sig, ndx) => {
+                let base = self.signal(sig);
+                if base.kind == VhdlType::Bit {
+                    return base;
+                }
+                let (ndx, _) = self.integer(ndx);
+                VhdlExpr::new(format!("{}({})", base.text, ndx), VhdlType::Bit)
+            }
+            VerilogExpression::Slice(sig, width, offset) => {
+                let base = self.signal(sig);
+                let kind = VhdlType::Vector {
+                    width: *width,
+                    signed: false,
+                };
+                match base.kind {
+                    VhdlType::Vector { signed, .. } => {
+                        let text = self.slice(&base.text, *width, offset);
+                        let text = if signed { cast(text, false) } else { text };
+                        VhdlExpr::new(text, kind)
+                    }
+                    _ => VhdlExpr::new(convert(&base, kind, false), kind),
+                }
+            }
+            VerilogExpression::IndexReplace(sig, ndx, val) => {
+                let sig = self.expression(sig, None);
+                let val = self.expression(val, None);
+                if sig.kind == VhdlType::Bit {
+                    return VhdlExpr::new(convert(&val, VhdlType::Bit, false), VhdlType::Bit);
+                }
+                let (ndx, _) = self.integer(ndx);
+                VhdlExpr::new(
+                    format!(
+                        "hdl_replace_bit({}, {}, {})",
+                        sig.text,
+                        ndx,
+                        convert(&val, VhdlType::Bit, false)
+                    ),
+                    sig.kind,
+                )
+            }
+            VerilogExpression::Signed(x) | VerilogExpression::Unsigned(x) => {
+                let signed = matches!(e, VerilogExpression::Signed(_));
+                let width = self.natural_width(x);
+                let x = self.expression(x, None);
+                let kind = VhdlType::Vector { width, signed };
+                VhdlExpr::new(convert(&x, kind, true), kind)
+            }
+            VerilogExpression::Replicate(x, count) => self.expression(
+                &VerilogExpression::Concat(vec![(**x).clone(); *count]),
+                hint,
+            ),
+            VerilogExpression::Concat(parts) => {
+                let width = self.natural_width(e);
+                let text = parts
+                    .iter()
+                    .map(|x| {
+                        let kind = VhdlType::Vector {
+                            width: self.natural_width(x),
+                            signed: false,
+                        };
+                        convert(&self.expression(x, None), kind, true)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" & ");
+                VhdlExpr::new(
+                    format!("unsigned'({})", text),
+                    VhdlType::Vector {
+                        width,
+                        signed: false,
+                    },
+                )
+            }
+            VerilogExpression::Call(f, args) => {
+                let args = f
+                    .args
+                    .iter()
+                    .zip(args.iter())
+                    .map(|(decl, arg)| {
+                        let kind = VhdlType::new(decl.width, decl.signed);
+                        convert(&self.expression(arg, Some(decl.width)), kind, false)
+                    })
+                    .collect::<Vec<_>>();
+                let text = if args.is_empty() {
+                    vhdl_ident(&f.name)
+                } else {
+                    format!("{}({})", vhdl_ident(&f.name), args.join(", "))
+                };
+                VhdlExpr::new(text, VhdlType::new(f.width, f.signed))
+            }
+        }
+    }
+
+    fn slice(&self, base: &str, width: usize, offset: &VerilogExpression) -> String {
+        match self.integer(offset) {
+            (_, Some(offset)) => format!("{}({} downto {})", base, offset + width - 1, offset),
+            (offset, None) => format!("{}({} + {} downto {})", base, offset, width - 1, offset),
+        }
+    }
+
+    fn binary(
+        &self,
+        l: &VerilogExpression,
+        op: &VerilogOp,
+        r: &VerilogExpression,
+        hint: Option<usize>,
+    ) -> VhdlExpr {
+        match op {
+            VerilogOp::Add
+            | VerilogOp::Sub
+            | VerilogOp::Mul
+            | VerilogOp::BitXor
+            | VerilogOp::BitAnd
+            | VerilogOp::BitOr => {
+                let text_op = match op {
+                    VerilogOp::Add => "+",
+                    VerilogOp::Sub => "-",
+                    VerilogOp::Mul => "*",
+                    VerilogOp::BitXor => "xor",
+                    VerilogOp::BitAnd => "and",
+                    _ => "or",
+                };
+                let width =
+                    hint.unwrap_or_else(|| self.natural_width(l).max(self.natural_width(r)));
+                let lx = self.expression(l, Some(width));
+                let rx = self.expression(r, Some(width));
+                let bitwise = !matches!(op, VerilogOp::Add | VerilogOp::Sub | VerilogOp::Mul);
+                let single = |x: &VhdlExpr| {
+                    matches!(x.kind, VhdlType::Bit | VhdlType::Boolean) && x.literal.is_none()
+                };
+                if bitwise && (single(&lx) || single(&rx)) && width == 1 {
+                    return VhdlExpr::new(
+                        format!(
+                            "({} {} {})",
+                            convert(&lx, VhdlType::Bit, true),
+                            text_op,
+                            convert(&rx, VhdlType::Bit, true)
+                        ),
+                        VhdlType::Bit,
+                    );
+                }
+                let kind = VhdlType::Vector {
+                    width,
+                    signed: common_sign(&lx, &rx),
+                };
+                let text = format!(
+                    "({} {} {})",
+                    convert(&lx, kind, true),
+                    text_op,
+                    convert(&rx, kind, true)
+                );
+                let text = if matches!(op, VerilogOp::Mul) {
+                    format!("resize({}, {})", text, width)
+                } else {
+                    text
+                };
+                VhdlExpr::new(text, kind)
+            }
+            VerilogOp::Shl | VerilogOp::Shr => {
+                let width = self.natural_width(l).max(hint.unwrap_or(0));
+                let lx = self.expression(l, Some(width));
+                let kind = VhdlType::Vector {
+                    width,
+                    signed: lx.signed().unwrap_or(false),
+                };
+                let (amount, _) = self.integer(r);
+                VhdlExpr::new(
+                    format!(
+                        "{}({}, {})",
+                        if matches!(op, VerilogOp::Shl) {
+                            "shift_left"
+                        } else {
+                            "shift_right"
+                        },
+                        convert(&lx, kind, true),
+                        amount
+                    ),
+                    kind,
+                )
+            }
+            VerilogOp::LogicalAnd | VerilogOp::LogicalOr => {
+                let lx = self.expression(l, None);
+                let rx = self.expression(r, None);
+                VhdlExpr::new(
+                    format!(
+                        "({} {} {})",
+                        convert(&lx, VhdlType::Boolean, true),
+                        if matches!(op, VerilogOp::LogicalAnd) {
+                            "and"
+                        } else {
+                            "or"
+                        },
+                        convert(&rx, VhdlType::Boolean, true)
+                    ),
+                    VhdlType::Boolean,
+                )
+            }
+            VerilogOp::Eq
+            | VerilogOp::Ne
+            | VerilogOp::Lt
+            | VerilogOp::Le
+            | VerilogOp::Gt
+            | VerilogOp::Ge => {
+                let text_op = match op {
+                    VerilogOp::Eq => "=",
+                    VerilogOp::Ne => "/=",
+                    VerilogOp::Lt => "<",
+                    VerilogOp::Le => "<=",
+                    VerilogOp::Gt => ">",
+                    _ => ">=",
+                };
+                let lx = self.expression(l, None);
+                let rx = self.expression(r, None);
+                let kind = match (lx.kind, rx.kind) {
+                    (VhdlType::Boolean, VhdlType::Boolean) => Some(VhdlType::Boolean),
+                    (VhdlType::Integer, VhdlType::Integer) => Some(VhdlType::Integer),
+                    (VhdlType::Bit | VhdlType::Boolean, VhdlType::Bit | VhdlType::Boolean)
+                        if lx.literal.is_none() || rx.literal.is_none() =>
+                    {
+                        Some(VhdlType::Bit)
+                    }
+                    _ => None,
+                };
+                let text = match kind {
+                    Some(kind) => format!(
+                        "({} {} {})",
+                        convert(&lx, kind, true),
+                        text_op,
+                        convert(&rx, kind, true)
+                    ),
+                    None => {
+                        // A literal is compared at the width of the other side if it fits
+                        let fits = |x: &VhdlExpr, other: &VerilogExpression| {
+                            x.literal
+                                .as_ref()
+                                .is_some_and(|v| v.bits() as usize <= self.natural_width(other))
+                        };
+                        let width = if fits(&lx, r) {
+                            self.natural_width(r)
+                        } else if fits(&rx, l) {
+                            self.natural_width(l)
+                        } else {
+                            self.natural_width(l).max(self.natural_width(r))
+                        };
+                        let lx = self.expression(l, Some(width));
+                        let rx = self.expression(r, Some(width));
+                        let kind = VhdlType::Vector {
+                            width,
+                            signed: common_sign(&lx, &rx),
+                        };
+                        format!(
+                            "({} {} {})",
+                            convert(&lx, kind, true),
+                            text_op,
+                            convert(&rx, kind, true)
+                        )
+                    }
+                };
+                VhdlExpr::new(text, VhdlType::Boolean)
+            }
+        }
+    }
+
+    fn unary(&self, op: &VerilogOpUnary, x: &VerilogExpression, hint: Option<usize>) -> VhdlExpr {
+        match op {
+            VerilogOpUnary::Not | VerilogOpUnary::Neg => {
+                let width = hint.unwrap_or_else(|| self.natural_width(x));
+                let ex = self.expression(x, Some(width));
+                if matches!(op, VerilogOpUnary::Not)
+                    && matches!(ex.kind, VhdlType::Boolean | VhdlType::Bit)
+                    && ex.literal.is_none()
+                    && width == 1
+                {
+                    return VhdlExpr::new(format!("(not {})", ex.text), ex.kind);
+                }
+                let kind = VhdlType::Vector {
+                    width,
+                    signed: ex.signed().unwrap_or(false),
+                };
+                let text = convert(&ex, kind, true);
+                let text = match (op, kind) {
+                    (VerilogOpUnary::Not, _) => format!("(not {})", text),
+                    (_, VhdlType::Vector { signed: true, .. }) => format!("(-{})", text),
+                    _ => format!("(0 - {})", text),
+                };
+                VhdlExpr::new(text, kind)
+            }
+            VerilogOpUnary::All | VerilogOpUnary::Any => {
+                let ex = self.expression(x, None);
+                let text = match ex.kind {
+                    VhdlType::Vector { .. } => format!(
+                        "({} {})",
+                        if matches!(op, VerilogOpUnary::All) {
+                            "and"
+                        } else {
+                            "or"
+                        },
+                        ex.text
+                    ),
+                    _ => convert(&ex, VhdlType::Bit, true),
+                };
+                VhdlExpr::new(text, VhdlType::Bit)
+            }
+        }
+    }
+
+    fn condition(&self, e: &VerilogExpression) -> String {
+        convert(&self.expression(e, None), VhdlType::Boolean, true)
+    }
+
+    fn block(&mut self, b: &VerilogBlock) {
+        for s in b {
+            self.statement(s);
+        }
+    }
+
+    fn statement(&mut self, s: &VerilogStatement) {
+        match s {
+            VerilogStatement::Assignment(target, value) => {
+                let name = match target {
+                    VerilogExpression::Signal(name) => self.fixup(name),
+                    _ => panic!("Only signals can be assigned in VHDL: {:?}", target),
+                };
+                let kind = self.lookup(&name);
+                let value = self.expression(value, Some(kind.width()));
+                self.io.add(format!(
+                    "{} {} {};",
+                    vhdl_ident(&name),
+                    self.assign_op(&name),
+                    convert(&value, kind, false)
+                ));
+            }
+            VerilogStatement::SliceAssignment {
+                base,
+                width,
+                offset,
+                replacement,
+            } => {
+                let name = self.fixup(base);
+                let target = match self.lookup(&name) {
+                    VhdlType::Vector { signed, .. } => (
+                        self.slice(&vhdl_ident(&name), *width, offset),
+                        VhdlType::Vector {
+                            width: *width,
+                            signed,
+                        },
+                    ),
+                    kind => (vhdl_ident(&name), kind),
+                };
+                let value = self.expression(replacement, Some(*width));
+                self.io.add(format!(
+                    "{} {} {};",
+                    target.0,
+                    self.assign_op(&name),
+                    convert(&value, target.1, false)
+                ));
+            }
+            VerilogStatement::If(c) => {
+                self.conditional(c, "if");
+                self.io.add("end if;");
+            }
+            VerilogStatement::Match(m) => self.match_statement(m),
+            VerilogStatement::Loop(l) => {
+                for i in l.from.as_usize()..l.to.as_usize() {
+                    self.loops.push(LoopVariable {
+                        variable: l.index.clone(),
+                        value: i,
+                    });
+                    self.block(&l.block);
+                    self.loops.pop();
+                }
+            }
+            VerilogStatement::Comment(x) => self.io.add(format!("-- {}", x)),
+            VerilogStatement::Declaration(_) => {}
+            VerilogStatement::Assert(test, message) => self.io.add(format!(
+                "assert {} report \"{}\" severity failure;",
+                self.condition(test),
+                message.replace('"', "\"\"")
+            )),
+            VerilogStatement::Assume(_) => {}
+        }
+    }
+
+    fn assign_op(&self, name: &str) -> &'static str {
+        if self.variables.iter().any(|x| x == name) {
+            ":="
+        } else {
+            "<="
+        }
+    }
+
+    fn conditional(&mut self, c: &VerilogConditional, keyword: &str) {
+        self.io
+            .add(format!("{} {} then", keyword, self.condition(&c.test)));
+        self.io.push();
+        self.block(&c.then);
+        self.io.pop();
+        match &c.otherwise {
+            VerilogBlockOrConditional::Block(b) => {
+                self.io.add("else");
+                self.io.push();
+                self.block(b);
+                self.io.pop();
+            }
+            VerilogBlockOrConditional::Conditional(s) => match s.as_ref() {
+                VerilogStatement::If(c) => self.conditional(c, "elsif"),
+                s => {
+                    self.io.add("else");
+                    self.io.push();
+                    self.statement(s);
+                    self.io.pop();
+                }
+            },
+            VerilogBlockOrConditional::None => {}
+        }
+    }
+
+    // A case needs a locally static selector in VHDL, which slices of a struct are
+    // not, so matches become if/elsif chains.
+    fn match_statement(&mut self, m: &VerilogMatch) {
+        let mut keyword = "if";
+        let mut default = None;
+        for case in &m.cases {
+            let condition = self.fixup(&case.condition);
+            if condition == "default" {
+                default = Some(&case.block);
+                continue;
+            }
+            let value = match parse_int(&condition) {
+                Some(x) => VerilogExpression::Literal(VerilogLiteral::from(x as u64)),
+                None => VerilogExpression::Signal(case.condition.clone()),
+            };
+            let test =
+                VerilogExpression::Binary(Box::new(m.test.clone()), VerilogOp::Eq, Box::new(value));
+            self.io
+                .add(format!("{} {} then", keyword, self.condition(&test)));
+            self.io.push();
+            self.block(&case.block);
+            self.io.pop();
+            keyword = "elsif";
+        }
+        match default {
+            Some(block) if keyword == "if" => self.block(block),
+            Some(block) => {
+                self.io.add("else");
+                self.io.push();
+                self.block(block);
+                self.io.pop();
+                self.io.add("end if;");
+            }
+            None if keyword == "elsif" => self.io.add("end if;"),
+            None => {}
+        }
+    }
+}
+
+impl Display for VhdlCodeGenerator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.io.to_string())
+    }
+}
+
+pub(crate) fn parse_int(x: &str) -> Option<usize> {
+    let x = x.replace('_', "");
+    // Drop a type suffix such as u32
+    let x = match x.find(['u', 'i']) {
+        Some(ndx) => &x[0..ndx],
+        None => &x,
+    };
+    if let Some(hex) = x.strip_prefix("0x") {
+        usize::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = x.strip_prefix("0b") {
+        usize::from_str_radix(bin, 2).ok()
+    } else {
+        x.parse().ok()
+    }
+}
+
+fn process_variables(gen: &mut VhdlCodeGenerator, code: &VerilogBlock) -> Vec<String> {
+    verilog_declarations(code)
+        .iter()
+        .map(|x| {
+            let kind = VhdlType::new(x.width, x.signed);
+            gen.types.insert(x.name.clone(), kind);
+            gen.variables.push(x.name.clone());
+            format!("variable {} : {};", vhdl_ident(&x.name), kind)
+        })
+        .collect()
+}
+
+pub fn vhdl_combinatorial(
+    code: &VerilogBlock,
+    types: &BTreeMap<String, VhdlType>,
+    registers: &[String],
+) -> String {
+    let mut gen = VhdlCodeGenerator::new(types, registers);
+    let variables = process_variables(&mut gen, code);
+    let mut io = CodeWriter::new();
+    io.add("process(all)");
+    io.push();
+    variables.iter().for_each(|x| io.add(x));
+    io.pop();
+    io.add("begin");
+    io.push();
+    gen.block(&with_local_defaults(code));
+    io.add(gen.to_string());
+    io.pop();
+    io.add("end process;");
+    io.to_string()
+}
+
+pub fn vhdl_sequential(
+    code: &VerilogBlock,
+    clock: &str,
+    types: &BTreeMap<String, VhdlType>,
+    registers: &[String],
+) -> String {
+    let mut io = CodeWriter::new();
+    io.add(vhdl_combinatorial(
+        &with_register_defaults(code, registers),
+        types,
+        registers,
+    ));
+    let clock = vhdl_ident(&ident_fixup(clock, &[], &[]));
+    io.add(format!("process({})", clock));
+    io.add("begin");
+    io.push();
+    io.add(format!("if rising_edge({}) then", clock));
+    io.push();
+    for x in registers {
+        io.add(format!(
+            "{} <= {};",
+            vhdl_ident(x),
+            vhdl_ident(&format!("{}_next", x))
+        ));
+    }
+    io.pop();
+    io.add("end if;");
+    io.pop();
+    io.add("end process;");
+    io.to_string()
+}
+
+pub fn vhdl_function(f: &VerilogFunction) -> String {
+    let mut types = BTreeMap::new();
+    for arg in &f.args {
+        types.insert(arg.name.clone(), VhdlType::new(arg.width, arg.signed));
+    }
+    let result = format!("{}_result", f.name);
+    let kind = VhdlType::new(f.width, f.signed);
+    types.insert(result.clone(), kind);
+    let mut gen = VhdlCodeGenerator::new(&types, &[]);
+    gen.result = Some(f.name.clone());
+    gen.variables.push(result.clone());
+    let variables = process_variables(&mut gen, &f.body);
+    let args = f
+        .args
+        .iter()
+        .map(|x| {
+            format!(
+                "{} : {}",
+                vhdl_ident(&x.name),
+                VhdlType::new(x.width, x.signed)
+            )
+        })
+        .collect::<Vec<_>>();
+    let return_type = match kind {
+        VhdlType::Vector { signed: true, .. } => "signed",
+        VhdlType::Vector { .. } => "unsigned",
+        _ => "std_logic",
+    };
+    let mut io = CodeWriter::new();
+    if args.is_empty() {
+        io.add(format!(
+            "function {} return {} is",
+            vhdl_ident(&f.name),
+            return_type
+        ));
+    } else {
+        io.add(format!(
+            "function {}({}) return {} is",
+            vhdl_ident(&f.name),
+            args.join("; "),
+            return_type
+        ));
+    }
+    io.push();
+    io.add(format!("variable {} : {};", vhdl_ident(&result), kind));
+    variables.iter().for_each(|x| io.add(x));
+    io.pop();
+    io.add("begin");
+    io.push();
+    gen.block(&with_local_defaults(&f.body));
+    io.add(gen.to_string());
+    io.add(format!("return {};", vhdl_ident(&result)));
+    io.pop();
+    io.add("end function;");
+    io.to_string()
+}
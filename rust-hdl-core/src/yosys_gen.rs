@@ -0,0 +1,1038 @@
+use crate::ast::{
+    VerilogBlock, VerilogBlockOrConditional, VerilogConditional, VerilogExpression,
+    VerilogFunction, VerilogLiteral, VerilogMatch, VerilogOp, VerilogOpUnary, VerilogStatement,
+};
+use crate::code_writer::CodeWriter;
+use crate::verilog_gen::{
+    ident_fixup, verilog_declarations, with_local_defaults, with_register_defaults, LoopVariable,
+};
+use crate::vhdl_gen::parse_int;
+use num_bigint::BigUint;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+// The output of the Yosys back end.  Modules written in Verilog (custom and blackbox
+// code) cannot be turned into a netlist, so they are instantiated by name from the
+// JSON and their definitions are collected in verilog, which has to be read as well.
+#[derive(Clone, Debug, Default)]
+pub struct YosysNetlist {
+    pub json: String,
+    pub verilog: String,
+}
+
+// Bits are numbered from 2, as 0 and 1 are the constants in the JSON format
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum YosysBit {
+    Zero,
+    One,
+    Net(usize),
+}
+
+impl Display for YosysBit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            YosysBit::Zero => write!(f, "\"0\""),
+            YosysBit::One => write!(f, "\"1\""),
+            YosysBit::Net(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+impl From<bool> for YosysBit {
+    fn from(x: bool) -> Self {
+        if x {
+            YosysBit::One
+        } else {
+            YosysBit::Zero
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct YosysValue {
+    pub bits: Vec<YosysBit>,
+    pub signed: bool,
+    // Literals take on the signedness of whatever they are combined with
+    literal: bool,
+}
+
+impl YosysValue {
+    pub fn new(bits: Vec<YosysBit>, signed: bool) -> YosysValue {
+        YosysValue {
+            bits,
+            signed,
+            literal: false,
+        }
+    }
+
+    pub fn constant(value: &BigUint, width: usize, signed: bool) -> YosysValue {
+        YosysValue::new(
+            (0..width).map(|i| value.bit(i as u64).into()).collect(),
+            signed,
+        )
+    }
+
+    fn width(&self) -> usize {
+        self.bits.len()
+    }
+
+    fn value(&self) -> Option<BigUint> {
+        let mut x = BigUint::default();
+        for (ndx, bit) in self.bits.iter().enumerate() {
+            match bit {
+                YosysBit::Zero => {}
+                YosysBit::One => x.set_bit(ndx as u64, true),
+                YosysBit::Net(_) => return None,
+            }
+        }
+        Some(x)
+    }
+
+    fn index(&self) -> Option<usize> {
+        self.value()
+            .and_then(|x| x.to_u64_digits().first().copied().or(Some(0)))
+            .map(|x| x as usize)
+    }
+
+    // Truncates or extends the bits, sign extending if asked to
+    fn extend(&self, width: usize, signed: bool) -> Vec<YosysBit> {
+        let fill = match self.bits.last() {
+            Some(x) if signed => *x,
+            _ => YosysBit::Zero,
+        };
+        (0..width)
+            .map(|i| self.bits.get(i).copied().unwrap_or(fill))
+            .collect()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum YosysDirection {
+    Input,
+    Output,
+}
+
+impl Display for YosysDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            YosysDirection::Input => write!(f, "input"),
+            YosysDirection::Output => write!(f, "output"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct YosysPort {
+    pub name: String,
+    pub direction: YosysDirection,
+    pub bits: Vec<YosysBit>,
+}
+
+#[derive(Clone, Debug)]
+pub struct YosysCell {
+    pub name: String,
+    pub kind: String,
+    pub parameters: Vec<(String, usize)>,
+    pub connections: Vec<YosysPort>,
+}
+
+#[derive(Clone, Debug)]
+pub struct YosysNet {
+    pub name: String,
+    pub bits: Vec<YosysBit>,
+    pub init: Option<BigUint>,
+}
+
+#[derive(Clone, Debug)]
+pub struct YosysModule {
+    pub ports: Vec<YosysPort>,
+    pub cells: Vec<YosysCell>,
+    pub netnames: Vec<YosysNet>,
+    next: usize,
+}
+
+impl Default for YosysModule {
+    fn default() -> Self {
+        YosysModule {
+            ports: vec![],
+            cells: vec![],
+            netnames: vec![],
+            next: 2,
+        }
+    }
+}
+
+impl YosysModule {
+    pub fn wire(&mut self, width: usize) -> Vec<YosysBit> {
+        let bits = (self.next..self.next + width).map(YosysBit::Net).collect();
+        self.next += width;
+        bits
+    }
+
+    pub fn instance(&mut self, kind: &str, name: &str, connections: Vec<YosysPort>) {
+        self.cells.push(YosysCell {
+            name: name.to_owned(),
+            kind: kind.to_owned(),
+            parameters: vec![],
+            connections,
+        })
+    }
+
+    // Adds one of the internal cells, with inputs A, B, ... and a fresh output Y
+    fn cell(
+        &mut self,
+        kind: &str,
+        parameters: &[(&str, usize)],
+        inputs: Vec<(&str, Vec<YosysBit>)>,
+        width: usize,
+    ) -> Vec<YosysBit> {
+        let y = self.wire(width);
+        let mut connections = inputs
+            .into_iter()
+            .map(|(name, bits)| YosysPort {
+                name: name.to_owned(),
+                direction: YosysDirection::Input,
+                bits,
+            })
+            .collect::<Vec<_>>();
+        connections.push(YosysPort {
+            name: "Y".into(),
+            direction: YosysDirection::Output,
+            bits: y.clone(),
+        });
+        self.cells.push(YosysCell {
+            name: format!("{}${}", kind, self.cells.len()),
+            kind: kind.to_owned(),
+            parameters: parameters
+                .iter()
+                .map(|(name, value)| (name.to_string(), *value))
+                .collect(),
+            connections,
+        });
+        y
+    }
+
+    // Drives the wires of a signal from a value with a $pos cell, which yosys
+    // removes again in opt
+    pub fn buffer(&mut self, from: &[YosysBit], to: &[YosysBit]) {
+        let width = to.len();
+        self.cells.push(YosysCell {
+            name: format!("$pos${}", self.cells.len()),
+            kind: "$pos".into(),
+            parameters: vec![
+                ("A_SIGNED".into(), 0),
+                ("A_WIDTH".into(), width),
+                ("Y_WIDTH".into(), width),
+            ],
+            connections: vec![
+                YosysPort {
+                    name: "A".into(),
+                    direction: YosysDirection::Input,
+                    bits: from.to_vec(),
+                },
+                YosysPort {
+                    name: "Y".into(),
+                    direction: YosysDirection::Output,
+                    bits: to.to_vec(),
+                },
+            ],
+        })
+    }
+
+    pub fn dff(&mut self, clock: &[YosysBit], d: &[YosysBit], q: &[YosysBit]) {
+        let port = |name: &str, direction, bits: &[YosysBit]| YosysPort {
+            name: name.into(),
+            direction,
+            bits: bits.to_vec(),
+        };
+        self.cells.push(YosysCell {
+            name: format!("$dff${}", self.cells.len()),
+            kind: "$dff".into(),
+            parameters: vec![("CLK_POLARITY".into(), 1), ("WIDTH".into(), q.len())],
+            connections: vec![
+                port("CLK", YosysDirection::Input, clock),
+                port("D", YosysDirection::Input, d),
+                port("Q", YosysDirection::Output, q),
+            ],
+        })
+    }
+
+    pub fn json(&self, top: bool) -> String {
+        let ports = self
+            .ports
+            .iter()
+            .map(|x| {
+                json_entry(
+                    &x.name,
+                    &json_object(&[
+                        format!("\"direction\": \"{}\"", x.direction),
+                        format!("\"bits\": {}", json_bits(&x.bits)),
+                    ]),
+                )
+            })
+            .collect::<Vec<_>>();
+        let cells = self
+            .cells
+            .iter()
+            .map(|x| {
+                let parameters = x
+                    .parameters
+                    .iter()
+                    .map(|(name, value)| json_entry(name, &value.to_string()))
+                    .collect::<Vec<_>>();
+                let directions = x
+                    .connections
+                    .iter()
+                    .map(|c| json_entry(&c.name, &format!("\"{}\"", c.direction)))
+                    .collect::<Vec<_>>();
+                let connections = x
+                    .connections
+                    .iter()
+                    .map(|c| json_entry(&c.name, &json_bits(&c.bits)))
+                    .collect::<Vec<_>>();
+                json_entry(
+                    &x.name,
+                    &json_object(&[
+                        format!("\"hide_name\": {}", hide_name(&x.name)),
+                        json_entry("type", &json_string(&x.kind)),
+                        json_entry("parameters", &json_object(&parameters)),
+                        "\"attributes\": {}".into(),
+                        json_entry("port_directions", &json_object(&directions)),
+                        json_entry("connections", &json_object(&connections)),
+                    ]),
+                )
+            })
+            .collect::<Vec<_>>();
+        let netnames = self
+            .netnames
+            .iter()
+            .map(|x| {
+                let attributes = match &x.init {
+                    // Attributes that look like bit strings are read as constants
+                    Some(init) => {
+                        json_object(&[format!("\"init\": \"{:0w$b}\"", init, w = x.bits.len())])
+                    }
+                    None => "{}".into(),
+                };
+                json_entry(
+                    &x.name,
+                    &json_object(&[
+                        format!("\"hide_name\": {}", hide_name(&x.name)),
+                        format!("\"bits\": {}", json_bits(&x.bits)),
+                        json_entry("attributes", &attributes),
+                    ]),
+                )
+            })
+            .collect::<Vec<_>>();
+        let attributes = if top {
+            json_object(&["\"top\": 1".into()])
+        } else {
+            "{}".into()
+        };
+        json_object(&[
+            json_entry("attributes", &attributes),
+            json_entry("ports", &json_object(&ports)),
+            json_entry("cells", &json_object(&cells)),
+            json_entry("netnames", &json_object(&netnames)),
+        ])
+    }
+}
+
+fn hide_name(name: &str) -> usize {
+    if name.starts_with('$') {
+        1
+    } else {
+        0
+    }
+}
+
+pub(crate) fn json_string(x: &str) -> String {
+    format!("\"{}\"", x.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub(crate) fn json_entry(key: &str, value: &str) -> String {
+    format!("{}: {}", json_string(key), value)
+}
+
+pub(crate) fn json_object(entries: &[String]) -> String {
+    if entries.is_empty() {
+        return "{}".into();
+    }
+    let mut io = CodeWriter::new();
+    io.add("{");
+    io.push();
+    io.add(entries.join(",\n"));
+    io.pop();
+    io.add("}");
+    io.to_string().trim_end().to_owned()
+}
+
+fn json_bits(bits: &[YosysBit]) -> String {
+    format!(
+        "[{}]",
+        bits.iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+// Verilog mixes signed and unsigned operands as unsigned
+fn common_sign(a: &YosysValue, b: &YosysValue) -> bool {
+    match (a.literal, b.literal) {
+        (false, false) => a.signed && b.signed,
+        (false, true) => a.signed,
+        (true, false) => b.signed,
+        (true, true) => false,
+    }
+}
+
+// The kernel is executed symbolically.  Each variable maps to the bits that hold its
+// current value, assignments replace them, and the two sides of a branch are merged
+// with multiplexers.  Functions are inlined and loops unrolled along the way.
+pub struct YosysCodeGenerator<'a> {
+    module: &'a mut YosysModule,
+    env: BTreeMap<String, YosysValue>,
+    loops: Vec<LoopVariable>,
+    registers: Vec<String>,
+}
+
+impl<'a> YosysCodeGenerator<'a> {
+    pub fn new(
+        module: &'a mut YosysModule,
+        signals: &BTreeMap<String, YosysValue>,
+        registers: &[String],
+    ) -> YosysCodeGenerator<'a> {
+        YosysCodeGenerator {
+            module,
+            env: signals.clone(),
+            loops: vec![],
+            registers: registers.to_vec(),
+        }
+    }
+
+    fn fixup(&self, name: &str) -> String {
+        ident_fixup(name, &self.loops, &self.registers)
+    }
+
+    fn signal(&self, name: &str) -> YosysValue {
+        let name = self.fixup(name);
+        if !name.is_empty() && name.chars().all(|c| c.is_ascii_digit()) {
+            let mut value = YosysValue::constant(&name.parse().unwrap(), 32, false);
+            value.literal = true;
+            return value;
+        }
+        match self.env.get(&name) {
+            Some(x) => x.clone(),
+            None => panic!("Signal {} has no known value in the Yosys generator", name),
+        }
+    }
+
+    fn declare(&mut self, code: &VerilogBlock) {
+        for x in verilog_declarations(code) {
+            self.env.insert(
+                x.name.clone(),
+                YosysValue::constant(&BigUint::default(), x.width, x.signed),
+            );
+        }
+    }
+
+    fn natural_width(&self, e: &VerilogExpression) -> usize {
+        match e {
+            VerilogExpression::Signal(s) => self.signal(s).width(),
+            VerilogExpression::Literal(l) => l.bits(),
+            VerilogExpression::Cast(_, bits) => *bits,
+            VerilogExpression::Paren(x)
+            | VerilogExpression::Signed(x)
+            | VerilogExpression::Unsigned(x)
+            | VerilogExpression::IndexReplace(x, _, _) => self.natural_width(x),
+            VerilogExpression::Binary(l, op, r) => match op {
+                VerilogOp::Add
+                | VerilogOp::Sub
+                | VerilogOp::Mul
+                | VerilogOp::BitXor
+                | VerilogOp::BitAnd
+                | VerilogOp::BitOr => self.natural_width(l).max(self.natural_width(r)),
+                VerilogOp::Shl | VerilogOp::Shr => self.natural_width(l),
+                _ => 1,
+            },
+            VerilogExpression::Unary(op, x) => match op {
+                VerilogOpUnary::Not | VerilogOpUnary::Neg => self.natural_width(x),
+                VerilogOpUnary::All | VerilogOpUnary::Any => 1,
+            },
+            VerilogExpression::Index(_, _) => 1,
+            VerilogExpression::Slice(_, width, _) => *width,
+            VerilogExpression::Concat(x) => x.iter().map(|x| self.natural_width(x)).sum(),
+            VerilogExpression::Replicate(x, count) => count * self.natural_width(x),
+            VerilogExpression::Call(f, _) => f.width,
+        }
+    }
+
+    // As in the VHDL back end, the hint is the width of the context an expression is
+    // evaluated in, which is all the low bits of arithmetic can reach.
+    fn expression(&mut self, e: &VerilogExpression, hint: Option<usize>) -> YosysValue {
+        match e {
+            VerilogExpression::Signal(s) => self.signal(s),
+            VerilogExpression::Literal(l) => {
+                let mut value = YosysValue::constant(l.value(), l.bits(), false);
+                value.literal = true;
+                value
+            }
+            VerilogExpression::Cast(x, bits) => {
+                let x = self.expression(x, Some(*bits));
+                YosysValue::new(x.extend(*bits, x.signed), false)
+            }
+            VerilogExpression::Paren(x) => self.expression(x, hint),
+            VerilogExpression::Binary(l, op, r) => self.binary(l, op, r, hint),
+            VerilogExpression::Unary(op, x) => self.unary(op, x, hint),
+            VerilogExpression::Index(sig, ndx) => {
+                let base = self.signal(sig);
+                let offset = self.expression(ndx, None);
+                self.slice(&base, 1, &offset)
+            }
+            VerilogExpression::Slice(sig, width, offset) => {
+                let base = self.signal(sig);
+                let offset = self.expression(offset, None);
+                self.slice(&base, *width, &offset)
+            }
+            VerilogExpression::IndexReplace(sig, ndx, val) => {
+                let base = self.expression(sig, None);
+                let offset = self.expression(ndx, None);
+                let value = self.expression(val, None);
+                let value = YosysValue::new(value.extend(1, false), false);
+                YosysValue::new(self.replace(&base, &offset, &value), base.signed)
+            }
+            VerilogExpression::Signed(x) | VerilogExpression::Unsigned(x) => {
+                let width = self.natural_width(x);
+                let x = self.expression(x, None);
+                YosysValue::new(
+                    x.extend(width, x.signed),
+                    matches!(e, VerilogExpression::Signed(_)),
+                )
+            }
+            VerilogExpression::Replicate(x, count) => self.expression(
+                &VerilogExpression::Concat(vec![(**x).clone(); *count]),
+                hint,
+            ),
+            VerilogExpression::Concat(parts) => {
+                let mut bits = vec![];
+                for x in parts.iter().rev() {
+                    let width = self.natural_width(x);
+                    let x = self.expression(x, None);
+                    bits.extend(x.extend(width, x.signed));
+                }
+                YosysValue::new(bits, false)
+            }
+            VerilogExpression::Call(f, args) => self.call(f, args),
+        }
+    }
+
+    fn slice(&mut self, base: &YosysValue, width: usize, offset: &YosysValue) -> YosysValue {
+        if base.width() == 1 && width == 1 {
+            return YosysValue::new(base.bits.clone(), false);
+        }
+        let bits = match offset.index() {
+            Some(offset) => (offset..offset + width)
+                .map(|i| base.bits.get(i).copied().unwrap_or(YosysBit::Zero))
+                .collect(),
+            None => self.module.cell(
+                "$shiftx",
+                &[
+                    ("A_SIGNED", 0),
+                    ("A_WIDTH", base.width()),
+                    ("B_SIGNED", 0),
+                    ("B_WIDTH", offset.width()),
+                    ("Y_WIDTH", width),
+                ],
+                vec![("A", base.bits.clone()), ("B", offset.bits.clone())],
+                width,
+            ),
+        };
+        YosysValue::new(bits, false)
+    }
+
+    // Replaces width bits of the base starting at the offset.  A variable offset
+    // becomes (base & ~(mask << offset)) | (value << offset).
+    fn replace(
+        &mut self,
+        base: &YosysValue,
+        offset: &YosysValue,
+        value: &YosysValue,
+    ) -> Vec<YosysBit> {
+        let size = base.width();
+        if let Some(offset) = offset.index() {
+            let mut bits = base.bits.clone();
+            for (ndx, bit) in value.bits.iter().enumerate() {
+                if offset + ndx < size {
+                    bits[offset + ndx] = *bit;
+                }
+            }
+            return bits;
+        }
+        let shift = |gen: &mut Self, bits: Vec<YosysBit>| {
+            gen.module.cell(
+                "$shl",
+                &[
+                    ("A_SIGNED", 0),
+                    ("A_WIDTH", size),
+                    ("B_SIGNED", 0),
+                    ("B_WIDTH", offset.width()),
+                    ("Y_WIDTH", size),
+                ],
+                vec![("A", bits), ("B", offset.bits.clone())],
+                size,
+            )
+        };
+        let ones = (0..size)
+            .map(|i| (i < value.width()).into())
+            .collect::<Vec<_>>();
+        let mask = shift(self, ones);
+        let mask = self.unary_cell("$not", mask, size);
+        let kept = self.binary_cell("$and", base.bits.clone(), mask, false, size);
+        let value = shift(self, value.extend(size, false));
+        self.binary_cell("$or", kept, value, false, size)
+    }
+
+    fn unary_cell(&mut self, kind: &str, a: Vec<YosysBit>, width: usize) -> Vec<YosysBit> {
+        self.module.cell(
+            kind,
+            &[("A_SIGNED", 0), ("A_WIDTH", a.len()), ("Y_WIDTH", width)],
+            vec![("A", a)],
+            width,
+        )
+    }
+
+    fn binary_cell(
+        &mut self,
+        kind: &str,
+        a: Vec<YosysBit>,
+        b: Vec<YosysBit>,
+        signed: bool,
+        width: usize,
+    ) -> Vec<YosysBit> {
+        let signed = signed as usize;
+        self.module.cell(
+            kind,
+            &[
+                ("A_SIGNED", signed),
+                ("A_WIDTH", a.len()),
+                ("B_SIGNED", signed),
+                ("B_WIDTH", b.len()),
+                ("Y_WIDTH", width),
+            ],
+            vec![("A", a), ("B", b)],
+            width,
+        )
+    }
+
+    fn binary(
+        &mut self,
+        l: &VerilogExpression,
+        op: &VerilogOp,
+        r: &VerilogExpression,
+        hint: Option<usize>,
+    ) -> YosysValue {
+        match op {
+            VerilogOp::Add
+            | VerilogOp::Sub
+            | VerilogOp::Mul
+            | VerilogOp::BitXor
+            | VerilogOp::BitAnd
+            | VerilogOp::BitOr => {
+                let width =
+                    hint.unwrap_or_else(|| self.natural_width(l).max(self.natural_width(r)));
+                let lx = self.expression(l, Some(width));
+                let rx = self.expression(r, Some(width));
+                let signed = common_sign(&lx, &rx);
+                let a = YosysValue::new(lx.extend(width, signed), signed);
+                let b = YosysValue::new(rx.extend(width, signed), signed);
+                if let (Some(x), Some(y)) = (a.value(), b.value()) {
+                    let modulus = BigUint::from(1_u32) << width;
+                    let value = match op {
+                        VerilogOp::Add => x + y,
+                        VerilogOp::Sub => x + &modulus - y,
+                        VerilogOp::Mul => x * y,
+                        VerilogOp::BitXor => x ^ y,
+                        VerilogOp::BitAnd => x & y,
+                        _ => x | y,
+                    };
+                    return YosysValue::constant(&(value % modulus), width, signed);
+                }
+                let kind = match op {
+                    VerilogOp::Add => "$add",
+                    VerilogOp::Sub => "$sub",
+                    VerilogOp::Mul => "$mul",
+                    VerilogOp::BitXor => "$xor",
+                    VerilogOp::BitAnd => "$and",
+                    _ => "$or",
+                };
+                YosysValue::new(
+                    self.binary_cell(kind, a.bits, b.bits, signed, width),
+                    signed,
+                )
+            }
+            VerilogOp::Shl | VerilogOp::Shr => {
+                let width = self.natural_width(l).max(hint.unwrap_or(0));
+                let lx = self.expression(l, Some(width));
+                let signed = lx.signed && !lx.literal;
+                let a = YosysValue::new(lx.extend(width, signed), signed);
+                let amount = self.expression(r, None);
+                let fill = if signed && matches!(op, VerilogOp::Shr) {
+                    a.bits[width - 1]
+                } else {
+                    YosysBit::Zero
+                };
+                if let Some(amount) = amount.index() {
+                    let bits = (0..width)
+                        .map(|i| match op {
+                            VerilogOp::Shl if i >= amount => a.bits[i - amount],
+                            VerilogOp::Shr if i + amount < width => a.bits[i + amount],
+                            _ => fill,
+                        })
+                        .collect();
+                    return YosysValue::new(bits, signed);
+                }
+                let kind = match op {
+                    VerilogOp::Shl => "$shl",
+                    _ if signed => "$sshr",
+                    _ => "$shr",
+                };
+                let bits = self.module.cell(
+                    kind,
+                    &[
+                        ("A_SIGNED", signed as usize),
+                        ("A_WIDTH", width),
+                        ("B_SIGNED", 0),
+                        ("B_WIDTH", amount.width()),
+                        ("Y_WIDTH", width),
+                    ],
+                    vec![("A", a.bits), ("B", amount.bits)],
+                    width,
+                );
+                YosysValue::new(bits, signed)
+            }
+            VerilogOp::LogicalAnd | VerilogOp::LogicalOr => {
+                let a = self.condition(l);
+                let b = self.condition(r);
+                let and = matches!(op, VerilogOp::LogicalAnd);
+                let bit = match (a, b) {
+                    (YosysBit::Zero, x) | (x, YosysBit::Zero) if !and => x,
+                    (YosysBit::One, x) | (x, YosysBit::One) if and => x,
+                    (YosysBit::Zero, _) | (_, YosysBit::Zero) => YosysBit::Zero,
+                    (YosysBit::One, _) | (_, YosysBit::One) => YosysBit::One,
+                    (a, b) => {
+                        let kind = if and { "$logic_and" } else { "$logic_or" };
+                        self.binary_cell(kind, vec![a], vec![b], false, 1)[0]
+                    }
+                };
+                YosysValue::new(vec![bit], false)
+            }
+            VerilogOp::Eq
+            | VerilogOp::Ne
+            | VerilogOp::Lt
+            | VerilogOp::Le
+            | VerilogOp::Gt
+            | VerilogOp::Ge => {
+                let width = self.natural_width(l).max(self.natural_width(r));
+                let lx = self.expression(l, Some(width));
+                let rx = self.expression(r, Some(width));
+                let signed = common_sign(&lx, &rx);
+                let a = YosysValue::new(lx.extend(width, signed), signed);
+                let b = YosysValue::new(rx.extend(width, signed), signed);
+                if let (Some(x), Some(y)) = (a.value(), b.value()) {
+                    if !signed {
+                        let bit = match op {
+                            VerilogOp::Eq => x == y,
+                            VerilogOp::Ne => x != y,
+                            VerilogOp::Lt => x < y,
+                            VerilogOp::Le => x <= y,
+                            VerilogOp::Gt => x > y,
+                            _ => x >= y,
+                        };
+                        return YosysValue::new(vec![bit.into()], false);
+                    }
+                }
+                let kind = match op {
+                    VerilogOp::Eq => "$eq",
+                    VerilogOp::Ne => "$ne",
+                    VerilogOp::Lt => "$lt",
+                    VerilogOp::Le => "$le",
+                    VerilogOp::Gt => "$gt",
+                    _ => "$ge",
+                };
+                YosysValue::new(self.binary_cell(kind, a.bits, b.bits, signed, 1), false)
+            }
+        }
+    }
+
+    fn unary(
+        &mut self,
+        op: &VerilogOpUnary,
+        x: &VerilogExpression,
+        hint: Option<usize>,
+    ) -> YosysValue {
+        match op {
+            VerilogOpUnary::Not | VerilogOpUnary::Neg => {
+                let width = hint.unwrap_or_else(|| self.natural_width(x));
+                let ex = self.expression(x, Some(width));
+                let signed = ex.signed && !ex.literal;
+                let a = YosysValue::new(ex.extend(width, signed), signed);
+                if let Some(value) = a.value() {
+                    let modulus = BigUint::from(1_u32) << width;
+                    let ones = &modulus - 1_u32;
+                    let value = match op {
+                        VerilogOpUnary::Not => value ^ ones,
+                        _ => (&modulus - value) % &modulus,
+                    };
+                    return YosysValue::constant(&value, width, signed);
+                }
+                let kind = match op {
+                    VerilogOpUnary::Not => "$not",
+                    _ => "$neg",
+                };
+                let bits = self.module.cell(
+                    kind,
+                    &[
+                        ("A_SIGNED", signed as usize),
+                        ("A_WIDTH", width),
+                        ("Y_WIDTH", width),
+                    ],
+                    vec![("A", a.bits)],
+                    width,
+                );
+                YosysValue::new(bits, signed)
+            }
+            VerilogOpUnary::All | VerilogOpUnary::Any => {
+                let ex = self.expression(x, None);
+                if ex.width() == 1 {
+                    return YosysValue::new(ex.bits, false);
+                }
+                let kind = match op {
+                    VerilogOpUnary::All => "$reduce_and",
+                    _ => "$reduce_or",
+                };
+                YosysValue::new(self.unary_cell(kind, ex.bits, 1), false)
+            }
+        }
+    }
+
+    fn condition(&mut self, e: &VerilogExpression) -> YosysBit {
+        let x = self.expression(e, None);
+        if let Some(value) = x.value() {
+            return (value != BigUint::default()).into();
+        }
+        if x.width() == 1 {
+            return x.bits[0];
+        }
+        self.unary_cell("$reduce_bool", x.bits, 1)[0]
+    }
+
+    fn call(&mut self, f: &VerilogFunction, args: &[VerilogExpression]) -> YosysValue {
+        let mut env = BTreeMap::new();
+        for (decl, arg) in f.args.iter().zip(args.iter()) {
+            let x = self.expression(arg, Some(decl.width));
+            env.insert(
+                decl.name.clone(),
+                YosysValue::new(x.extend(decl.width, x.signed), decl.signed),
+            );
+        }
+        env.insert(
+            f.name.clone(),
+            YosysValue::constant(&BigUint::default(), f.width, f.signed),
+        );
+        let env = std::mem::replace(&mut self.env, env);
+        let loops = std::mem::take(&mut self.loops);
+        self.declare(&f.body);
+        self.block(&with_local_defaults(&f.body));
+        let result = self.env[&f.name].clone();
+        self.env = env;
+        self.loops = loops;
+        result
+    }
+
+    fn block(&mut self, b: &VerilogBlock) {
+        for s in b {
+            self.statement(s);
+        }
+    }
+
+    fn assign(&mut self, name: &str, bits: Vec<YosysBit>) {
+        let signed = match self.env.get(name) {
+            Some(x) => x.signed,
+            None => panic!("Signal {} has no known value in the Yosys generator", name),
+        };
+        self.env
+            .insert(name.to_owned(), YosysValue::new(bits, signed));
+    }
+
+    fn statement(&mut self, s: &VerilogStatement) {
+        match s {
+            VerilogStatement::Assignment(target, value) => {
+                let name = match target {
+                    VerilogExpression::Signal(name) => self.fixup(name),
+                    _ => panic!("Only signals can be assigned in a netlist: {:?}", target),
+                };
+                let width = self.signal(&name).width();
+                let value = self.expression(value, Some(width));
+                self.assign(&name, value.extend(width, value.signed));
+            }
+            VerilogStatement::SliceAssignment {
+                base,
+                width,
+                offset,
+                replacement,
+            } => {
+                let name = self.fixup(base);
+                let target = self.signal(&name);
+                let offset = self.expression(offset, None);
+                let value = self.expression(replacement, Some(*width));
+                let value = YosysValue::new(value.extend(*width, value.signed), false);
+                let bits = self.replace(&target, &offset, &value);
+                self.assign(&name, bits);
+            }
+            VerilogStatement::If(c) => self.conditional(c),
+            VerilogStatement::Match(m) => self.match_statement(m),
+            VerilogStatement::Loop(l) => {
+                for i in l.from.as_usize()..l.to.as_usize() {
+                    self.loops.push(LoopVariable {
+                        variable: l.index.clone(),
+                        value: i,
+                    });
+                    self.block(&l.block);
+                    self.loops.pop();
+                }
+            }
+            // Assertions are for simulation and formal tools, and have no netlist
+            VerilogStatement::Comment(_)
+            | VerilogStatement::Declaration(_)
+            | VerilogStatement::Assert(..)
+            | VerilogStatement::Assume(_) => {}
+        }
+    }
+
+    fn otherwise(&mut self, o: &VerilogBlockOrConditional) {
+        match o {
+            VerilogBlockOrConditional::Block(b) => self.block(b),
+            VerilogBlockOrConditional::Conditional(s) => self.statement(s),
+            VerilogBlockOrConditional::None => {}
+        }
+    }
+
+    fn conditional(&mut self, c: &VerilogConditional) {
+        match self.condition(&c.test) {
+            YosysBit::One => self.block(&c.then),
+            YosysBit::Zero => self.otherwise(&c.otherwise),
+            select => {
+                let before = self.env.clone();
+                self.block(&c.then);
+                let then = std::mem::replace(&mut self.env, before);
+                self.otherwise(&c.otherwise);
+                self.merge(select, then);
+            }
+        }
+    }
+
+    // Takes each variable from then where select is set, and from the current
+    // environment otherwise
+    fn merge(&mut self, select: YosysBit, then: BTreeMap<String, YosysValue>) {
+        for (name, value) in then {
+            let otherwise = &self.env[&name];
+            if otherwise.bits == value.bits {
+                continue;
+            }
+            let bits = match select {
+                YosysBit::One => value.bits,
+                YosysBit::Zero => continue,
+                _ => {
+                    let width = value.width();
+                    self.module.cell(
+                        "$mux",
+                        &[("WIDTH", width)],
+                        vec![
+                            ("A", otherwise.bits.clone()),
+                            ("B", value.bits),
+                            ("S", vec![select]),
+                        ],
+                        width,
+                    )
+                }
+            };
+            self.assign(&name, bits);
+        }
+    }
+
+    // The first matching case wins, so the cases are folded in from the last one
+    fn match_statement(&mut self, m: &VerilogMatch) {
+        let before = self.env.clone();
+        let mut cases = vec![];
+        let mut default = None;
+        for case in &m.cases {
+            let condition = self.fixup(&case.condition);
+            if condition == "default" {
+                default = Some(&case.block);
+                continue;
+            }
+            let value = match parse_int(&condition) {
+                Some(x) => VerilogExpression::Literal(VerilogLiteral::from(x as u64)),
+                None => VerilogExpression::Signal(case.condition.clone()),
+            };
+            let test =
+                VerilogExpression::Binary(Box::new(m.test.clone()), VerilogOp::Eq, Box::new(value));
+            let select = self.condition(&test);
+            self.env = before.clone();
+            self.block(&case.block);
+            cases.push((select, std::mem::replace(&mut self.env, before.clone())));
+        }
+        if let Some(block) = default {
+            self.block(block);
+        }
+        for (select, then) in cases.into_iter().rev() {
+            self.merge(select, then);
+        }
+    }
+
+    // Every signal the kernel changed is driven from its final value
+    fn finish(self, signals: &BTreeMap<String, YosysValue>) {
+        for (name, value) in &self.env {
+            if let Some(signal) = signals.get(name) {
+                if signal.bits != value.bits {
+                    self.module.buffer(&value.bits, &signal.bits);
+                }
+            }
+        }
+    }
+}
+
+pub fn yosys_combinatorial(
+    module: &mut YosysModule,
+    code: &VerilogBlock,
+    signals: &BTreeMap<String, YosysValue>,
+    registers: &[String],
+) {
+    let mut gen = YosysCodeGenerator::new(module, signals, registers);
+    gen.declare(code);
+    gen.block(&with_local_defaults(code));
+    gen.finish(signals);
+}
+
+// The register D inputs (<name>_next) are computed as in the Verilog output and
+// loaded by a $dff per register.
+pub fn yosys_sequential(
+    module: &mut YosysModule,
+    code: &VerilogBlock,
+    clock: &str,
+    signals: &BTreeMap<String, YosysValue>,
+    registers: &[String],
+) {
+    yosys_combinatorial(
+        module,
+        &with_register_defaults(code, registers),
+        signals,
+        registers,
+    );
+    let clock = &signals[&ident_fixup(clock, &[], &[])];
+    for x in registers {
+        let d = &signals[&format!("{}_next", x)];
+        module.dff(&clock.bits, &d.bits, &signals[x].bits);
+    }
+}
@@ -24,9 +24,10 @@ fn connect_statement(statement: &syn::Stmt) -> Result<TS> {
     match statement {
         syn::Stmt::Expr(e) => connect_inner_statement(e),
         syn::Stmt::Semi(e, _) => connect_inner_statement(e),
+        syn::Stmt::Local(_) => Ok(TS::new()),
         _ => Err(syn::Error::new(
             statement.span(),
-            "Local definitions and items are not allowed in HDL kernels",
+            "Item definitions are not allowed in HDL kernels",
         )),
     }
 }
@@ -1,38 +1,342 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
 use std::ops::Index;
 
 use quote::format_ident;
 use quote::quote;
+use syn::parse::{Parse, ParseStream};
 use syn::spanned::Spanned;
-use syn::{BinOp, Expr, Pat, Result, Stmt, UnOp};
+use syn::visit::Visit;
+use syn::visit_mut::VisitMut;
+use syn::{BinOp, Expr, Pat, Result, Stmt, Token, UnOp};
 
 use crate::common;
 use crate::common::TS;
 
-pub(crate) fn hdl_gen_process(item: syn::ItemFn) -> Result<TS> {
+pub(crate) struct HdlGenArgs {
+    clock: Option<syn::Expr>,
+    functions: Vec<syn::Ident>,
+}
+
+// The #[hdl_function]s called by the kernel, as in functions(parity, highest)
+fn parse_functions(input: ParseStream) -> Result<Vec<syn::Ident>> {
+    let content;
+    syn::parenthesized!(content in input);
+    let names = content.parse_terminated::<syn::Ident, Token![,]>(syn::Ident::parse)?;
+    Ok(names.into_iter().collect())
+}
+
+impl Parse for HdlGenArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut args = HdlGenArgs {
+            clock: None,
+            functions: vec![],
+        };
+        while !input.is_empty() {
+            let key: syn::Ident = input.parse()?;
+            if key == "clock" {
+                input.parse::<Token![=]>()?;
+                args.clock = Some(input.parse()?);
+            } else if key == "functions" {
+                args.functions = parse_functions(input)?;
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "Unknown hdl_gen argument; use #[hdl_gen(clock = self.<clock signal>)] or #[hdl_gen(functions(<hdl functions>))]",
+                ));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+pub(crate) struct HdlFunctionArgs {
+    functions: Vec<syn::Ident>,
+}
+
+impl Parse for HdlFunctionArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.is_empty() {
+            return Ok(HdlFunctionArgs { functions: vec![] });
+        }
+        let key: syn::Ident = input.parse()?;
+        if key != "functions" {
+            return Err(syn::Error::new(
+                key.span(),
+                "Unknown hdl_function argument; use #[hdl_function(functions(<hdl functions>))]",
+            ));
+        }
+        Ok(HdlFunctionArgs {
+            functions: parse_functions(input)?,
+        })
+    }
+}
+
+// The Verilog name of each let binding in scope, innermost last.  A binding
+// that reuses a name gets a fresh one (t, t_1, t_2, ...), so shadowing in Rust
+// does not merge two locals into one reg.
+#[derive(Default)]
+struct Locals {
+    scopes: Vec<Vec<(String, String)>>,
+    used: BTreeSet<String>,
+}
+
+thread_local! {
+    // The calls that are routed to #[hdl_function]s while one item is expanded
+    static HDL_FUNCTIONS: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+    static HDL_LOCALS: RefCell<Locals> = const {
+        RefCell::new(Locals {
+            scopes: vec![],
+            used: BTreeSet::new(),
+        })
+    };
+}
+
+fn with_hdl_functions<T>(functions: &[syn::Ident], f: impl FnOnce() -> T) -> T {
+    let names = functions.iter().map(|x| x.to_string()).collect();
+    let saved = HDL_FUNCTIONS.with(|x| x.replace(names));
+    let locals = Locals {
+        scopes: vec![vec![]],
+        used: BTreeSet::new(),
+    };
+    let saved_locals = HDL_LOCALS.with(|x| x.replace(locals));
+    let ret = f();
+    HDL_FUNCTIONS.with(|x| x.replace(saved));
+    HDL_LOCALS.with(|x| x.replace(saved_locals));
+    ret
+}
+
+fn reserve_name(name: &str) {
+    HDL_LOCALS.with(|x| x.borrow_mut().used.insert(name.to_string()));
+}
+
+fn bind_local(name: &str) -> String {
+    HDL_LOCALS.with(|x| {
+        let mut locals = x.borrow_mut();
+        let mut unique = name.to_string();
+        let mut count = 0;
+        while locals.used.contains(&unique) {
+            count += 1;
+            unique = format!("{}_{}", name, count);
+        }
+        locals.used.insert(unique.clone());
+        if let Some(scope) = locals.scopes.last_mut() {
+            scope.push((name.to_string(), unique.clone()));
+        }
+        unique
+    })
+}
+
+fn local_name(name: &str) -> Option<String> {
+    HDL_LOCALS.with(|x| {
+        x.borrow()
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|x| x.0 == name)
+            .map(|x| x.1.clone())
+    })
+}
+
+fn path_local_name(path: &syn::ExprPath) -> Option<String> {
+    path.path
+        .get_ident()
+        .and_then(|ident| local_name(&ident.to_string()))
+}
+
+pub(crate) fn hdl_gen_process(item: syn::ItemFn, args: &HdlGenArgs) -> Result<TS> {
+    with_hdl_functions(&args.functions, || hdl_gen_kernel(item, args))
+}
+
+fn hdl_gen_kernel(item: syn::ItemFn, args: &HdlGenArgs) -> Result<TS> {
     let signature = &item.sig;
     if signature.inputs.len() != 1 {
         return Err(syn::Error::new(
-            signature.span(),
-            "HDL functions must contain a single argument (&mut self)",
+            signature.inputs.span(),
+            "HDL update functions must take a single argument (&mut self)",
         ));
     }
     let body = hdl_block(&item.block)?;
-    Ok(quote! {
-    fn hdl(&self) -> rust_hdl_core::ast::Verilog {
-       rust_hdl_core::ast::Verilog::Combinatorial(#body)
+    match &args.clock {
+        None => Ok(quote! {
+        fn hdl(&self) -> rust_hdl_core::ast::Verilog {
+           rust_hdl_core::ast::Verilog::Combinatorial(#body)
+        }
+        }),
+        Some(clock) => {
+            let clock = hdl_read_signal_name(clock)?;
+            Ok(quote! {
+            fn hdl(&self) -> rust_hdl_core::ast::Verilog {
+               rust_hdl_core::ast::Verilog::Sequential {
+                   clock: #clock.to_string(),
+                   code: #body,
+               }
+            }
+            })
+        }
+    }
+}
+
+// A clocked kernel first lets everything it assigns see the clock edge, which is
+// where registers load their D inputs.  Each hdl_assert! is told which block it is
+// in, so that a failure can be reported against it.
+pub(crate) fn hdl_gen_update(item: &syn::ItemFn, args: &HdlGenArgs) -> TS {
+    let mut item = item.clone();
+    AssertBlocks.visit_block_mut(&mut item.block);
+    if let Some(clock) = &args.clock {
+        let mut finder = TargetFinder::default();
+        finder.visit_block(&item.block);
+        let edges: Vec<syn::Stmt> = finder
+            .targets
+            .iter()
+            .map(|x| {
+                syn::parse_quote!(rust_hdl_core::reg::Clocked::clock_edge(&mut self.#x, #clock.pos_edge());)
+            })
+            .collect();
+        item.block.stmts.splice(0..0, edges);
+    }
+    quote!(#item)
+}
+
+struct AssertBlocks;
+
+impl VisitMut for AssertBlocks {
+    fn visit_macro_mut(&mut self, mac: &mut syn::Macro) {
+        if mac.path.segments.last().map(|x| x.ident == "hdl_assert") == Some(true) {
+            let tokens = &mac.tokens;
+            mac.tokens = quote!(self; #tokens);
+        }
+    }
+}
+
+#[derive(Default)]
+struct TargetFinder {
+    targets: Vec<syn::Member>,
+}
+
+impl<'ast> Visit<'ast> for TargetFinder {
+    fn visit_expr_field(&mut self, field: &'ast syn::ExprField) {
+        if let (syn::Member::Named(next), Expr::Field(base)) = (&field.member, field.base.as_ref())
+        {
+            if next == "next" && !self.targets.contains(&base.member) {
+                if let Expr::Path(path) = base.base.as_ref() {
+                    if path.path.is_ident("self") {
+                        self.targets.push(base.member.clone());
+                    }
+                }
+            }
+        }
+        syn::visit::visit_expr_field(self, field);
+    }
+}
+
+// The function is kept for simulation, and a companion hdl_function_<name> builds the
+// Verilog function that hdl_gen code calls in its place
+pub(crate) fn hdl_function_process(item: syn::ItemFn, args: &HdlFunctionArgs) -> Result<TS> {
+    with_hdl_functions(&args.functions, || hdl_function_companion(item))
+}
+
+fn hdl_function_companion(item: syn::ItemFn) -> Result<TS> {
+    let signature = &item.sig;
+    if !signature.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &signature.generics,
+            "HDL functions cannot be generic; use concrete types (e.g., Bits<8>)",
+        ));
     }
+    let ret = match &signature.output {
+        syn::ReturnType::Type(_, ty) => ty.as_ref(),
+        syn::ReturnType::Default => {
+            return Err(syn::Error::new(
+                signature.span(),
+                "HDL functions must return a Synth value",
+            ))
+        }
+    };
+    let mut arg_names = vec![];
+    let mut arg_types = vec![];
+    for arg in &signature.inputs {
+        match arg {
+            syn::FnArg::Typed(x) => match x.pat.as_ref() {
+                Pat::Ident(y) if y.subpat.is_none() => {
+                    arg_names.push(&y.ident);
+                    arg_types.push(x.ty.as_ref());
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        x.pat.span(),
+                        "HDL function arguments must be simple identifiers",
+                    ))
+                }
+            },
+            syn::FnArg::Receiver(x) => {
+                return Err(syn::Error::new(x.span(), "HDL functions cannot take self"))
+            }
+        }
+    }
+    let (tail, stmts) = match item.block.stmts.split_last() {
+        Some((Stmt::Expr(tail), stmts)) => (tail, stmts),
+        _ => {
+            return Err(syn::Error::new(
+                item.block.span(),
+                "HDL functions must end with an expression that gives the result",
+            ))
+        }
+    };
+    reserve_name(&signature.ident.to_string());
+    arg_names.iter().for_each(|x| reserve_name(&x.to_string()));
+    let mut stmt = vec![];
+    for statement in stmts {
+        stmt.push(hdl_statement(statement)?);
+    }
+    let result = hdl_compute(tail)?;
+    let ident = &signature.ident;
+    let name = common::fixup_ident(ident.to_string());
+    let arg_strings = arg_names
+        .iter()
+        .map(|x| common::fixup_ident(x.to_string()))
+        .collect::<Vec<_>>();
+    let vis = &item.vis;
+    let companion = format_ident!("hdl_function_{}", ident);
+    Ok(quote! {
+        #vis fn #companion() -> rust_hdl_core::ast::VerilogFunction {
+            #(let #arg_names: #arg_types = Default::default();)*
+            rust_hdl_core::ast::VerilogFunction {
+                name: #name.to_string(),
+                width: <#ret as rust_hdl_core::synth::Synth>::BITS,
+                signed: <#ret as rust_hdl_core::synth::Synth>::SIGNED,
+                args: vec![#(rust_hdl_core::ast::VerilogDeclaration {
+                    name: #arg_strings.to_string(),
+                    width: <#arg_types as rust_hdl_core::synth::Synth>::BITS,
+                    signed: <#arg_types as rust_hdl_core::synth::Synth>::SIGNED,
+                }),*],
+                body: {
+                    let mut ret = vec![];
+                    #(#stmt)*
+                    ret.push(rust_hdl_core::ast::VerilogStatement::Assignment(
+                        rust_hdl_core::ast::VerilogExpression::Signal(#name.to_string()), #result));
+                    ret
+                },
+            }
+        }
     })
 }
 
 fn hdl_block(block: &syn::Block) -> Result<TS> {
+    HDL_LOCALS.with(|x| x.borrow_mut().scopes.push(vec![]));
     let mut stmt = vec![];
     for statement in &block.stmts {
         stmt.push(hdl_statement(statement)?);
     }
+    HDL_LOCALS.with(|x| x.borrow_mut().scopes.pop());
     Ok(quote! {
     {
         let mut ret = vec![];
-        #(ret.push(#stmt));*;
+        #(#stmt)*
         ret
     }
     })
@@ -40,39 +344,192 @@ fn hdl_block(block: &syn::Block) -> Result<TS> {
 
 fn hdl_statement(statement: &syn::Stmt) -> Result<TS> {
     match statement {
-        Stmt::Expr(e) => hdl_inner_statement(e),
-        Stmt::Semi(e, _) => hdl_inner_statement(e),
-        _ => Err(syn::Error::new(
-            statement.span(),
-            "Local definitions and items are not allowed in HDL kernels",
+        Stmt::Expr(e) | Stmt::Semi(e, _) => {
+            let stmt = hdl_inner_statement(e)?;
+            Ok(quote!(ret.push(#stmt);))
+        }
+        Stmt::Local(local) => hdl_local(local),
+        Stmt::Item(item) => Err(syn::Error::new(
+            item.span(),
+            "Item definitions are not allowed in HDL kernels; move them outside the function",
         )),
     }
 }
 
-fn hdl_for_loop(expr: &syn::ExprForLoop) -> Result<TS> {
-    if let Pat::Ident(loop_index) = &expr.pat {
-        if let Expr::Range(range) = &expr.expr.as_ref() {
-            if let Some(from) = range.from.as_ref() {
-                if let Some(to) = range.to.as_ref() {
-                    let block = hdl_block(&expr.body)?;
-                    let loop_index = quote!(#loop_index).to_string();
-                    return Ok(quote!(
-                    rust_hdl_core::ast::VerilogStatement::Loop(
-                        rust_hdl_core::ast::VerilogLoop {
-                            index: #loop_index.into(),
-                            from: #from.into(),
-                            to: #to.into(),
-                            block: #block,
-                        }
-                    )));
-                }
+// The binding is also evaluated in the generated code, so that the width of the
+// Verilog reg can be taken from the Rust type
+fn hdl_local(local: &syn::Local) -> Result<TS> {
+    let (ident, ty) = match &local.pat {
+        Pat::Ident(x) if x.subpat.is_none() => (&x.ident, None),
+        Pat::Type(x) => match x.pat.as_ref() {
+            Pat::Ident(y) if y.subpat.is_none() => (&y.ident, Some(x.ty.as_ref())),
+            _ => {
+                return Err(syn::Error::new(
+                    local.pat.span(),
+                    "Only simple let bindings (e.g. let x = ...) are supported in HDL kernels",
+                ))
             }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                local.pat.span(),
+                "Only simple let bindings (e.g. let x = ...) are supported in HDL kernels",
+            ))
+        }
+    };
+    let init = match &local.init {
+        Some((_, init)) => init.as_ref(),
+        None => {
+            return Err(syn::Error::new(
+                local.span(),
+                "Let bindings in HDL kernels must be initialized",
+            ))
+        }
+    };
+    hdl_ident(ident.to_string(), ident.span())?;
+    // The value can read the binding that this one shadows
+    let value = hdl_compute(init)?;
+    let name = bind_local(&ident.to_string());
+    let binding = match ty {
+        Some(ty) => quote!(let #ident: #ty = #init;),
+        None => quote!(let #ident = #init;),
+    };
+    Ok(quote! {
+        #binding
+        ret.push(rust_hdl_core::ast::VerilogStatement::Declaration(
+            rust_hdl_core::ast::VerilogDeclaration {
+                name: #name.to_string(),
+                width: rust_hdl_core::synth::SynthWidth::synth_width(&#ident),
+                signed: rust_hdl_core::synth::SynthWidth::synth_signed(&#ident),
+            }));
+        ret.push(rust_hdl_core::ast::VerilogStatement::Assignment(
+            rust_hdl_core::ast::VerilogExpression::Signal(#name.to_string()), #value));
+    })
+}
+
+#[derive(Default)]
+struct LocalFinder {
+    local: Option<proc_macro2::Span>,
+}
+
+impl<'ast> Visit<'ast> for LocalFinder {
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        self.local.get_or_insert(local.span());
+    }
+}
+
+#[derive(Default)]
+struct SelfFinder {
+    found: Option<proc_macro2::Span>,
+}
+
+impl<'ast> Visit<'ast> for SelfFinder {
+    fn visit_ident(&mut self, ident: &'ast proc_macro2::Ident) {
+        if ident == "self" {
+            self.found.get_or_insert(ident.span());
         }
     }
-    Err(syn::Error::new(
-        expr.span(),
-        "For loops must be simple (e.g. for <ident> in <const>..<const>",
-    ))
+}
+
+fn hdl_for_loop(expr: &syn::ExprForLoop) -> Result<TS> {
+    let loop_index = match &expr.pat {
+        Pat::Ident(x) if x.subpat.is_none() => &x.ident,
+        _ => {
+            return Err(syn::Error::new(
+                expr.pat.span(),
+                "HDL for loops need a plain loop index (e.g., for i in 0..8)",
+            ))
+        }
+    };
+    let range = match expr.expr.as_ref() {
+        Expr::Range(range) => range,
+        _ => {
+            return Err(syn::Error::new(
+                expr.expr.span(),
+                "HDL for loops must iterate over a constant range (e.g., for i in 0..8)",
+            ))
+        }
+    };
+    if let syn::RangeLimits::Closed(limits) = &range.limits {
+        return Err(syn::Error::new_spanned(
+            limits,
+            "Inclusive ranges are not supported in HDL for loops; use an exclusive range (e.g., 0..8 instead of 0..=7)",
+        ));
+    }
+    let (from, to) = match (&range.from, &range.to) {
+        (Some(from), Some(to)) => (from, to),
+        _ => {
+            return Err(syn::Error::new(
+                range.span(),
+                "HDL for loops need both ends of the range (e.g., for i in 0..8)",
+            ))
+        }
+    };
+    let mut finder = LocalFinder::default();
+    finder.visit_block(&expr.body);
+    if let Some(span) = finder.local {
+        return Err(syn::Error::new(
+            span,
+            "Let bindings are not supported inside HDL for loops; declare the local before the loop",
+        ));
+    }
+    let block = hdl_block(&expr.body)?;
+    let loop_index = loop_index.to_string();
+    Ok(quote!(
+    rust_hdl_core::ast::VerilogStatement::Loop(
+        rust_hdl_core::ast::VerilogLoop {
+            index: #loop_index.into(),
+            from: #from.into(),
+            to: #to.into(),
+            block: #block,
+        }
+    )))
+}
+
+// Rejected constructs name what was found and what to write instead
+fn hdl_unsupported(expr: &syn::Expr) -> syn::Error {
+    let message = match expr {
+        Expr::While(_) | Expr::Loop(_) => {
+            "Only for loops over a constant range translate to HDL (e.g., for i in 0..8)"
+        }
+        Expr::Return(_) | Expr::Break(_) | Expr::Continue(_) => {
+            "Early exits (return, break, continue) are not supported in HDL; use if/else instead"
+        }
+        Expr::AssignOp(_) => {
+            "Compound assignments are not supported in HDL; write the operation out (e.g., x = x + y)"
+        }
+        Expr::Index(_) => {
+            "Indexing with [] is not supported in HDL; use .get_bit(i) or .get_bits::<N>(offset)"
+        }
+        Expr::If(_) | Expr::Match(_) => {
+            "if and match cannot be used as values in HDL; assign to a signal or local in each branch instead"
+        }
+        Expr::Reference(_) => "References are not supported in HDL; remove the &",
+        Expr::Block(_) | Expr::Unsafe(_) => {
+            "Nested blocks are not supported in HDL; use the statements directly"
+        }
+        Expr::Closure(_) => {
+            "Closures are not supported in HDL; use an #[hdl_function] for reusable logic"
+        }
+        Expr::Tuple(_) | Expr::Array(_) | Expr::Repeat(_) => {
+            "Tuples and arrays are not supported as HDL values; use Bits, concat or a LogicStruct"
+        }
+        Expr::Struct(_) => {
+            "Struct literals are not supported in HDL; use a LogicStruct signal and its set_value_<field> methods"
+        }
+        Expr::Range(_) => "Ranges can only appear in HDL for loops",
+        Expr::Try(_) | Expr::Await(_) | Expr::Async(_) | Expr::TryBlock(_) | Expr::Yield(_) => {
+            "Error propagation and async code do not translate to HDL"
+        }
+        _ => "This expression does not translate to HDL",
+    };
+    match expr {
+        Expr::AssignOp(x) => syn::Error::new_spanned(x.op, message),
+        Expr::Index(_) | Expr::Reference(_) | Expr::Range(_) => {
+            syn::Error::new_spanned(expr, message)
+        }
+        _ => syn::Error::new(expr.span(), message),
+    }
 }
 
 fn hdl_inner_statement(expr: &syn::Expr) -> Result<TS> {
@@ -83,18 +540,22 @@ fn hdl_inner_statement(expr: &syn::Expr) -> Result<TS> {
         Expr::MethodCall(x) => hdl_method_set(x),
         Expr::Macro(x) => hdl_macro(x),
         Expr::ForLoop(x) => hdl_for_loop(x),
-        _ => Err(syn::Error::new(
-            expr.span(),
-            format!("Expression does not translate {:?}", expr),
-        )),
+        Expr::Group(x) => hdl_inner_statement(&x.expr),
+        Expr::Path(_) | Expr::Field(_) | Expr::Lit(_) | Expr::Binary(_) | Expr::Call(_) => {
+            Err(syn::Error::new_spanned(
+                expr,
+                "This value is computed but never used; assign it to a signal (e.g., self.x.next = ...)",
+            ))
+        }
+        _ => Err(hdl_unsupported(expr)),
     }
 }
 
 fn hdl_assignment(expr: &syn::ExprAssign) -> Result<TS> {
     if let syn::Expr::Index(_) = *expr.left {
-        Err(syn::Error::new(
-            expr.span(),
-            "Indexed assignments do not translate",
+        Err(syn::Error::new_spanned(
+            &expr.left,
+            "Indexed assignments do not translate to HDL; use .set_bit(i, value)",
         ))
     } else {
         hdl_non_indexed_assignment(expr)
@@ -105,10 +566,12 @@ fn hdl_non_indexed_assignment(expr: &syn::ExprAssign) -> Result<TS> {
     let target;
     if let Expr::Field(p) = &*expr.left {
         target = hdl_map_field_assign(p)?;
+    } else if let Expr::Path(p) = &*expr.left {
+        target = hdl_map_path(p)?;
     } else {
         return Err(syn::Error::new(
-            expr.span(),
-            "unsupported assignment type for HDL",
+            expr.left.span(),
+            "Only signals (x.next), struct fields and locals can be assigned in HDL",
         ));
     }
     let value = hdl_compute(expr.right.as_ref())?;
@@ -117,8 +580,23 @@ fn hdl_non_indexed_assignment(expr: &syn::ExprAssign) -> Result<TS> {
     }))
 }
 
+// fixup_ident refuses Verilog keywords, so check for them here to report them with a span
+fn hdl_ident(name: String, span: proc_macro2::Span) -> Result<String> {
+    let fixed = name.replace(' ', "").replace("self.", "");
+    if ["config", "input", "output"].contains(&fixed.as_str()) {
+        return Err(syn::Error::new(
+            span,
+            format!(
+                "`{}` is a Verilog keyword and cannot be used as a signal name; rename it",
+                fixed
+            ),
+        ));
+    }
+    Ok(common::fixup_ident(name))
+}
+
 fn hdl_map_field_assign(expr: &syn::ExprField) -> Result<TS> {
-    let expr_expanded = common::fixup_ident(quote!(#expr).to_string());
+    let expr_expanded = hdl_ident(quote!(#expr).to_string(), expr.span())?;
     if expr_expanded.ends_with("_val") {
         return Err(syn::Error::new(
             expr.span(),
@@ -129,7 +607,7 @@ fn hdl_map_field_assign(expr: &syn::ExprField) -> Result<TS> {
 }
 
 fn hdl_map_field(expr: &syn::ExprField) -> Result<TS> {
-    let expr_expanded = common::fixup_ident(quote!(#expr).to_string());
+    let expr_expanded = hdl_ident(quote!(#expr).to_string(), expr.span())?;
     if expr_expanded.ends_with("_next") {
         return Err(syn::Error::new(
             expr.span(),
@@ -139,8 +617,51 @@ fn hdl_map_field(expr: &syn::ExprField) -> Result<TS> {
     Ok(quote!(rust_hdl_core::ast::VerilogExpression::Signal(#expr_expanded.to_string())))
 }
 
+fn hdl_signal_name(expr: &syn::Expr) -> String {
+    if let Expr::MethodCall(method) = expr {
+        let method_name = method.method.to_string();
+        if method.args.is_empty() && ["val", "raw", "into"].contains(&method_name.as_str()) {
+            return hdl_signal_name(method.receiver.as_ref());
+        }
+    }
+    if let Some(name) = match expr {
+        Expr::Path(path) => path_local_name(path),
+        _ => None,
+    } {
+        return name;
+    }
+    common::fixup_ident(quote!(#expr).to_string())
+}
+
+// A signal or constant, possibly read through .val(), .raw() or .into()
+fn is_signal_read(expr: &syn::Expr) -> bool {
+    match expr {
+        Expr::MethodCall(method) => {
+            method.args.is_empty()
+                && ["val", "raw", "into"].contains(&method.method.to_string().as_str())
+                && is_signal_read(method.receiver.as_ref())
+        }
+        Expr::Field(_) | Expr::Path(_) => true,
+        _ => false,
+    }
+}
+
+fn hdl_read_signal_name(expr: &syn::Expr) -> Result<String> {
+    let signal = hdl_signal_name(expr);
+    if signal.ends_with("_next") {
+        return Err(syn::Error::new_spanned(
+            expr,
+            "Do not read from .next in HDL.  Use .val instead.",
+        ));
+    }
+    Ok(signal)
+}
+
 fn hdl_map_path(expr: &syn::ExprPath) -> Result<TS> {
-    let expr_expanded = common::fixup_ident(quote!(#expr).to_string());
+    if let Some(name) = path_local_name(expr) {
+        return Ok(quote!(rust_hdl_core::ast::VerilogExpression::Signal(#name.to_string())));
+    }
+    let expr_expanded = hdl_ident(quote!(#expr).to_string(), expr.span())?;
     Ok(quote!(rust_hdl_core::ast::VerilogExpression::Signal(#expr_expanded.to_string())))
 }
 
@@ -160,8 +681,8 @@ fn hdl_conditional(conditions: &syn::ExprIf) -> Result<TS> {
             }
             _ => {
                 return Err(syn::Error::new(
-                    conditions.span(),
-                    "Unsupported if/else structure",
+                    e_branch.span(),
+                    "Unsupported if/else structure; use else { .. } or else if",
                 ));
             }
         }
@@ -176,6 +697,12 @@ fn hdl_match(m: &syn::ExprMatch) -> Result<TS> {
     let mut condition = vec![];
     let mut blocks = vec![];
     for arm in &m.arms {
+        if let Some((if_token, guard)) = &arm.guard {
+            return Err(syn::Error::new_spanned(
+                quote!(#if_token #guard),
+                "Match guards are not supported in HDL; move the condition into an if inside the arm",
+            ));
+        }
         condition.push(hdl_pattern(&arm.pat)?);
         blocks.push(hdl_body(&arm.body)?);
     }
@@ -202,10 +729,16 @@ fn hdl_compute(m: &syn::Expr) -> Result<TS> {
         Expr::MethodCall(method) => hdl_method(method),
         Expr::Lit(lit) => hdl_literal(lit),
         Expr::Cast(cast) => hdl_cast(&cast),
-        _ => Err(syn::Error::new(
+        Expr::Group(group) => hdl_compute(&group.expr),
+        Expr::Assign(_) => Err(syn::Error::new(
+            m.span(),
+            "Assignments cannot be used as values in HDL; make the assignment a statement of its own",
+        )),
+        Expr::ForLoop(_) | Expr::Macro(_) => Err(syn::Error::new(
             m.span(),
-            format!("Unsupported expression type: {:?}", m),
+            "This statement cannot be used as a value in HDL",
         )),
+        _ => Err(hdl_unsupported(m)),
     }
 }
 
@@ -214,10 +747,10 @@ fn hdl_unop(unop: &syn::ExprUnary) -> Result<TS> {
     let op = match &unop.op {
         UnOp::Not(_) => quote!(rust_hdl_core::ast::VerilogOpUnary::Not),
         UnOp::Neg(_) => quote!(rust_hdl_core::ast::VerilogOpUnary::Neg),
-        _ => {
+        UnOp::Deref(x) => {
             return Err(syn::Error::new(
-                unop.span(),
-                "Unsupported operator in HDL code",
+                x.span(),
+                "Dereferencing is not supported in HDL; remove the *",
             ));
         }
     };
@@ -246,10 +779,16 @@ fn hdl_binop(binop: &syn::ExprBinary) -> Result<TS> {
         BinOp::Ne(_) => quote!(rust_hdl_core::ast::VerilogOp::Ne),
         BinOp::Ge(_) => quote!(rust_hdl_core::ast::VerilogOp::Ge),
         BinOp::Gt(_) => quote!(rust_hdl_core::ast::VerilogOp::Gt),
-        _ => {
+        BinOp::Div(_) | BinOp::Rem(_) => {
+            return Err(syn::Error::new(
+                binop.op.span(),
+                "Division and remainder are not supported in HDL; use shifts and masks for powers of two",
+            ));
+        }
+        op => {
             return Err(syn::Error::new(
-                binop.span(),
-                "Unsupported operator in HDL code",
+                op.span(),
+                "Compound assignments are not supported in HDL; write the operation out (e.g., x = x + y)",
             ));
         }
     };
@@ -259,6 +798,15 @@ fn hdl_binop(binop: &syn::ExprBinary) -> Result<TS> {
 }
 
 fn hdl_literal(lit: &syn::ExprLit) -> Result<TS> {
+    match &lit.lit {
+        syn::Lit::Int(_) | syn::Lit::Bool(_) => {}
+        _ => {
+            return Err(syn::Error::new(
+                lit.span(),
+                "Only integer and boolean literals are supported in HDL",
+            ))
+        }
+    }
     Ok(quote!({
        rust_hdl_core::ast::VerilogExpression::Literal(#lit.into())
     }))
@@ -272,16 +820,92 @@ fn hdl_cast(cast: &syn::ExprCast) -> Result<TS> {
     }))
 }
 
+// Only the calls named in functions(...) are lowered to #[hdl_function]s
+fn hdl_function_name(call: &syn::ExprCall) -> Option<&syn::Ident> {
+    match call.func.as_ref() {
+        Expr::Path(path) if path.qself.is_none() && path.path.segments.len() == 1 => {
+            let segment = &path.path.segments[0];
+            let routed = HDL_FUNCTIONS.with(|x| x.borrow().contains(&segment.ident.to_string()));
+            if segment.arguments.is_empty() && routed {
+                Some(&segment.ident)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
 fn hdl_call(call: &syn::ExprCall) -> Result<TS> {
+    if let Some(ident) = hdl_function_name(call) {
+        let companion = format_ident!("hdl_function_{}", ident);
+        let mut args = vec![];
+        for arg in &call.args {
+            args.push(hdl_compute(arg)?);
+        }
+        return Ok(quote!({
+            rust_hdl_core::ast::VerilogExpression::Call(Box::new(#companion()), vec![#(#args),*])
+        }));
+    }
     let funcname = quote!(#call).to_string();
+    for (builtin, count) in [
+        ("bit_cast", 1),
+        ("signed_bit_cast", 1),
+        ("mul_wide", 2),
+        ("all_true", 1),
+    ] {
+        if funcname.starts_with(builtin) && call.args.len() != count {
+            return Err(syn::Error::new(
+                call.func.span(),
+                format!("{} takes {} argument(s) in HDL", builtin, count),
+            ));
+        }
+    }
     if funcname.starts_with("bit_cast") {
         hdl_compute(&call.args[0])
+    } else if funcname.starts_with("signed_bit_cast") {
+        let arg = hdl_compute(&call.args[0])?;
+        Ok(quote!({
+        rust_hdl_core::ast::VerilogExpression::Signed(Box::new(#arg))
+        }))
+    } else if funcname.starts_with("concat") {
+        let mut args = vec![];
+        for arg in &call.args {
+            args.push(hdl_compute(arg)?);
+        }
+        Ok(quote!({
+            rust_hdl_core::ast::VerilogExpression::Concat(vec![#(#args),*])
+        }))
+    } else if funcname.starts_with("mul_wide") {
+        let a_expr = &call.args[0];
+        let b_expr = &call.args[1];
+        let a = hdl_compute(a_expr)?;
+        let b = hdl_compute(b_expr)?;
+        // Zero extend both factors to the product width so the multiply is full width in any context
+        Ok(quote!({
+        let a_zeros = rust_hdl_core::ast::VerilogExpression::Literal(rust_hdl_core::ast::VerilogLiteral::zero((#b_expr).len()));
+        let b_zeros = rust_hdl_core::ast::VerilogExpression::Literal(rust_hdl_core::ast::VerilogLiteral::zero((#a_expr).len()));
+        rust_hdl_core::ast::VerilogExpression::Paren(Box::new(
+            rust_hdl_core::ast::VerilogExpression::Binary(
+                Box::new(rust_hdl_core::ast::VerilogExpression::Concat(vec![a_zeros, #a])),
+                rust_hdl_core::ast::VerilogOp::Mul,
+                Box::new(rust_hdl_core::ast::VerilogExpression::Concat(vec![b_zeros, #b])))))
+        }))
     } else if funcname.starts_with("all_true") {
         let arg = hdl_compute(&call.args[0])?;
         Ok(quote!({
         rust_hdl_core::ast::VerilogExpression::Unary(rust_hdl_core::ast::VerilogOpUnary::All, Box::new(#arg))
         }))
     } else {
+        // Anything else is evaluated once when the HDL is generated, so it cannot depend on signals
+        let mut finder = SelfFinder::default();
+        finder.visit_expr_call(call);
+        if let Some(span) = finder.found {
+            return Err(syn::Error::new(
+                span,
+                "Only constant expressions can be passed to this call in HDL; use bit_cast, concat or an #[hdl_function] named in functions(...) to compute with signals",
+            ));
+        }
         Ok(quote!({
         rust_hdl_core::ast::VerilogExpression::Literal(#call.into())
         }))
@@ -293,7 +917,7 @@ fn hdl_method_set(method: &syn::ExprMethodCall) -> Result<TS> {
     let field_set_match = regex::Regex::new(r"set_value_([a-zA-Z][a-zA-Z0-9_]*)").unwrap();
     if field_set_match.is_match(method_name.as_ref()) {
         let expr = method.receiver.as_ref();
-        let signal = common::fixup_ident(quote!(#expr).to_string());
+        let signal = hdl_signal_name(expr);
         let field = field_set_match
             .captures(method_name.as_ref())
             .unwrap()
@@ -315,7 +939,7 @@ fn hdl_method_set(method: &syn::ExprMethodCall) -> Result<TS> {
         }));
     } else if method_name == "set_bit" {
         let expr = method.receiver.as_ref();
-        let signal = common::fixup_ident(quote!(#expr).to_string());
+        let signal = hdl_signal_name(expr);
         let index = hdl_compute(method.args.index(0))?;
         let value = hdl_compute(method.args.index(1))?;
         return Ok(quote!({
@@ -328,9 +952,9 @@ fn hdl_method_set(method: &syn::ExprMethodCall) -> Result<TS> {
         }));
     }
     Err(syn::Error::new(
-        method.span(),
+        method.method.span(),
         format!(
-            "Unsupported set method {} called for HDL conversion",
+            "Method `{}` cannot be used as an HDL statement; use .set_bit(i, value), .set_value_<field>(value) or assign to .next",
             method_name
         ),
     ))
@@ -341,7 +965,7 @@ fn hdl_method(method: &syn::ExprMethodCall) -> Result<TS> {
     let field_get_match = regex::Regex::new(r"get_value_([a-zA-Z][a-zA-Z0-9_]*)").unwrap();
     if field_get_match.is_match(method_name.as_ref()) {
         let expr = method.receiver.as_ref();
-        let signal = common::fixup_ident(quote!(#expr).to_string());
+        let signal = hdl_read_signal_name(expr)?;
         let field = field_get_match
             .captures(method_name.as_ref())
             .unwrap()
@@ -359,18 +983,17 @@ fn hdl_method(method: &syn::ExprMethodCall) -> Result<TS> {
     match method_name.as_ref() {
         "get_bits" => {
             let expr = method.receiver.as_ref();
-            let signal = common::fixup_ident(quote!(#expr).to_string());
-            if method.turbofish.is_none() {
-                return Err(syn::Error::new(method.span(), "get_bits needs a type argument to indicate the width of the slice (e.g., x.get_bits::<Bits4>(ndx))"));
-            }
-            if method.turbofish.as_ref().unwrap().args.len() != 1 {
-                return Err(syn::Error::new(method.span(), "get_bits needs a type argument to indicate the width of the slice (e.g., x.get_bits::<Bits4>(ndx))"));
-            }
-            let width_type = method.turbofish.as_ref().unwrap().args.first().unwrap();
-            let width = quote!(#width_type::bits());
+            let signal = hdl_read_signal_name(expr)?;
+            let width = match &method.turbofish {
+                Some(turbofish) if turbofish.args.len() == 1 => {
+                    let width = &turbofish.args[0];
+                    quote!((#width))
+                }
+                _ => return Err(syn::Error::new(method.method.span(), "get_bits needs the width of the slice as a constant (e.g., x.get_bits::<4>(ndx))")),
+            };
             if method.args.len() != 1 {
                 return Err(syn::Error::new(
-                    method.span(),
+                    method.args.span(),
                     "get_bits needs one argument (offset)",
                 ));
             }
@@ -381,11 +1004,11 @@ fn hdl_method(method: &syn::ExprMethodCall) -> Result<TS> {
         }
         "get_bit" => {
             let expr = method.receiver.as_ref();
-            let signal = common::fixup_ident(quote!(#expr).to_string());
+            let signal = hdl_read_signal_name(expr)?;
             if method.args.is_empty() {
                 return Err(syn::Error::new(
-                    method.span(),
-                    "get_bit must be supplied with an argument",
+                    method.method.span(),
+                    "get_bit must be supplied with an argument (the bit index)",
                 ));
             }
             let index = hdl_compute(method.args.first().unwrap())?;
@@ -397,8 +1020,8 @@ fn hdl_method(method: &syn::ExprMethodCall) -> Result<TS> {
             let receiver = hdl_compute(method.receiver.as_ref())?;
             if method.args.len() != 2 {
                 return Err(syn::Error::new(
-                    method.span(),
-                    "set_bit needs two arguments",
+                    method.method.span(),
+                    "replace_bit needs two arguments (index, value)",
                 ));
             }
             let index = hdl_compute(method.args.index(0))?;
@@ -408,20 +1031,110 @@ fn hdl_method(method: &syn::ExprMethodCall) -> Result<TS> {
             }))
         }
         "any" => {
-            let receiver = method.receiver.as_ref();
-            let signal = common::fixup_ident(quote!(#receiver).to_string());
+            let signal = hdl_read_signal_name(method.receiver.as_ref())?;
             Ok(quote!({
             rust_hdl_core::ast::VerilogExpression::Unary(rust_hdl_core::ast::VerilogOpUnary::Any,
                 Box::new(rust_hdl_core::ast::VerilogExpression::Signal(#signal.to_string())))
             }))
         }
+        "mul_full" => {
+            if method.args.len() != 1 {
+                return Err(syn::Error::new(
+                    method.method.span(),
+                    "mul_full needs one argument (the other factor)",
+                ));
+            }
+            let a_expr = method.receiver.as_ref();
+            let b_expr = &method.args[0];
+            for factor in [a_expr, b_expr] {
+                if !is_signal_read(factor) {
+                    return Err(syn::Error::new(
+                        factor.span(),
+                        "The factors of mul_full must be signals or constants in HDL (assign the expression to a local first)",
+                    ));
+                }
+            }
+            let a = hdl_read_signal_name(a_expr)?;
+            let b = hdl_read_signal_name(b_expr)?;
+            // Extend both factors to the product width so the multiply is full width in any context
+            Ok(quote!({
+               let a_width = rust_hdl_core::synth::SynthWidth::synth_width(&(#a_expr));
+               let b_width = rust_hdl_core::synth::SynthWidth::synth_width(&(#b_expr));
+               let signed = rust_hdl_core::synth::SynthWidth::synth_signed(&(#a_expr));
+               let extend = |name: &str, width: usize, extra: usize| {
+                   if !signed {
+                       return rust_hdl_core::ast::VerilogExpression::Concat(vec![
+                           rust_hdl_core::ast::VerilogExpression::Literal(rust_hdl_core::ast::VerilogLiteral::zero(extra)),
+                           rust_hdl_core::ast::VerilogExpression::Signal(name.to_string())]);
+                   }
+                   rust_hdl_core::ast::VerilogExpression::Signed(Box::new(
+                       rust_hdl_core::ast::VerilogExpression::Concat(vec![
+                           rust_hdl_core::ast::VerilogExpression::Replicate(Box::new(
+                               rust_hdl_core::ast::VerilogExpression::Index(name.to_string(),
+                                   Box::new(rust_hdl_core::ast::VerilogExpression::Literal((width - 1).into())))), extra),
+                           rust_hdl_core::ast::VerilogExpression::Signal(name.to_string())])))
+               };
+               rust_hdl_core::ast::VerilogExpression::Paren(Box::new(
+                   rust_hdl_core::ast::VerilogExpression::Binary(Box::new(extend(#a, a_width, b_width)),
+                   rust_hdl_core::ast::VerilogOp::Mul, Box::new(extend(#b, b_width, a_width)))))
+            }))
+        }
+        "truncate_to" | "round_to" => {
+            let expr = method.receiver.as_ref();
+            let receiver = hdl_compute(expr)?;
+            let frac_bits = match &method.turbofish {
+                Some(turbofish) if turbofish.args.len() == 2 => turbofish.args.last().unwrap(),
+                _ => {
+                    return Err(syn::Error::new(
+                        method.method.span(),
+                        format!(
+                            "{} needs the target widths as type arguments (e.g., x.{}::<4, 12>())",
+                            method_name, method_name
+                        ),
+                    ))
+                }
+            };
+            let rounding = if method_name == "round_to" {
+                quote!(shift > 0)
+            } else {
+                quote!(false)
+            };
+            Ok(quote!({
+               let shift: usize = #expr.frac_bits() - #frac_bits;
+               let value = if #rounding {
+                   rust_hdl_core::ast::VerilogExpression::Paren(Box::new(
+                       rust_hdl_core::ast::VerilogExpression::Binary(Box::new(#receiver), rust_hdl_core::ast::VerilogOp::Add,
+                       Box::new(rust_hdl_core::ast::VerilogExpression::Literal((1_usize << (shift - 1)).into())))))
+               } else {
+                   #receiver
+               };
+               rust_hdl_core::ast::VerilogExpression::Paren(Box::new(
+                   rust_hdl_core::ast::VerilogExpression::Binary(Box::new(value), rust_hdl_core::ast::VerilogOp::Shr,
+                   Box::new(rust_hdl_core::ast::VerilogExpression::Literal(shift.into())))))
+            }))
+        }
+        "as_signed" => {
+            let receiver = hdl_compute(method.receiver.as_ref())?;
+            Ok(quote!({
+               rust_hdl_core::ast::VerilogExpression::Signed(Box::new(#receiver))
+            }))
+        }
+        "as_unsigned" => {
+            let receiver = hdl_compute(method.receiver.as_ref())?;
+            Ok(quote!({
+               rust_hdl_core::ast::VerilogExpression::Unsigned(Box::new(#receiver))
+            }))
+        }
         "val" | "into" | "raw" => {
             let receiver = method.receiver.as_ref();
             hdl_compute(receiver)
         }
         _ => Err(syn::Error::new(
-            method.span(),
-            "Unsupported method call for hardware conversion",
+            method.method.span(),
+            format!(
+                "Method `{}` has no HDL translation; supported methods are val, into, raw, get_bit, get_bits, replace_bit, any, as_signed, as_unsigned, mul_full, truncate_to, round_to and get_value_<field>",
+                method_name
+            ),
         )),
     }
 }
@@ -441,12 +1154,13 @@ fn hdl_pattern(pat: &Pat) -> Result<String> {
         Pat::Lit(lit) => Ok(quote!(#lit).to_string()),
         Pat::Path(pat) => Ok(common::fixup_ident(quote!(#pat).to_string())),
         Pat::Wild(_pat) => Ok("default".to_string()),
+        Pat::Or(_) => Err(syn::Error::new(
+            pat.span(),
+            "Or patterns are not supported in HDL match arms; write one arm per value",
+        )),
         _ => Err(syn::Error::new(
             pat.span(),
-            format!(
-                "pattern type {:?} is not allowable in match statements for HDL",
-                pat
-            ),
+            "Only literals, constants, enum variants and _ can be used as HDL match patterns",
         )),
     }
 }
@@ -480,9 +1194,40 @@ fn hdl_macro(x: &syn::ExprMacro) -> Result<TS> {
                 quote!(rust_hdl_core::ast::VerilogStatement::Comment(#invocation_as_string.to_string())),
             )
         }
+        "hdl_assert" => hdl_assert(x),
+        "hdl_assume" => {
+            let test = hdl_compute(&x.mac.parse_body::<Expr>()?)?;
+            Ok(quote!(rust_hdl_core::ast::VerilogStatement::Assume(#test)))
+        }
         _ => Err(syn::Error::new(
-            x.span(),
-            "Unsupported macro invokation in HDL",
+            x.mac.path.span(),
+            format!(
+                "Macro `{}!` is not supported in HDL; only println!, comment!, assert!, hdl_assert! and hdl_assume! are allowed",
+                macro_name
+            ),
         )),
     }
 }
+
+fn hdl_assert(x: &syn::ExprMacro) -> Result<TS> {
+    let args = x
+        .mac
+        .parse_body_with(syn::punctuated::Punctuated::<Expr, Token![,]>::parse_terminated)?;
+    let message = match (args.len(), args.last()) {
+        (
+            2,
+            Some(Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(message),
+                ..
+            })),
+        ) => message.value(),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &x.mac,
+                "hdl_assert! takes a condition and a string literal, e.g. hdl_assert!(self.x.val() != 0, \"x is zero\")",
+            ))
+        }
+    };
+    let test = hdl_compute(&args[0])?;
+    Ok(quote!(rust_hdl_core::ast::VerilogStatement::Assert(#test, #message.to_string())))
+}
@@ -3,15 +3,21 @@ mod connect_gen;
 mod hdl_gen;
 mod logic_block;
 mod logic_interface;
+mod logic_state;
+mod logic_struct;
 
 use syn::parse_macro_input;
 use syn::DeriveInput;
 
 use crate::common::TS;
 use crate::connect_gen::connect_gen;
-use crate::hdl_gen::hdl_gen_process;
+use crate::hdl_gen::{
+    hdl_function_process, hdl_gen_process, hdl_gen_update, HdlFunctionArgs, HdlGenArgs,
+};
 use crate::logic_block::get_impl_for_logic_block;
 use crate::logic_interface::get_impl_for_logic_interface;
+use crate::logic_state::get_impl_for_logic_state;
+use crate::logic_struct::get_impl_for_logic_struct;
 use proc_macro::TokenStream;
 use quote::quote;
 
@@ -35,18 +41,50 @@ pub fn logic_interface(input: TokenStream) -> TokenStream {
     }
 }
 
+#[proc_macro_derive(LogicState, attributes(logic_state))]
+pub fn logic_state(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match get_impl_for_logic_state(&input) {
+        Err(e) => e.to_compile_error().into(),
+        Ok(x) => x.into(),
+    }
+}
+
+#[proc_macro_derive(LogicStruct)]
+pub fn logic_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match get_impl_for_logic_struct(&input) {
+        Err(e) => e.to_compile_error().into(),
+        Ok(x) => x.into(),
+    }
+}
+
 #[proc_macro_attribute]
-pub fn hdl_gen(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn hdl_gen(attr: TokenStream, item: TokenStream) -> TokenStream {
     let orig = TS::from(item.clone());
+    let args = match syn::parse::<HdlGenArgs>(attr) {
+        Err(e) => {
+            let err = e.to_compile_error();
+            return TokenStream::from(quote!(#orig #err));
+        }
+        Ok(args) => args,
+    };
     let parse = parse_macro_input!(item as syn::ItemFn);
+    let update = hdl_gen_update(&parse, &args);
     let connects = match connect_gen(&parse) {
         Err(e) => return e.to_compile_error().into(),
         Ok(t) => t,
     };
-    match hdl_gen_process(parse) {
-        Err(e) => e.to_compile_error().into(),
+    // Keep the original function on error so only the HDL diagnostic is reported
+    match hdl_gen_process(parse, &args) {
+        Err(e) => {
+            let err = e.to_compile_error();
+            TokenStream::from(quote!(#orig #err))
+        }
         Ok(hdl_code) => TokenStream::from(quote! {
-            #orig
+            #update
 
         #[allow(dead_code)]
         #[allow(unused_variables)]
@@ -60,3 +98,29 @@ pub fn hdl_gen(_attr: TokenStream, item: TokenStream) -> TokenStream {
         }),
     }
 }
+
+#[proc_macro_attribute]
+pub fn hdl_function(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let orig = TS::from(item.clone());
+    let args = match syn::parse::<HdlFunctionArgs>(attr) {
+        Err(e) => {
+            let err = e.to_compile_error();
+            return TokenStream::from(quote!(#orig #err));
+        }
+        Ok(args) => args,
+    };
+    let parse = parse_macro_input!(item as syn::ItemFn);
+    match hdl_function_process(parse, &args) {
+        Err(e) => {
+            let err = e.to_compile_error();
+            TokenStream::from(quote!(#orig #err))
+        }
+        Ok(hdl_code) => TokenStream::from(quote! {
+            #orig
+
+        #[allow(dead_code)]
+        #[allow(unused_variables)]
+            #hdl_code
+        }),
+    }
+}
@@ -0,0 +1,173 @@
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Data, Fields, Meta, NestedMeta, Result};
+
+use crate::common::TS;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Encoding {
+    Binary,
+    OneHot,
+}
+
+fn get_encoding(input: &syn::DeriveInput) -> Result<Encoding> {
+    let mut encoding = Encoding::Binary;
+    for attr in &input.attrs {
+        if !attr.path.is_ident("logic_state") {
+            continue;
+        }
+        if let Meta::List(list) = attr.parse_meta()? {
+            for item in &list.nested {
+                match item {
+                    NestedMeta::Meta(Meta::Path(p)) if p.is_ident("binary") => {
+                        encoding = Encoding::Binary
+                    }
+                    NestedMeta::Meta(Meta::Path(p)) if p.is_ident("one_hot") => {
+                        encoding = Encoding::OneHot
+                    }
+                    _ => {
+                        return Err(syn::Error::new(
+                            item.span(),
+                            "Unsupported state encoding (use binary or one_hot)",
+                        ))
+                    }
+                }
+            }
+        } else {
+            return Err(syn::Error::new(
+                attr.span(),
+                "Expected an encoding, e.g. #[logic_state(one_hot)]",
+            ));
+        }
+    }
+    Ok(encoding)
+}
+
+fn get_variants(input: &syn::DeriveInput) -> Result<Vec<syn::Ident>> {
+    match &input.data {
+        Data::Enum(de) => {
+            let mut variants = vec![];
+            for variant in &de.variants {
+                if !matches!(variant.fields, Fields::Unit) {
+                    return Err(syn::Error::new(
+                        variant.span(),
+                        "LogicState enums cannot have fields",
+                    ));
+                }
+                if let Some((_, discriminant)) = &variant.discriminant {
+                    return Err(syn::Error::new(
+                        discriminant.span(),
+                        "LogicState assigns the encoding itself - remove the explicit discriminant",
+                    ));
+                }
+                variants.push(variant.ident.clone());
+            }
+            if variants.is_empty() {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "LogicState enums must have at least one variant",
+                ));
+            }
+            Ok(variants)
+        }
+        _ => Err(syn::Error::new(
+            input.span(),
+            "LogicState can only be derived for enums",
+        )),
+    }
+}
+
+fn binary_width(count: usize) -> usize {
+    let mut width = 1;
+    while (1_usize << width) < count {
+        width += 1;
+    }
+    width
+}
+
+pub(crate) fn get_impl_for_logic_state(input: &syn::DeriveInput) -> Result<TS> {
+    let encoding = get_encoding(input)?;
+    let variants = get_variants(input)?;
+    let name = &input.ident;
+    let type_name = name.to_string();
+    let (width, codes) = match encoding {
+        Encoding::Binary => (
+            binary_width(variants.len()),
+            (0..variants.len()).map(|x| x as u128).collect::<Vec<_>>(),
+        ),
+        Encoding::OneHot => {
+            // The discriminants are usize
+            if variants.len() > 64 {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "One hot encoding supports at most 64 states",
+                ));
+            }
+            (
+                variants.len(),
+                (0..variants.len()).map(|x| 1_u128 << x).collect::<Vec<_>>(),
+            )
+        }
+    };
+    let variant_names = variants.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    let codes_usize = codes.iter().map(|x| *x as usize).collect::<Vec<_>>();
+    let first = &variants[0];
+    Ok(quote! {
+        impl Default for #name {
+            fn default() -> Self {
+                #name::#first
+            }
+        }
+
+        impl rust_hdl_core::synth::Synth for #name {
+            const BITS: usize = #width;
+            const ENUM_TYPE: bool = true;
+            const TYPE_NAME: &'static str = #type_name;
+            fn name(ndx: usize) -> &'static str {
+                match ndx {
+                    #(#codes_usize => #variant_names,)*
+                    _ => "",
+                }
+            }
+            fn enums() -> Vec<rust_hdl_core::synth::SynthEnum> {
+                vec![rust_hdl_core::synth::SynthEnum {
+                    type_name: #type_name,
+                    discriminants: vec![#((#variant_names, #codes_usize)),*],
+                }]
+            }
+            fn vcd(self) -> rust_hdl_core::synth::VCDValue {
+                match self {
+                    #(#name::#variants => rust_hdl_core::synth::VCDValue::String(#variant_names.into()),)*
+                }
+            }
+            fn verilog(self) -> rust_hdl_core::ast::VerilogLiteral {
+                rust_hdl_core::bits::Bits::<#width>::from(self).into()
+            }
+        }
+
+        impl From<#name> for rust_hdl_core::bits::Bits<#width> {
+            fn from(x: #name) -> Self {
+                let xval: u128 = match x {
+                    #(#name::#variants => #codes,)*
+                };
+                xval.into()
+            }
+        }
+
+        impl From<rust_hdl_core::bits::Bits<#width>> for #name {
+            fn from(x: rust_hdl_core::bits::Bits<#width>) -> Self {
+                let xval: u128 = x.into();
+                match xval {
+                    #(#codes => #name::#variants,)*
+                    _ => panic!("Illegal conversion"),
+                }
+            }
+        }
+
+        impl<D: rust_hdl_core::clock::Domain> From<#name> for rust_hdl_core::prelude::Tagged<#name, D> {
+            fn from(x: #name) -> Self {
+                rust_hdl_core::prelude::Tagged(x, std::marker::PhantomData)
+            }
+        }
+    })
+}
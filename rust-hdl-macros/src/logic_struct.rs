@@ -0,0 +1,156 @@
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{Data, Result};
+
+use crate::common::TS;
+
+pub(crate) fn get_impl_for_logic_struct(input: &syn::DeriveInput) -> Result<TS> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "LogicStruct does not support generic structs",
+        ));
+    }
+    let ds = match &input.data {
+        Data::Struct(ds) => ds,
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "LogicStruct can only be derived for structs",
+            ))
+        }
+    };
+    let mut fields = vec![];
+    let mut types = vec![];
+    for field in &ds.fields {
+        match &field.ident {
+            Some(ident) => fields.push(ident.clone()),
+            None => {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "Unnamed fields are not supported",
+                ))
+            }
+        }
+        types.push(field.ty.clone());
+    }
+    if fields.is_empty() {
+        return Err(syn::Error::new(
+            input.span(),
+            "LogicStruct structs must have at least one field",
+        ));
+    }
+    let name = &input.ident;
+    let type_name = name.to_string();
+    let field_names = fields.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    let widths = types
+        .iter()
+        .map(|ty| quote!(<#ty as rust_hdl_core::synth::Synth>::BITS))
+        .collect::<Vec<_>>();
+    let offsets = (0..fields.len())
+        .map(|ndx| {
+            let previous = &widths[0..ndx];
+            quote!(0 #(+ #previous)*)
+        })
+        .collect::<Vec<_>>();
+    let get_value = fields
+        .iter()
+        .map(|x| format_ident!("get_value_{}", x))
+        .collect::<Vec<_>>();
+    let set_value = fields
+        .iter()
+        .map(|x| format_ident!("set_value_{}", x))
+        .collect::<Vec<_>>();
+    let get_width = fields
+        .iter()
+        .map(|x| format_ident!("get_width_{}", x))
+        .collect::<Vec<_>>();
+    let get_offset = fields
+        .iter()
+        .map(|x| format_ident!("get_my_offset_{}", x))
+        .collect::<Vec<_>>();
+    Ok(quote! {
+        impl rust_hdl_core::synth::Synth for #name {
+            const BITS: usize = #(#widths)+*;
+            const STRUCT_TYPE: bool = true;
+            const TYPE_NAME: &'static str = #type_name;
+            fn structs() -> Vec<rust_hdl_core::synth::SynthStruct> {
+                let mut ret = vec![];
+                #(ret.extend(<#types as rust_hdl_core::synth::Synth>::structs());)*
+                ret.push(rust_hdl_core::synth::SynthStruct {
+                    type_name: #type_name,
+                    fields: vec![#(
+                        rust_hdl_core::synth::SynthStructField {
+                            name: #field_names,
+                            bits: <#types as rust_hdl_core::synth::Synth>::BITS,
+                            signed: <#types as rust_hdl_core::synth::Synth>::SIGNED,
+                            type_name: if <#types as rust_hdl_core::synth::Synth>::ENUM_TYPE
+                                || <#types as rust_hdl_core::synth::Synth>::STRUCT_TYPE
+                            {
+                                Some(<#types as rust_hdl_core::synth::Synth>::TYPE_NAME)
+                            } else {
+                                None
+                            },
+                        },
+                    )*],
+                });
+                ret
+            }
+            fn enums() -> Vec<rust_hdl_core::synth::SynthEnum> {
+                let mut ret = vec![];
+                #(ret.extend(<#types as rust_hdl_core::synth::Synth>::enums());)*
+                ret
+            }
+            fn vcd(self) -> rust_hdl_core::synth::VCDValue {
+                let t: rust_hdl_core::bits::Bits<{ #name::BITS }> = self.into();
+                t.into()
+            }
+            fn verilog(self) -> rust_hdl_core::ast::VerilogLiteral {
+                let t: rust_hdl_core::bits::Bits<{ #name::BITS }> = self.into();
+                t.into()
+            }
+        }
+
+        impl From<#name> for rust_hdl_core::bits::Bits<{ #name::BITS }> {
+            fn from(x: #name) -> Self {
+                let mut ret = Self::default();
+                #(ret.set_bits::<{ #widths }>(#offsets, x.#fields.into());)*
+                ret
+            }
+        }
+
+        impl From<rust_hdl_core::bits::Bits<{ #name::BITS }>> for #name {
+            fn from(x: rust_hdl_core::bits::Bits<{ #name::BITS }>) -> Self {
+                #name {
+                    #(#fields: x.get_bits::<{ #widths }>(#offsets).into(),)*
+                }
+            }
+        }
+
+        impl<D: rust_hdl_core::clock::Domain> From<#name> for rust_hdl_core::prelude::Tagged<#name, D> {
+            fn from(x: #name) -> Self {
+                rust_hdl_core::prelude::Tagged(x, std::marker::PhantomData)
+            }
+        }
+
+        impl #name {
+            #(
+            pub fn #get_value(&self) -> #types {
+                self.#fields
+            }
+
+            pub fn #set_value(&mut self, val: #types) {
+                self.#fields = val;
+            }
+
+            pub fn #get_width(&self) -> usize {
+                #widths
+            }
+
+            pub fn #get_offset(&self) -> usize {
+                #offsets
+            }
+            )*
+        }
+    })
+}
@@ -1,6 +1,9 @@
+use std::collections::BTreeMap;
 use std::env::temp_dir;
-use std::fs::{create_dir, remove_dir_all, File};
+use std::fmt::{Display, Formatter};
+use std::fs::{create_dir, read_to_string, remove_dir_all, File};
 use std::io::{Error, Write};
+use std::path::PathBuf;
 use std::process::Command;
 
 #[derive(Debug)]
@@ -8,6 +11,7 @@ pub enum SynthError {
     SynthesisFailed { stdout: String, stderr: String },
     LatchingWriteToSignal(Vec<String>),
     IOError(std::io::Error),
+    BadReport(String),
 }
 
 impl From<std::io::Error> for SynthError {
@@ -56,3 +60,431 @@ pub fn yosys_validate(prefix: &str, translation: &str) -> Result<(), SynthError>
     }
     Ok(())
 }
+
+// Analyzes VHDL-2008 output with ghdl.  A missing ghdl shows up as an IOError.
+pub fn ghdl_validate(prefix: &str, translation: &str) -> Result<(), SynthError> {
+    let dir = temp_dir().as_path().join(prefix);
+    let _ = remove_dir_all(&dir);
+    let _ = create_dir(&dir);
+    let mut vhd_file = File::create(dir.clone().join("top.vhd"))?;
+    write!(vhd_file, "{}", translation)?;
+    let output = Command::new("ghdl")
+        .current_dir(dir.clone())
+        .arg("-a")
+        .arg("--std=08")
+        .arg("top.vhd")
+        .output()?;
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    if !output.status.success() {
+        return Err(SynthError::SynthesisFailed { stdout, stderr });
+    }
+    Ok(())
+}
+
+// Reads the JSON netlist, plus the Verilog for any modules it leaves out, the way a
+// yosys flow would.  A missing yosys shows up as an IOError.
+pub fn yosys_netlist_validate(prefix: &str, json: &str, verilog: &str) -> Result<(), SynthError> {
+    let dir = temp_dir().as_path().join(prefix);
+    let _ = remove_dir_all(&dir);
+    let _ = create_dir(&dir);
+    let mut json_file = File::create(dir.clone().join("top.json"))?;
+    write!(json_file, "{}", json)?;
+    let mut script = "read_json top.json; ".to_string();
+    if !verilog.trim().is_empty() {
+        let mut v_file = File::create(dir.clone().join("custom.v"))?;
+        write!(v_file, "{}", verilog)?;
+        script += "read_verilog custom.v; ";
+    }
+    script += "hierarchy -check -top top; proc; opt";
+    let output = Command::new("yosys")
+        .current_dir(dir.clone())
+        .arg("-p")
+        .arg(script)
+        .output()?;
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    if !output.status.success() || !stdout.contains("End of script.") {
+        return Err(SynthError::SynthesisFailed { stdout, stderr });
+    }
+    Ok(())
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FormalMode {
+    // Checks the assertions for the first depth cycles from reset
+    Bmc,
+    // Proves they hold in every cycle, using induction over depth cycles
+    KInduction,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FormalResult {
+    Pass,
+    // A trace from reset that ends in a failed assertion
+    Fail { counterexample: PathBuf },
+    // The induction step failed, from a state that may not be reachable.  A larger
+    // depth or more assertions may close the proof.
+    Unknown { counterexample: Option<PathBuf> },
+}
+
+// The assertions (and assumptions) of the design are only seen by yosys when it
+// reads the Verilog with -formal, which defines FORMAL
+pub fn sby_project(mode: FormalMode, depth: usize) -> String {
+    let mode = match mode {
+        FormalMode::Bmc => "bmc",
+        FormalMode::KInduction => "prove",
+    };
+    format!(
+        "[options]
+mode {}
+depth {}
+
+[engines]
+smtbmc
+
+[script]
+read -formal top.v
+prep -top top
+
+[files]
+top.v
+",
+        mode, depth
+    )
+}
+
+// Runs SymbiYosys on the design.  A missing sby shows up as an IOError.
+pub fn sby_verify(
+    prefix: &str,
+    translation: &str,
+    mode: FormalMode,
+    depth: usize,
+) -> Result<FormalResult, SynthError> {
+    let dir = temp_dir().as_path().join(prefix);
+    let _ = remove_dir_all(&dir);
+    let _ = create_dir(&dir);
+    let mut v_file = File::create(dir.clone().join("top.v"))?;
+    write!(v_file, "{}", translation)?;
+    let mut sby_file = File::create(dir.clone().join("top.sby"))?;
+    write!(sby_file, "{}", sby_project(mode, depth))?;
+    let output = Command::new("sby")
+        .current_dir(dir.clone())
+        .arg("-f")
+        .arg("top.sby")
+        .output()?;
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let trace = |name: &str| {
+        let path = dir.join("top").join("engine_0").join(name);
+        if path.exists() {
+            Some(path)
+        } else {
+            None
+        }
+    };
+    if stdout.contains("DONE (PASS") {
+        Ok(FormalResult::Pass)
+    } else if stdout.contains("DONE (FAIL") {
+        match trace("trace.vcd") {
+            Some(counterexample) => Ok(FormalResult::Fail { counterexample }),
+            None => Err(SynthError::SynthesisFailed { stdout, stderr }),
+        }
+    } else if stdout.contains("DONE (UNKNOWN") {
+        Ok(FormalResult::Unknown {
+            counterexample: trace("trace_induct.vcd"),
+        })
+    } else {
+        Err(SynthError::SynthesisFailed { stdout, stderr })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModuleUtilization {
+    pub luts: usize,
+    pub carries: usize,
+    pub dffs: usize,
+    pub brams: usize,
+    pub plls: usize,
+    // Every cell type, including instances of other modules
+    pub cells: BTreeMap<String, usize>,
+}
+
+impl ModuleUtilization {
+    fn from_cells(cells: BTreeMap<String, usize>) -> ModuleUtilization {
+        let count = |f: &dyn Fn(&str) -> bool| {
+            cells
+                .iter()
+                .filter(|(name, _)| f(name))
+                .map(|(_, count)| count)
+                .sum()
+        };
+        ModuleUtilization {
+            // iCE40 cells, then ECP5 ones
+            luts: count(&|x| x == "SB_LUT4" || x == "LUT4"),
+            carries: count(&|x| x == "SB_CARRY" || x == "CCU2C"),
+            dffs: count(&|x| x.starts_with("SB_DFF") || x == "TRELLIS_FF"),
+            brams: count(&|x| x.starts_with("SB_RAM40_4K") || x == "DP16KD" || x == "PDPW16KD"),
+            plls: count(&|x| x.starts_with("SB_PLL40") || x == "EHXPLLL"),
+            cells,
+        }
+    }
+}
+
+impl Display for ModuleUtilization {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} LUTs, {} carries, {} DFFs, {} BRAMs, {} PLLs",
+            self.luts, self.carries, self.dffs, self.brams, self.plls
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UtilizationReport {
+    // Each module on its own, without the modules it instantiates
+    pub modules: BTreeMap<String, ModuleUtilization>,
+    // The whole design, with every instance counted
+    pub total: ModuleUtilization,
+}
+
+fn cells_by_type(x: &serde_json::Value) -> Result<BTreeMap<String, usize>, SynthError> {
+    x["num_cells_by_type"]
+        .as_object()
+        .map(|cells| {
+            cells
+                .iter()
+                .map(|(name, count)| {
+                    (
+                        name.trim_start_matches('\\').to_string(),
+                        count.as_u64().unwrap_or(0) as usize,
+                    )
+                })
+                .collect()
+        })
+        .ok_or_else(|| SynthError::BadReport("Missing num_cells_by_type".into()))
+}
+
+impl UtilizationReport {
+    // Parses the output of yosys' stat -json
+    pub fn from_stat_json(json: &str) -> Result<UtilizationReport, SynthError> {
+        let stat: serde_json::Value =
+            serde_json::from_str(json).map_err(|x| SynthError::BadReport(x.to_string()))?;
+        let modules = stat["modules"]
+            .as_object()
+            .ok_or_else(|| SynthError::BadReport("Missing modules".into()))?
+            .iter()
+            .map(|(name, x)| {
+                Ok((
+                    name.trim_start_matches('\\').to_string(),
+                    ModuleUtilization::from_cells(cells_by_type(x)?),
+                ))
+            })
+            .collect::<Result<_, SynthError>>()?;
+        let total = ModuleUtilization::from_cells(cells_by_type(&stat["design"])?);
+        Ok(UtilizationReport { modules, total })
+    }
+
+    pub fn module(&self, name: &str) -> Option<&ModuleUtilization> {
+        self.modules.get(name)
+    }
+}
+
+impl Display for UtilizationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (name, module) in &self.modules {
+            writeln!(f, "{}: {}", name, module)?;
+        }
+        write!(f, "total: {}", self.total)
+    }
+}
+
+// Synthesizes the design for the iCE40 with its hierarchy kept, so that each module
+// gets its own counts.  A missing yosys shows up as an IOError.
+pub fn yosys_utilization(prefix: &str, translation: &str) -> Result<UtilizationReport, SynthError> {
+    let dir = temp_dir().as_path().join(prefix);
+    let _ = remove_dir_all(&dir);
+    let _ = create_dir(&dir);
+    let mut v_file = File::create(dir.clone().join("top.v"))?;
+    write!(v_file, "{}", translation)?;
+    let output = Command::new("yosys")
+        .current_dir(dir.clone())
+        .arg("-p")
+        .arg("synth_ice40 -noflatten -top top; tee -q -o stat.json stat -json")
+        .arg("top.v")
+        .output()?;
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    if !output.status.success() {
+        return Err(SynthError::SynthesisFailed { stdout, stderr });
+    }
+    UtilizationReport::from_stat_json(&read_to_string(dir.join("stat.json"))?)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClockTiming {
+    pub achieved_mhz: f64,
+    pub constraint_mhz: f64,
+}
+
+impl ClockTiming {
+    pub fn met(&self) -> bool {
+        self.achieved_mhz >= self.constraint_mhz
+    }
+}
+
+impl Display for ClockTiming {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.2} MHz ({} at {:.2} MHz)",
+            self.achieved_mhz,
+            if self.met() { "PASS" } else { "FAIL" },
+            self.constraint_mhz
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimingReport {
+    // By the name of the clock net after placement, e.g. clock$SB_IO_IN_$glb_clk
+    pub clocks: BTreeMap<String, ClockTiming>,
+}
+
+impl TimingReport {
+    // Parses the fmax section of the report nextpnr writes with --report
+    pub fn from_nextpnr_json(json: &str) -> Result<TimingReport, SynthError> {
+        let report: serde_json::Value =
+            serde_json::from_str(json).map_err(|x| SynthError::BadReport(x.to_string()))?;
+        let clocks = report["fmax"]
+            .as_object()
+            .ok_or_else(|| SynthError::BadReport("Missing fmax".into()))?
+            .iter()
+            .map(
+                |(name, x)| match (x["achieved"].as_f64(), x["constraint"].as_f64()) {
+                    (Some(achieved_mhz), Some(constraint_mhz)) => Ok((
+                        name.clone(),
+                        ClockTiming {
+                            achieved_mhz,
+                            constraint_mhz,
+                        },
+                    )),
+                    _ => Err(SynthError::BadReport(format!("Bad fmax for {}", name))),
+                },
+            )
+            .collect::<Result<_, SynthError>>()?;
+        Ok(TimingReport { clocks })
+    }
+
+    // Looks a clock up by the name of its net in the design
+    pub fn clock(&self, name: &str) -> Option<&ClockTiming> {
+        self.clocks
+            .iter()
+            .find(|(x, _)| {
+                *x == name
+                    || x.starts_with(&format!("{}$", name))
+                    || x.starts_with(&format!("{}_$", name))
+            })
+            .map(|x| x.1)
+    }
+
+    pub fn failing(&self) -> Vec<&str> {
+        self.clocks
+            .iter()
+            .filter(|(_, x)| !x.met())
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+impl Display for TimingReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let lines = self
+            .clocks
+            .iter()
+            .map(|(name, x)| format!("{}: {}", name, x))
+            .collect::<Vec<_>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{TimingReport, UtilizationReport};
+
+    #[test]
+    fn test_utilization_report() {
+        let stat = r#"{
+            "creator": "Yosys",
+            "invocation": "stat -json ",
+            "modules": {
+                "\\top": {
+                    "num_wires": 12,
+                    "num_cells": 4,
+                    "num_cells_by_type": {
+                        "SB_PLL40_CORE": 1,
+                        "top_counter": 2,
+                        "SB_LUT4": 1
+                    }
+                },
+                "\\top_counter": {
+                    "num_wires": 20,
+                    "num_cells": 18,
+                    "num_cells_by_type": {
+                        "SB_CARRY": 6,
+                        "SB_DFFE": 4,
+                        "SB_DFFSR": 4,
+                        "SB_LUT4": 7,
+                        "SB_RAM40_4K": 1
+                    }
+                }
+            },
+            "design": {
+                "num_wires": 52,
+                "num_cells": 38,
+                "num_cells_by_type": {
+                    "SB_CARRY": 12,
+                    "SB_DFFE": 8,
+                    "SB_DFFSR": 8,
+                    "SB_LUT4": 15,
+                    "SB_PLL40_CORE": 1,
+                    "SB_RAM40_4K": 2
+                }
+            }
+        }"#;
+        let report = UtilizationReport::from_stat_json(stat).unwrap();
+        let top = report.module("top").unwrap();
+        assert_eq!((top.luts, top.plls, top.dffs), (1, 1, 0));
+        assert_eq!(top.cells["top_counter"], 2);
+        let counter = report.module("top_counter").unwrap();
+        assert_eq!(
+            (counter.luts, counter.carries, counter.dffs, counter.brams),
+            (7, 6, 8, 1)
+        );
+        assert_eq!(
+            (report.total.luts, report.total.dffs, report.total.brams),
+            (15, 16, 2)
+        );
+        assert!(UtilizationReport::from_stat_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_timing_report() {
+        let report = r#"{
+            "utilization": {
+                "ICESTORM_LC": { "available": 7680, "used": 212 }
+            },
+            "fmax": {
+                "clock$SB_IO_IN_$glb_clk": { "achieved": 131.42, "constraint": 100.0 },
+                "pll_clock_out_$glb_clk": { "achieved": 187.5, "constraint": 200.0 }
+            }
+        }"#;
+        let timing = TimingReport::from_nextpnr_json(report).unwrap();
+        assert!(timing.clock("clock").unwrap().met());
+        assert!(!timing.clock("pll_clock_out").unwrap().met());
+        assert!(timing.clock("clk").is_none());
+        assert_eq!(timing.failing(), vec!["pll_clock_out_$glb_clk"]);
+        assert!(TimingReport::from_nextpnr_json("{}").is_err());
+    }
+}
@@ -13,5 +13,5 @@ fn test_pll_synthesizable() {
     let vlog = generate_verilog(&uut);
     yosys_validate("vlog", &vlog).unwrap();
     println!("{}", vlog);
-    rust_hdl_alchitry_cu::synth::generate_bitstream(uut, "pll_cu");
+    rust_hdl_alchitry_cu::synth::generate_bitstream(uut, "pll_cu").unwrap();
 }
@@ -1,6 +1,6 @@
 use crate::pulser::Pulser;
 use rust_hdl_alchitry_cu::pins::Mhz100;
-use rust_hdl_alchitry_cu::synth::generate_bitstream;
+use rust_hdl_alchitry_cu::synth::{generate_bitstream, BuildError};
 use rust_hdl_core::prelude::*;
 use std::time::Duration;
 
@@ -37,5 +37,41 @@ impl Default for AlchitryCuPulser {
 #[test]
 fn synthesize_alchitry_cu_pulser() {
     let uut = AlchitryCuPulser::default();
-    generate_bitstream(uut, "pulser");
+    generate_bitstream(uut, "pulser").unwrap();
+}
+
+#[derive(LogicBlock)]
+struct Unlit {
+    clock: Signal<In, Clock, Mhz100>,
+    leds: Signal<Out, Bits<8>, Async>,
+}
+
+impl Logic for Unlit {
+    #[hdl_gen]
+    fn update(&mut self) {}
+}
+
+#[test]
+fn test_bitstream_errors_and_caching() {
+    let uut = Unlit {
+        clock: rust_hdl_alchitry_cu::pins::clock(),
+        leds: rust_hdl_alchitry_cu::pins::leds(),
+    };
+    match generate_bitstream(uut, "unlit") {
+        Err(BuildError::DesignErrors(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].path, "uut::leds");
+        }
+        x => panic!("Expected the undriven LEDs to be reported, got {:?}", x),
+    }
+    let build = || generate_bitstream(AlchitryCuPulser::default(), "pulser_cached");
+    match build() {
+        Err(BuildError::MissingTool(tool)) => println!("{} not found, skipping", tool),
+        x => {
+            assert!(!x.unwrap().cached);
+            let artifacts = build().unwrap();
+            assert!(artifacts.cached);
+            assert!(artifacts.bitstream.exists());
+        }
+    }
 }
@@ -43,5 +43,5 @@ impl Default for AlchitryCuPulserPLL {
 #[test]
 fn synthesize_alchitry_cu_pulser_with_pll() {
     let uut = AlchitryCuPulserPLL::default();
-    generate_bitstream(uut, "pulser_pll");
+    generate_bitstream(uut, "pulser_pll").unwrap();
 }
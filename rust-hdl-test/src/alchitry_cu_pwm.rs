@@ -1,6 +1,5 @@
 use crate::snore::snore;
 use rust_hdl_alchitry_cu::pins::Mhz100;
-use rust_hdl_core::check_connected::check_connected;
 use rust_hdl_core::prelude::*;
 use rust_hdl_synth::yosys_validate;
 use rust_hdl_widgets::prelude::*;
@@ -59,9 +58,9 @@ impl<const P: usize> Default for AlchitryCuPWM<Mhz100, P> {
 fn test_pwm_synthesizes() {
     let mut uut: AlchitryCuPWM<Mhz100, 6> = AlchitryCuPWM::default();
     uut.connect_all();
-    check_connected(&uut);
+    assert_eq!(lint_errors(&uut), vec![]);
     let vlog = generate_verilog(&uut);
     println!("{}", vlog);
     yosys_validate("pwm_cu", &vlog).unwrap();
-    rust_hdl_alchitry_cu::synth::generate_bitstream(uut, "pwm_cu");
+    rust_hdl_alchitry_cu::synth::generate_bitstream(uut, "pwm_cu").unwrap();
 }
@@ -1,8 +1,7 @@
 use std::collections::BTreeMap;
 
-use rust_hdl_core::check_connected::check_connected;
 use rust_hdl_core::prelude::*;
-use rust_hdl_synth::yosys_validate;
+use rust_hdl_synth::{yosys_utilization, yosys_validate, SynthError};
 use rust_hdl_widgets::prelude::*;
 
 use crate::snore;
@@ -104,9 +103,28 @@ impl<const P: usize> Default for AlchitryCuPWMVec<Mhz100, P> {
 fn test_pwm_vec_synthesizes() {
     let mut uut: AlchitryCuPWMVec<Mhz100, 6> = AlchitryCuPWMVec::default();
     uut.connect_all();
-    check_connected(&uut);
+    assert_eq!(lint_errors(&uut), vec![]);
     let vlog = generate_verilog(&uut);
     println!("{}", vlog);
     yosys_validate("pwm_cu", &vlog).unwrap();
-    rust_hdl_alchitry_cu::synth::generate_bitstream(uut, "pwm_cu");
+    rust_hdl_alchitry_cu::synth::generate_bitstream(uut, "pwm_cu").unwrap();
+}
+
+#[test]
+fn test_pwm_vec_utilization() {
+    let mut uut: AlchitryCuPWMVec<Mhz100, 6> = AlchitryCuPWMVec::default();
+    uut.connect_all();
+    let report = match yosys_utilization("pwm_vec_utilization", &generate_verilog(&uut)) {
+        Err(SynthError::IOError(_)) => {
+            println!("yosys not found, skipping");
+            return;
+        }
+        x => x.unwrap(),
+    };
+    println!("{}", report);
+    // The faders share their PWM module
+    assert!(report.module("top_faders_0_pwm").unwrap().luts < 200);
+    assert_eq!(report.total.brams, 0);
+    assert_eq!(report.total.plls, 0);
+    assert!(report.total.dffs > 0);
 }
@@ -1,6 +1,5 @@
 use std::collections::BTreeMap;
 
-use rust_hdl_core::check_connected::check_connected;
 use rust_hdl_core::prelude::*;
 use rust_hdl_synth::yosys_validate;
 use rust_hdl_widgets::prelude::*;
@@ -112,8 +111,8 @@ impl<const P: usize> Default for AlchitryCuPWMVecSyncROM<P> {
 fn test_pwm_vec_sync_rom_synthesizes() {
     let mut uut: AlchitryCuPWMVecSyncROM<6> = AlchitryCuPWMVecSyncROM::default();
     uut.connect_all();
-    check_connected(&uut);
+    assert_eq!(lint_errors(&uut), vec![]);
     let vlog = generate_verilog(&uut);
     yosys_validate("pwm_cu_srom", &vlog).unwrap();
-    rust_hdl_alchitry_cu::synth::generate_bitstream(uut, "pwm_cu_srom");
+    rust_hdl_alchitry_cu::synth::generate_bitstream(uut, "pwm_cu_srom").unwrap();
 }
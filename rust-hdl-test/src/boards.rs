@@ -0,0 +1,402 @@
+use crate::pulser::Pulser;
+use rust_hdl_alchitry_cu::pins::AlchitryCu;
+use rust_hdl_boards::bitstream::{generate_bitstream, BuildError};
+use rust_hdl_boards::board::{signal_at, Board, Toolchain};
+use rust_hdl_boards::icebreaker::IceBreaker;
+use rust_hdl_boards::icestick::IceStick;
+use rust_hdl_boards::lpf_gen::generate_lpf;
+use rust_hdl_boards::pcf_gen::{generate_pcf, PinError};
+use rust_hdl_boards::pin_db::{chipdb_path, PinDatabase};
+use rust_hdl_boards::tinyfpga_bx::TinyFpgaBx;
+use rust_hdl_boards::ulx3s::Ulx3s;
+use rust_hdl_boards::{icebreaker, icestick, tinyfpga_bx, ulx3s};
+use rust_hdl_core::prelude::*;
+use std::time::Duration;
+
+#[derive(LogicBlock)]
+struct Blinky<F: Domain, const N: usize> {
+    pulser: Pulser<F>,
+    clock: Signal<In, Clock, F>,
+    leds: Signal<Out, Bits<N>, Async>,
+}
+
+impl<F: Domain, const N: usize> Logic for Blinky<F, N> {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.pulser.enable.next = true.into();
+        self.pulser.clock.next = self.clock.val();
+        self.leds.next = 0_u32.into();
+        if self.pulser.pulse.val().raw() {
+            self.leds.next = 1_u32.into();
+        }
+    }
+}
+
+impl<F: Domain, const N: usize> Blinky<F, N> {
+    fn new(clock: Signal<In, Clock, F>, leds: Signal<Out, Bits<N>, Async>) -> Self {
+        Self {
+            pulser: Pulser::new(1.0, Duration::from_millis(250)),
+            clock,
+            leds,
+        }
+    }
+}
+
+#[test]
+fn test_board_descriptions() {
+    assert_eq!(AlchitryCu::DEVICE, "hx8k");
+    assert_eq!(AlchitryCu::clock_pins(), vec![("P7", 100_000_000)]);
+    assert_eq!(IceBreaker::PACKAGE, "sg48");
+    assert_eq!(Ulx3s::TOOLCHAIN, Toolchain::Ecp5);
+    assert_eq!(
+        Ulx3s::TOOLCHAIN.pnr_args(Ulx3s::DEVICE, Ulx3s::PACKAGE)[0..3],
+        ["--85k", "--package", "CABGA381"]
+    );
+    assert_eq!(
+        TinyFpgaBx::TOOLCHAIN.pnr_args(TinyFpgaBx::DEVICE, TinyFpgaBx::PACKAGE)[0],
+        "--lp8k"
+    );
+    assert_eq!(IceStick::led_pins().len(), 5);
+}
+
+// Made up sites, in the format of icebox's chipdb files
+const CHIPDB_1K: &str = "
+.device 1k
+.io_tile 0 1
+.io_tile 13 8
+.io_tile 6 0
+.io_tile 6 17
+.pins tq144
+21 0 8 0
+95 13 8 1
+96 13 8 0
+97 6 17 1
+98 6 17 0
+99 0 1 0
+.gbufpin
+0 8 0 5
+";
+
+const CHIPDB_8K: &str = "
+.device 8k
+.io_tile 0 4
+.io_tile 0 5
+.io_tile 16 0
+.io_tile 17 0
+.io_tile 33 16
+.io_tile 20 33
+.pins CB132
+L1 0 4 0
+M1 0 4 1
+N1 0 5 0
+P1 0 5 1
+P7 16 0 0
+P8 16 0 1
+L14 17 0 0
+J11 33 16 0
+K11 20 33 0
+.pins CT256
+R9 16 0 0
+.gbufpin
+16 0 0 4
+.extra_cell 16 0 PLL
+PLLOUT_A 16 0 1
+PLLOUT_B 17 0 0
+";
+
+#[test]
+fn test_pin_database() {
+    let pins = PinDatabase::from_chipdb(CHIPDB_8K, "cb132").unwrap();
+    assert_eq!(pins.pins().count(), 9);
+    assert!(pins.pin("R9").is_none());
+    let clock = pins.pin("P7").unwrap();
+    assert_eq!(
+        (clock.bank, clock.global_buffer, clock.pll),
+        (2, Some(4), false)
+    );
+    assert!(pins.pin("P8").unwrap().pll);
+    assert!(pins.pin("L14").unwrap().pll);
+    assert_eq!(pins.pin("J11").unwrap().bank, 1);
+    assert_eq!(pins.pin("K11").unwrap().bank, 0);
+    assert_eq!(pins.pin("L1").unwrap().bank, 3);
+    assert!(PinDatabase::from_chipdb(CHIPDB_8K, "tq144").is_err());
+    assert!(PinDatabase::from_chipdb(".pins CB132\nP7 16\n", "cb132").is_err());
+}
+
+#[test]
+fn test_builtin_pin_databases() {
+    let pins = PinDatabase::builtin("tq144").unwrap();
+    assert_eq!(pins.pins().count(), 96);
+    assert!(pins.pin("21").unwrap().global_buffer.is_some());
+    assert!(pins.pin("5").is_none());
+    let pins = PinDatabase::builtin("sg48").unwrap();
+    assert_eq!(pins.pins().count(), 39);
+    for pin in ["35", "11", "37", "10"] {
+        assert!(pins.pin(pin).is_some());
+    }
+    let pins = PinDatabase::builtin("cm81").unwrap();
+    assert!(pins.pin("B2").is_some() && pins.pin("B3").is_some());
+    let pins = PinDatabase::builtin("CB132").unwrap();
+    for pin in AlchitryCu::led_pins()
+        .into_iter()
+        .chain(AlchitryCu::button_pins())
+        .chain(AlchitryCu::clock_pins().into_iter().map(|x| x.0))
+    {
+        assert!(pins.pin(pin).is_some());
+    }
+    assert!(!pins.complete);
+    assert!(PinDatabase::builtin("ct256").is_none());
+    let table = PinDatabase::from_chipdb(CHIPDB_1K, "tq144")
+        .unwrap()
+        .table_source("TQ144", "iCE40HX1K");
+    assert!(table.starts_with("// iCE40HX1K\npub const TQ144: PinTable = PinTable {\n"));
+    assert!(table.contains("    complete: true,\n"));
+    assert!(
+        table.contains("        (\"21\", 3, Some(5), false),\n        (\"95\", 1, None, false),\n")
+    );
+}
+
+const PIN_TABLES: [(&str, &str, &str, &str); 4] = [
+    ("hx1k", "tq144", "TQ144", "iCE40HX1K, as on the iCEstick"),
+    ("up5k", "sg48", "SG48", "iCE40UP5K, as on the iCEBreaker"),
+    ("lp8k", "cm81", "CM81", "iCE40LP8K, as on the TinyFPGA BX"),
+    ("hx8k", "cb132", "CB132", "iCE40HX8K, as on the Alchitry Cu"),
+];
+
+#[test]
+fn test_installed_pin_databases() {
+    for (device, package, _, _) in PIN_TABLES {
+        match chipdb_path(device) {
+            None => println!("icebox chipdb for {} not found, skipping", device),
+            Some(path) => {
+                let chipdb = std::fs::read_to_string(path).unwrap();
+                let pins = PinDatabase::from_chipdb(&chipdb, package).unwrap();
+                let builtin = PinDatabase::builtin(package).unwrap();
+                for pin in builtin.pins() {
+                    assert_eq!(Some(pin), pins.pin(&pin.name), "{} {}", package, pin.name);
+                }
+                if builtin.complete {
+                    assert_eq!(builtin.pins().count(), pins.pins().count());
+                }
+            }
+        }
+    }
+}
+
+// Writes the built-in pin tables from the installed icebox chipdbs, with
+//   cargo test -p rust_hdl_test regenerate_pin_tables -- --ignored
+#[test]
+#[ignore]
+fn regenerate_pin_tables() {
+    let mut code = "\
+// The I/O pins of the packages that the boards here use, so that pin assignments
+// can be checked without icebox.  Generated from the icebox chipdbs by
+// regenerate_pin_tables in rust-hdl-test.
+use crate::pin_db::PinTable;
+"
+    .to_string();
+    for (device, package, name, comment) in PIN_TABLES {
+        let path = chipdb_path(device).expect("icebox chipdb not found");
+        let chipdb = std::fs::read_to_string(path).unwrap();
+        let pins = PinDatabase::from_chipdb(&chipdb, package).unwrap();
+        code += "\n";
+        code += &pins.table_source(name, comment);
+    }
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../rust-hdl-boards/src/pin_tables.rs"
+    );
+    std::fs::write(path, code).unwrap();
+}
+
+#[test]
+fn test_board_constraint_files() {
+    let mut uut = Blinky::new(icestick::clock(), icestick::leds());
+    uut.connect_all();
+    let pins = PinDatabase::from_chipdb(CHIPDB_1K, "tq144").unwrap();
+    let pcf = generate_pcf(&uut, &pins).unwrap();
+    assert!(pcf.contains("set_io clock 21\n"));
+    assert!(pcf.contains("set_io leds[4] 95\n"));
+    let mut uut = Blinky::new(ulx3s::clock(), ulx3s::leds());
+    uut.connect_all();
+    let lpf = generate_lpf(&uut);
+    println!("{}", lpf);
+    assert!(lpf.contains("LOCATE COMP \"clock\" SITE \"G2\";\n"));
+    assert!(lpf.contains("LOCATE COMP \"leds[7]\" SITE \"H3\";\n"));
+    assert!(lpf.contains("IOBUF PORT \"leds[7]\" IO_TYPE=LVCMOS33;\n"));
+}
+
+fn build<B: Board, U: Block>(uut: U, prefix: &str) {
+    match generate_bitstream::<B, U>(uut, prefix) {
+        Err(BuildError::MissingTool(tool)) => println!("{} not found, skipping", tool),
+        x => assert!(x.unwrap().bitstream.exists()),
+    }
+}
+
+#[test]
+fn synthesize_board_blinkies() {
+    build::<IceStick, _>(
+        Blinky::new(icestick::clock(), icestick::leds()),
+        "blinky_icestick",
+    );
+    build::<IceBreaker, _>(
+        Blinky::new(icebreaker::clock(), icebreaker::leds_n()),
+        "blinky_icebreaker",
+    );
+    build::<TinyFpgaBx, _>(
+        Blinky::new(
+            tinyfpga_bx::clock(),
+            signal_at::<Out, Bits<1>, Async>(&TinyFpgaBx::led_pins()),
+        ),
+        "blinky_tinyfpga_bx",
+    );
+    build::<Ulx3s, _>(Blinky::new(ulx3s::clock(), ulx3s::leds()), "blinky_ulx3s");
+}
+
+#[derive(LogicBlock)]
+struct CuHeaders {
+    reset: Signal<In, Bit, Async>,
+    switches: Signal<In, Bits<2>, Async>,
+    lamps: Signal<Out, Bits<2>, Async>,
+}
+
+impl Logic for CuHeaders {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.lamps.next = self.switches.val();
+        if !self.reset.val().raw() {
+            self.lamps.next = 0_u32.into();
+        }
+    }
+}
+
+#[test]
+fn test_alchitry_cu_peripherals() {
+    use rust_hdl_alchitry_cu::pins::{header_input, header_output, reset_button};
+    let mut uut = CuHeaders {
+        reset: reset_button(),
+        switches: header_input(&["A2", "A3"]),
+        lamps: header_output(&["A48", "A49"]),
+    };
+    uut.connect_all();
+    assert!(lint_errors(&uut).is_empty());
+    let pins = PinDatabase::from_chipdb(CHIPDB_8K, "cb132").unwrap();
+    let pcf = generate_pcf(&uut, &pins).unwrap();
+    assert!(pcf.contains("set_io reset P8\n"));
+    assert!(pcf.contains("set_io switches[1] L1\n"));
+    assert!(pcf.contains("set_io lamps[0] N1\n"));
+}
+
+#[derive(LogicBlock)]
+struct IoShieldDemo {
+    leds: Signal<Out, Bits<24>, Async>,
+    buttons: Signal<In, Bits<5>, Async>,
+    dip_switches: Signal<In, Bits<24>, Async>,
+    segments: Signal<Out, Bits<8>, Async>,
+    digit_selects: Signal<Out, Bits<4>, Async>,
+}
+
+impl Logic for IoShieldDemo {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.leds.next = self.dip_switches.val();
+        self.segments.next = 0xFF_u32.into();
+        self.digit_selects.next = 0xF_u32.into();
+        if self.buttons.val().any() {
+            self.segments.next = 0_u32.into();
+            self.digit_selects.next = 0xE_u32.into();
+        }
+    }
+}
+
+#[test]
+fn test_alchitry_cu_io_shield() {
+    use rust_hdl_alchitry_cu::io_shield;
+    use rust_hdl_alchitry_cu::pins::map_alchitry_pin_to_cu_pad;
+    let mut uut = IoShieldDemo {
+        leds: io_shield::leds(),
+        buttons: io_shield::buttons(),
+        dip_switches: io_shield::dip_switches(),
+        segments: io_shield::segments(),
+        digit_selects: io_shield::digit_selects(),
+    };
+    uut.connect_all();
+    assert!(lint_errors(&uut).is_empty());
+    let mut pads = vec![];
+    for signal in [
+        &uut.leds as &dyn Atom,
+        &uut.buttons,
+        &uut.dip_switches,
+        &uut.segments,
+        &uut.digit_selects,
+    ] {
+        assert_eq!(signal.constraints().len(), signal.bits());
+        for pin in signal.constraints() {
+            if let Constraint::Location(pad) = pin.constraint {
+                pads.push(pad);
+            }
+        }
+    }
+    assert_eq!(pads.len(), 65);
+    pads.sort();
+    pads.dedup();
+    assert_eq!(pads.len(), 65);
+    let pins = PinDatabase::builtin(AlchitryCu::PACKAGE).unwrap();
+    assert!(pads.iter().all(|x| pins.pin(x).is_some()));
+    let pcf = generate_pcf(&uut, &pins).unwrap();
+    assert!(pcf.contains(&format!(
+        "set_io leds[0] {}\n",
+        map_alchitry_pin_to_cu_pad("B21")
+    )));
+    assert!(pads.contains(&map_alchitry_pin_to_cu_pad("C12").to_string()));
+    assert!(!pads.contains(&"P7".to_string()));
+}
+
+#[test]
+#[should_panic(expected = "Unknown pin A4")]
+fn test_alchitry_cu_unknown_header_pin() {
+    let _: Signal<Out, Bit, Async> = rust_hdl_alchitry_cu::pins::header_output(&["A4"]);
+}
+
+#[test]
+fn test_pcf_pin_errors() {
+    let mut uut = CuHeaders {
+        reset: signal_at(&["P8"]),
+        switches: signal_at(&["A2", "M1"]),
+        lamps: signal_at(&["P8", "N1"]),
+    };
+    uut.reset.connect();
+    uut.switches.connect();
+    uut.connect_all();
+    let pins = PinDatabase::from_chipdb(CHIPDB_8K, "cb132").unwrap();
+    let errors = generate_pcf(&uut, &pins).unwrap_err();
+    for error in &errors {
+        println!("{}", error);
+    }
+    assert_eq!(
+        errors,
+        vec![
+            PinError::Illegal {
+                port: "switches[0]".into(),
+                pin: "A2".into()
+            },
+            PinError::Duplicate {
+                pin: "P8".into(),
+                ports: vec!["reset".into(), "lamps[0]".into()]
+            },
+        ]
+    );
+    // The built-in table only lists the balls the Cu uses, so it cannot rule any out
+    let mut uut = CuHeaders {
+        reset: signal_at(&["B14"]),
+        switches: signal_at(&["A2", "M1"]),
+        lamps: signal_at(&["P8", "N1"]),
+    };
+    uut.reset.connect();
+    uut.switches.connect();
+    uut.connect_all();
+    let pins = PinDatabase::builtin("cb132").unwrap();
+    assert!(generate_pcf(&uut, &pins)
+        .unwrap()
+        .contains("set_io reset B14\n"));
+}
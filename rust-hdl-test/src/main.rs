@@ -11,6 +11,8 @@ mod alchitry_cu_pwm;
 mod alchitry_cu_pwm_vec;
 mod alchitry_cu_pwm_vec_srom;
 mod base_tests;
+#[cfg(test)]
+mod boards;
 mod fifo;
 mod nested_ports;
 mod pulser;
@@ -40,7 +42,7 @@ fn test_strobe_as_verilog() {
     uut.enable.connect();
     uut.clock.connect();
     uut.connect_all();
-    check_connected(&uut);
+    assert_eq!(lint_errors(&uut), vec![]);
     println!("{}", generate_verilog(&uut));
     let vlog = generate_verilog(&uut);
     yosys_validate("strobe", &vlog).unwrap();
@@ -63,6 +63,11 @@ fn test_write_modules_nested_ports() {
         fn update(&mut self) {
             self.widget_a.clock.next = self.clock.val();
             self.widget_b.clock.next = self.clock.val();
+            // Latch prevention
+            self.widget_a.bus.cmd.read.next = false.into();
+            self.widget_a.bus.data.read.next = false.into();
+            self.widget_b.bus.cmd.read.next = false.into();
+            self.widget_b.bus.data.read.next = false.into();
 
             if self.select.val().raw() {
                 self.bus.cmd.underflow.next = self.widget_a.bus.cmd.underflow.val();
@@ -98,7 +103,7 @@ fn test_write_modules_nested_ports() {
     uut.bus.data.read.connect();
     uut.select.connect();
     uut.connect_all();
-    check_connected(&uut);
+    assert_eq!(lint_errors(&uut), vec![]);
     let mut defines = ModuleDefines::default();
     uut.accept("uut", &mut defines);
     defines.defines();
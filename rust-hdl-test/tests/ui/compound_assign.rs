@@ -0,0 +1,19 @@
+use rust_hdl_core::prelude::*;
+
+#[derive(Clone, Debug, Default, LogicBlock)]
+struct Widget {
+    pub a: Signal<In, Bits<8>, Async>,
+    pub y: Signal<Out, Bits<8>, Async>,
+    pub flag: Signal<Out, Bit, Async>,
+}
+
+impl Logic for Widget {
+    #[hdl_gen]
+    fn update(&mut self) {
+        let mut any = false;
+        any |= self.a.val().any();
+        self.flag.next = any.into();
+    }
+}
+
+fn main() {}
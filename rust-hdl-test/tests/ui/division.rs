@@ -0,0 +1,22 @@
+use rust_hdl_core::prelude::*;
+
+#[derive(Clone, Debug, Default, LogicBlock)]
+struct Widget {
+    pub a: Signal<In, Bits<8>, Async>,
+    pub y: Signal<Out, Bits<8>, Async>,
+    pub flag: Signal<Out, Bit, Async>,
+}
+
+impl Logic for Widget {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.flag.next = false.into();
+        for i in 0_usize..8_usize {
+            if self.a.val().raw().get_bit(i / 2_usize) {
+                self.flag.next = true.into();
+            }
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use rust_hdl_core::prelude::*;
+
+#[hdl_function]
+fn widen<const N: usize>(x: Bits<N>) -> Bits<16> {
+    bit_cast::<16, N>(x)
+}
+
+fn main() {}
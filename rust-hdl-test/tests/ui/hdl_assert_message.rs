@@ -0,0 +1,19 @@
+use rust_hdl_core::prelude::*;
+
+const MESSAGE: &str = "a is zero";
+
+#[derive(Clone, Debug, Default, LogicBlock)]
+struct Widget {
+    pub a: Signal<In, Bits<8>, Async>,
+    pub y: Signal<Out, Bits<8>, Async>,
+}
+
+impl Logic for Widget {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.y.next = self.a.val();
+        hdl_assert!(self.a.val() != 0_u32, MESSAGE);
+    }
+}
+
+fn main() {}